@@ -28,21 +28,41 @@
 ///
 /// ```
 use clap::Parser;
+use flate2::read::MultiGzDecoder;
 
 use graph::data::graphql::ext::DirectiveFinder;
 use graph::data::graphql::DirectiveExt;
 use graph::data::graphql::DocumentExt;
-use graph::data::subgraph::SPEC_VERSION_1_1_0;
-use graph::prelude::s;
+use graph::data::store::IdType;
+use graph::data::subgraph::{
+    parse_undeployed_manifest, BaseSubgraphManifest, ManifestLink, SchemaFeature, SPEC_VERSIONS,
+};
+use graph::ipfs_client::IpfsClient;
 use graph::prelude::DeploymentHash;
-use graph::schema::InputSchema;
+use graph::prelude::{q, s};
+use graph::schema::{
+    ApiSchema, Deprecation, EntityType, InputSchema, RefKind, SchemaValidationError, SchemaWarning,
+    Severity, TypeKind, ValidationOptions,
+};
+use graph::semver::Version;
 use graphql_parser::parse_schema;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::de::IgnoredAny;
 use serde::Deserialize;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::BufRead;
 use std::io::BufReader;
+use std::io::Read;
+use std::io::{self, BufWriter, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
 use std::process::exit;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 pub fn usage(msg: &str) -> ! {
     println!("{}", msg);
@@ -72,15 +92,1294 @@ fn subgraph_id(schema: &s::Document) -> DeploymentHash {
             _ => None,
         })
         .unwrap_or("unknown".to_string());
-    DeploymentHash::new(id).expect("subgraph id is not a valid deployment hash")
+    DeploymentHash::from_ipfs_link(&id)
+        .or_else(|_| DeploymentHash::new(id))
+        .expect("subgraph id is not a valid deployment hash")
 }
 
-#[derive(Deserialize)]
+fn check_spec_version(version: &Version, known: &[Version]) -> Result<(), String> {
+    if known.contains(version) {
+        Ok(())
+    } else {
+        let known: Vec<_> = known.iter().map(|v| v.to_string()).collect();
+        Err(format!(
+            "unknown spec version {}, must be one of {}",
+            version,
+            known.join(", ")
+        ))
+    }
+}
+
+/// Load the spec versions and the features they support from the file
+/// given with `--spec-versions-file`. The file must contain a JSON object
+/// that maps versions to lists of features, e.g.,
+/// `{ "1.2.0": ["fullTextSearch", "immutableEntities", "int8Id"] }`
+fn load_spec_versions(path: &str) -> BTreeMap<Version, BTreeSet<SchemaFeature>> {
+    let msg = format!("failed to load spec versions from {}", path);
+    let raw = ensure(read_to_string(path, false), &msg);
+    let versions: BTreeMap<String, Vec<String>> = ensure(serde_json::from_str(&raw), &msg);
+    versions
+        .into_iter()
+        .map(|(version, features)| {
+            let version = ensure(Version::parse(&version), &msg);
+            let features = features
+                .iter()
+                .map(|feature| ensure(feature.parse::<SchemaFeature>(), &msg))
+                .collect();
+            (version, features)
+        })
+        .collect()
+}
+
+/// One line of a batch file
 struct Entry {
     id: i32,
     schema: String,
 }
 
+impl Entry {
+    /// Parse a line of a batch file, taking the id and the schema from the
+    /// keys given by `--id-field` and `--schema-field`
+    fn parse(line: &str, opt: &Opts) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        let id = value
+            .get(&opt.id_field)
+            .and_then(|id| id.as_i64())
+            .and_then(|id| i32::try_from(id).ok())
+            .ok_or_else(|| format!("`{}` is missing or not a 32-bit integer", opt.id_field))?;
+        let schema = value
+            .get(&opt.schema_field)
+            .and_then(|schema| schema.as_str())
+            .ok_or_else(|| format!("`{}` is missing or not a string", opt.schema_field))?;
+        Ok(Entry {
+            id,
+            schema: schema.to_string(),
+        })
+    }
+}
+
+/// How the result of validating each schema is printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    /// Human-readable lines like `Schema sgd123[Qm..]: OK`
+    Text,
+    /// One JSON object per schema, i.e., JSONL
+    Json,
+    /// A header row followed by one row per schema
+    Csv,
+    /// A JUnit XML `<testsuite>` with one `<testcase>` per schema
+    Junit,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "junit" => Ok(Format::Junit),
+            _ => Err(format!(
+                "invalid format `{}`, must be one of text, json, csv, junit",
+                s
+            )),
+        }
+    }
+}
+
+/// Which checks to run unless they are turned on or off explicitly
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Profile {
+    /// Only validate the input schema
+    Input,
+    /// Validate the input schema and generate the API schema
+    Full,
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "input" => Ok(Profile::Input),
+            "full" => Ok(Profile::Full),
+            _ => Err(format!(
+                "invalid profile `{}`, must be one of input, full",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Ok,
+    ParseError,
+    InputSchemaError,
+    ApiSchemaError,
+    /// The subgraph manifest is invalid, or its schema can't be found
+    ManifestError,
+    /// The schema could not be fetched from an `http(s)://` or `ipfs://`
+    /// URL
+    FetchError,
+    /// The schema is valid, but can not be grafted onto `--graft-base`
+    GraftError,
+    /// The schema is valid, but uses features that mappings with the API
+    /// version from `--api-version` can not write, and `--strict` is set
+    ApiVersionError,
+    /// The schema is valid, but the query from `--query` is not valid
+    /// against its API schema
+    QueryError,
+    /// The schema is valid, but has fulltext searches in languages that
+    /// are not among the text search configurations from `--collations`
+    CollationError,
+    /// The schema is valid, but has more types or fields than
+    /// `--max-types`, `--max-fields`, or `--max-api-fields` allow
+    TooLarge,
+    /// Validation panicked
+    Panic,
+}
+
+impl Status {
+    const ALL: [Status; 12] = [
+        Status::Ok,
+        Status::ParseError,
+        Status::InputSchemaError,
+        Status::ApiSchemaError,
+        Status::ManifestError,
+        Status::FetchError,
+        Status::GraftError,
+        Status::ApiVersionError,
+        Status::QueryError,
+        Status::CollationError,
+        Status::TooLarge,
+        Status::Panic,
+    ];
+
+    /// The name of the status, the same as in JSON output
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::ParseError => "parse_error",
+            Status::InputSchemaError => "input_schema_error",
+            Status::ApiSchemaError => "api_schema_error",
+            Status::ManifestError => "manifest_error",
+            Status::FetchError => "fetch_error",
+            Status::GraftError => "graft_error",
+            Status::ApiVersionError => "api_version_error",
+            Status::QueryError => "query_error",
+            Status::CollationError => "collation_error",
+            Status::TooLarge => "too_large",
+            Status::Panic => "panic",
+        }
+    }
+}
+
+/// What `--expect` says the outcome for a schema should be
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Expectation {
+    /// The schema fails validation, no matter how
+    Fail,
+    /// The schema has exactly this status
+    Status(Status),
+}
+
+impl Expectation {
+    fn matches(&self, status: Status) -> bool {
+        match self {
+            Expectation::Fail => status != Status::Ok,
+            Expectation::Status(expected) => *expected == status,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Expectation::Fail => "fail",
+            Expectation::Status(status) => status.as_str(),
+        }
+    }
+}
+
+impl FromStr for Expectation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "fail" {
+            return Ok(Expectation::Fail);
+        }
+        Status::ALL
+            .iter()
+            .find(|status| status.as_str() == s)
+            .map(|status| Expectation::Status(*status))
+            .ok_or_else(|| {
+                let known: Vec<_> = Status::ALL.iter().map(|status| status.as_str()).collect();
+                format!(
+                    "invalid expectation `{}`, must be `fail` or one of {}",
+                    s,
+                    known.join(", ")
+                )
+            })
+    }
+}
+
+/// Compares outcomes against the expectations from `--expect`
+struct Expectations {
+    expected: BTreeMap<String, Expectation>,
+    /// The names of the schemas we have seen an outcome for
+    seen: BTreeSet<String>,
+    /// A description of each outcome that differed from its expectation
+    mismatches: Vec<String>,
+}
+
+impl Expectations {
+    /// Read expectations from `path`. Each line has the name of a schema
+    /// as it is reported, followed by whitespace and the expected status.
+    /// Blank lines and lines starting with `#` are skipped
+    fn load(path: &str) -> Self {
+        let raw = ensure(
+            fs::read_to_string(path),
+            &format!("failed to read expectations {}", path),
+        );
+        let mut expected = BTreeMap::new();
+        for (idx, line) in raw.lines().enumerate() {
+            if is_comment(line) {
+                continue;
+            }
+            let (name, expectation) = match line.trim().rsplit_once(char::is_whitespace) {
+                Some((name, expectation)) => (name.trim_end(), expectation),
+                None => usage(&format!(
+                    "{}:{}: expected a schema name followed by a status",
+                    path,
+                    idx + 1
+                )),
+            };
+            let expectation = ensure(
+                expectation.parse::<Expectation>(),
+                &format!("{}:{}: invalid expectation", path, idx + 1),
+            );
+            expected.insert(name.to_string(), expectation);
+        }
+        Expectations {
+            expected,
+            seen: BTreeSet::new(),
+            mismatches: Vec::new(),
+        }
+    }
+
+    fn check(&mut self, outcome: &Outcome) {
+        self.seen.insert(outcome.name.clone());
+        match self.expected.get(&outcome.name) {
+            Some(expected) if expected.matches(outcome.status) => { /* as expected */ }
+            Some(expected) => self.mismatches.push(format!(
+                "{}: expected {} but got {}",
+                outcome.name,
+                expected.as_str(),
+                outcome.status.as_str()
+            )),
+            None => self.mismatches.push(format!(
+                "{}: got {} but there is no expectation for it",
+                outcome.name,
+                outcome.status.as_str()
+            )),
+        }
+    }
+
+    /// Record all the schemas for which we have an expectation, but that
+    /// we never saw an outcome for
+    fn finish(&mut self) {
+        for name in self.expected.keys() {
+            if !self.seen.contains(name) {
+                self.mismatches
+                    .push(format!("{}: expected but not validated", name));
+            }
+        }
+    }
+}
+
+/// The parts of an outcome from a previous run with `--format json` that
+/// `--baseline` compares against
+#[derive(Deserialize)]
+struct BaselineOutcome {
+    name: String,
+    status: String,
+    #[serde(default)]
+    errors: Vec<BaselineError>,
+}
+
+#[derive(Deserialize)]
+struct BaselineError {
+    code: String,
+}
+
+/// Compares outcomes against the outcomes of a previous run given with
+/// `--baseline`
+struct Baseline {
+    /// The status and the sorted error codes for each schema in the
+    /// previous run
+    previous: BTreeMap<String, (String, Vec<String>)>,
+    /// Schemas that were ok in the previous run, but fail now
+    regressed: Vec<String>,
+    /// Schemas that failed in the previous run, but are ok now
+    improved: Vec<String>,
+    /// Schemas that failed in both runs, but for different reasons
+    changed: Vec<String>,
+    /// The number of schemas that are not in the previous run
+    added: usize,
+}
+
+impl Baseline {
+    /// Read the JSONL output of a previous run from `path`. The summary
+    /// line and lines that are not outcomes are skipped
+    fn load(path: &str) -> Self {
+        let raw = ensure(
+            fs::read_to_string(path),
+            &format!("failed to read baseline {}", path),
+        );
+        let mut previous = BTreeMap::new();
+        for line in raw.lines().filter(|line| !is_comment(line)) {
+            if let Ok(outcome) = serde_json::from_str::<BaselineOutcome>(line) {
+                let mut codes: Vec<_> = outcome.errors.into_iter().map(|e| e.code).collect();
+                codes.sort();
+                previous.insert(outcome.name, (outcome.status, codes));
+            }
+        }
+        Baseline {
+            previous,
+            regressed: Vec::new(),
+            improved: Vec::new(),
+            changed: Vec::new(),
+            added: 0,
+        }
+    }
+
+    fn check(&mut self, outcome: &Outcome) {
+        let Some((status, codes)) = self.previous.get(&outcome.name) else {
+            self.added += 1;
+            return;
+        };
+        let was_ok = status == Status::Ok.as_str();
+        let mut now: Vec<_> = outcome.errors.iter().map(|e| e.code.to_string()).collect();
+        now.sort();
+        match (was_ok, outcome.is_ok()) {
+            (true, true) => { /* no change */ }
+            (true, false) => self.regressed.push(format!(
+                "{}: ok -> {} {}",
+                outcome.name,
+                outcome.status.as_str(),
+                now.join(";")
+            )),
+            (false, true) => self
+                .improved
+                .push(format!("{}: {} -> ok", outcome.name, status)),
+            (false, false) => {
+                if status != outcome.status.as_str() || codes != &now {
+                    self.changed.push(format!(
+                        "{}: {} {} -> {} {}",
+                        outcome.name,
+                        status,
+                        codes.join(";"),
+                        outcome.status.as_str(),
+                        now.join(";")
+                    ))
+                }
+            }
+        }
+    }
+
+    fn print(&self) {
+        eprintln!(
+            "Compared to baseline: {} regressed, {} improved, {} changed, {} not in baseline",
+            self.regressed.len(),
+            self.improved.len(),
+            self.changed.len(),
+            self.added
+        );
+        for (title, names) in [
+            ("Regressed", &self.regressed),
+            ("Improved", &self.improved),
+            ("Changed", &self.changed),
+        ] {
+            if !names.is_empty() {
+                eprintln!("{}:", title);
+                for name in names {
+                    eprintln!("    {}", name);
+                }
+            }
+        }
+    }
+}
+
+/// The result of validating one schema
+#[derive(Debug, Serialize)]
+struct Outcome {
+    /// The `id` of the entry in batch mode
+    id: Option<i32>,
+    name: String,
+    deployment: Option<String>,
+    status: Status,
+    message: Option<String>,
+    /// With `--all-spec-versions`, the spec versions under which the
+    /// schema is valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spec_versions: Option<Vec<String>>,
+    /// With `--dump-api`, the generated API schema in GraphQL SDL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_schema: Option<String>,
+    /// With `--dump-schema`, the resolved structure of the schema as
+    /// produced by `InputSchema::to_json`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<serde_json::Value>,
+    /// With `--details`, the lowest spec version that supports all the
+    /// features the schema uses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_spec_version: Option<String>,
+    /// With `--details`, a description of the fulltext searches and
+    /// aggregations that the schema declares
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    details: Vec<String>,
+    /// The individual validation errors for an `InputSchemaError`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<Diagnostic>,
+    /// The lints that a valid schema triggered
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<Diagnostic>,
+    /// The deprecated directives and arguments that a valid schema uses
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    deprecations: Vec<Diagnostic>,
+    /// With `--timing`, how long validation took in milliseconds
+    #[serde(rename = "elapsed_ms", skip_serializing_if = "Option::is_none")]
+    elapsed: Option<f64>,
+    /// With `--estimate`, the tables the schema would create, widest
+    /// first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tables: Vec<TableEstimate>,
+    /// With `--indexes`, the fields that the store will index, by table
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    indexes: Vec<TableIndex>,
+    /// With `--canonical`, the hex-encoded `InputSchema::schema_hash` of a
+    /// valid schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    /// With `--list-features`, the sorted feature tags of a valid schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    features: Option<Vec<String>>,
+    /// With `--diff-api`, how the API schema differs from the one of the
+    /// base schema
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    api_changes: Vec<ApiChange>,
+    /// With `--audit-inverses`, suggestions for derived fields for
+    /// references whose target has no field derived from them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    inverses: Vec<String>,
+    /// With `--fix`, whether the file was rewritten in canonical form
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    fixed: bool,
+}
+
+/// A query field, or a filter of a query field, that the API schema of a
+/// schema has but the one of the `--diff-api` base does not, or the other
+/// way around
+#[derive(Debug, PartialEq, Serialize)]
+struct ApiChange {
+    added: bool,
+    query_field: String,
+    /// The filter of `query_field`, or `None` if the change is about the
+    /// query field itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter: Option<String>,
+}
+
+/// The number of columns of the table that the store would create for an
+/// entity type
+#[derive(Debug, Serialize)]
+struct TableEstimate {
+    table: String,
+    columns: usize,
+    /// Whether the table has at least `WIDE_TABLE` columns
+    wide: bool,
+}
+
+/// A field, or fulltext search, that the store will create an index for
+#[derive(Debug, Serialize)]
+struct TableIndex {
+    table: String,
+    field: String,
+    method: &'static str,
+}
+
+/// Tables with at least this many columns are flagged by `--estimate`
+const WIDE_TABLE: usize = 100;
+
+/// The columns that every table has besides the ones for its fields:
+/// `vid`, and either `block_range` for mutable or `block$` for immutable
+/// entity types. Tables for offchain data sources also have a
+/// `causality_region` column, but we can't tell from the schema whether
+/// that is the case
+const SYSTEM_COLUMNS: usize = 2;
+
+/// A single validation error, with the position of the declaration it is
+/// about if we could determine that
+#[derive(Debug, Serialize)]
+struct Diagnostic {
+    line: Option<usize>,
+    column: Option<usize>,
+    code: &'static str,
+    /// The `Severity` of validation errors; warnings and deprecations
+    /// have `Severity::Lint`
+    severity: &'static str,
+    message: String,
+}
+
+impl Diagnostic {
+    fn new(error: &SchemaValidationError, document: &s::Document) -> Self {
+        let pos = error.position(document);
+        Diagnostic {
+            line: pos.map(|pos| pos.line),
+            column: pos.map(|pos| pos.column),
+            code: error.code(),
+            severity: error.severity().as_str(),
+            message: error.to_string(),
+        }
+    }
+
+    fn deprecation(deprecation: &Deprecation, document: &s::Document) -> Self {
+        let pos = deprecation.position(document);
+        Diagnostic {
+            line: pos.map(|pos| pos.line),
+            column: pos.map(|pos| pos.column),
+            code: deprecation.code(),
+            severity: Severity::Lint.as_str(),
+            message: deprecation.to_string(),
+        }
+    }
+
+    fn warning(warning: &SchemaWarning, document: &s::Document) -> Self {
+        let pos = warning.position(document);
+        Diagnostic {
+            line: pos.map(|pos| pos.line),
+            column: pos.map(|pos| pos.column),
+            code: warning.code(),
+            severity: Severity::Lint.as_str(),
+            message: warning.to_string(),
+        }
+    }
+
+    /// Print in the `file:line:col: [code] message` format that compilers
+    /// use
+    fn print(&self, name: &str, out: &mut dyn Write) -> io::Result<()> {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => writeln!(
+                out,
+                "{}:{}:{}: [{}] {}",
+                name, line, column, self.code, self.message
+            ),
+            _ => writeln!(out, "{}: [{}] {}", name, self.code, self.message),
+        }
+    }
+}
+
+impl Outcome {
+    fn new(name: &str, deployment: Option<&DeploymentHash>, status: Status) -> Self {
+        Outcome {
+            id: None,
+            name: name.to_string(),
+            deployment: deployment.map(|id| id.to_string()),
+            status,
+            message: None,
+            spec_versions: None,
+            api_schema: None,
+            schema: None,
+            min_spec_version: None,
+            details: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            deprecations: Vec::new(),
+            elapsed: None,
+            tables: Vec::new(),
+            indexes: Vec::new(),
+            hash: None,
+            features: None,
+            api_changes: Vec::new(),
+            inverses: Vec::new(),
+            fixed: false,
+        }
+    }
+
+    fn with_message(mut self, message: impl ToString) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    fn is_ok(&self) -> bool {
+        self.status == Status::Ok
+    }
+
+    fn print(&self, format: Format, out: &mut dyn Write) -> io::Result<()> {
+        match format {
+            Format::Text => {
+                let deployment = self.deployment.as_deref().unwrap_or("unknown");
+                let message = self.message.as_deref().unwrap_or("");
+                match self.status {
+                    Status::Ok => match &self.min_spec_version {
+                        Some(version) => writeln!(
+                            out,
+                            "Schema {}[{}]: OK (requires spec version {})",
+                            self.name, deployment, version
+                        )?,
+                        None => writeln!(out, "Schema {}[{}]: OK", self.name, deployment)?,
+                    },
+                    Status::ParseError => {
+                        writeln!(out, "Failed to parse schema {}: {}", self.name, message)?
+                    }
+                    Status::InputSchemaError if !self.errors.is_empty() => {
+                        writeln!(out, "InputSchema: {}[{}]:", self.name, deployment)?;
+                        for error in &self.errors {
+                            error.print(&self.name, out)?;
+                        }
+                    }
+                    Status::InputSchemaError => writeln!(
+                        out,
+                        "InputSchema: {}[{}]: {}",
+                        self.name, deployment, message
+                    )?,
+                    Status::ApiSchemaError => {
+                        writeln!(out, "ApiSchema: {}[{}]: {}", self.name, deployment, message)?
+                    }
+                    Status::ManifestError => writeln!(out, "Manifest: {}: {}", self.name, message)?,
+                    Status::FetchError => writeln!(out, "Fetch: {}: {}", self.name, message)?,
+                    Status::GraftError => {
+                        writeln!(out, "Graft: {}[{}]: {}", self.name, deployment, message)?
+                    }
+                    Status::ApiVersionError => writeln!(
+                        out,
+                        "API version: {}[{}]: {}",
+                        self.name, deployment, message
+                    )?,
+                    Status::QueryError => {
+                        writeln!(out, "Query: {}[{}]: {}", self.name, deployment, message)?
+                    }
+                    Status::CollationError => {
+                        writeln!(out, "Collation: {}[{}]: {}", self.name, deployment, message)?
+                    }
+                    Status::TooLarge => {
+                        writeln!(out, "Too large: {}[{}]: {}", self.name, deployment, message)?
+                    }
+                    Status::Panic => writeln!(out, "Panic: {}: {}", self.name, message)?,
+                }
+                match &self.spec_versions {
+                    Some(versions) if versions.is_empty() => {
+                        writeln!(out, "    not valid under any spec version")?
+                    }
+                    Some(versions) => {
+                        writeln!(out, "    valid under spec versions {}", versions.join(", "))?
+                    }
+                    None => { /* not requested */ }
+                }
+                for detail in &self.details {
+                    writeln!(out, "    {}", detail)?;
+                }
+                for warning in &self.warnings {
+                    write!(out, "warning: ")?;
+                    warning.print(&self.name, out)?;
+                }
+                for deprecation in &self.deprecations {
+                    write!(out, "deprecated: ")?;
+                    deprecation.print(&self.name, out)?;
+                }
+                for index in &self.indexes {
+                    writeln!(
+                        out,
+                        "    index {}.{} ({})",
+                        index.table, index.field, index.method
+                    )?;
+                }
+                if let Some(hash) = &self.hash {
+                    writeln!(out, "    hash: {}", hash)?;
+                }
+                if self.fixed {
+                    writeln!(out, "    rewritten in canonical form")?;
+                }
+                if let Some(elapsed) = self.elapsed {
+                    writeln!(out, "    validated in {:.1}ms", elapsed)?;
+                }
+                if let Some(features) = &self.features {
+                    writeln!(out, "    features: {}", features.join(", "))?;
+                }
+                for change in &self.api_changes {
+                    let verb = if change.added { "added" } else { "removed" };
+                    match &change.filter {
+                        Some(filter) => writeln!(
+                            out,
+                            "    api: {} filter `{}` of `{}`",
+                            verb, filter, change.query_field
+                        )?,
+                        None => writeln!(
+                            out,
+                            "    api: {} query field `{}`",
+                            verb, change.query_field
+                        )?,
+                    }
+                }
+                for inverse in &self.inverses {
+                    writeln!(out, "    suggestion: {}", inverse)?;
+                }
+                for table in &self.tables {
+                    let wide = if table.wide { " (wide)" } else { "" };
+                    writeln!(
+                        out,
+                        "    table {}: {} columns{}",
+                        table.table, table.columns, wide
+                    )?;
+                }
+                if let Some(api_schema) = &self.api_schema {
+                    writeln!(out, "{}", api_schema)?;
+                }
+                if let Some(schema) = &self.schema {
+                    writeln!(
+                        out,
+                        "{}",
+                        serde_json::to_string_pretty(schema).expect("JSON values can be printed")
+                    )?;
+                }
+                Ok(())
+            }
+            Format::Json => writeln!(
+                out,
+                "{}",
+                serde_json::to_string(self).expect("outcomes can be serialized")
+            ),
+            Format::Csv => {
+                let codes: Vec<_> = self.errors.iter().map(|error| error.code).collect();
+                let row = [
+                    self.id.map(|id| id.to_string()).unwrap_or_default(),
+                    self.name.clone(),
+                    self.deployment.clone().unwrap_or_default(),
+                    self.status.as_str().to_string(),
+                    codes.join(";"),
+                    self.message.clone().unwrap_or_default(),
+                ];
+                let row: Vec<_> = row.iter().map(|field| csv_field(field)).collect();
+                writeln!(out, "{}", row.join(","))
+            }
+            Format::Junit => {
+                write!(out, "  <testcase name=\"{}\"", xml_escape(&self.name))?;
+                if let Some(deployment) = &self.deployment {
+                    write!(out, " classname=\"{}\"", xml_escape(deployment))?;
+                }
+                if let Some(elapsed) = self.elapsed {
+                    write!(out, " time=\"{:.3}\"", elapsed / 1000.0)?;
+                }
+                if self.is_ok() {
+                    return writeln!(out, "/>");
+                }
+                let code = self
+                    .errors
+                    .first()
+                    .map(|error| error.code)
+                    .unwrap_or_else(|| self.status.as_str());
+                let message = self
+                    .message
+                    .as_deref()
+                    .or_else(|| self.errors.first().map(|error| error.message.as_str()))
+                    .unwrap_or("");
+                writeln!(out, ">")?;
+                write!(
+                    out,
+                    "    <failure type=\"{}\" message=\"{}\">",
+                    xml_escape(code),
+                    xml_escape(message)
+                )?;
+                for error in &self.errors {
+                    write!(
+                        out,
+                        "\n[{}] {}",
+                        xml_escape(error.code),
+                        xml_escape(&error.message)
+                    )?;
+                }
+                writeln!(out, "</failure>")?;
+                writeln!(out, "  </testcase>")
+            }
+        }
+    }
+}
+
+/// Counts of outcomes by status over a whole run
+#[derive(Debug, Default)]
+struct Summary {
+    total: usize,
+    ok: usize,
+    parse_errors: usize,
+    input_schema_errors: usize,
+    api_schema_errors: usize,
+    manifest_errors: usize,
+    fetch_errors: usize,
+    graft_errors: usize,
+    api_version_errors: usize,
+    query_errors: usize,
+    collation_errors: usize,
+    too_large: usize,
+    panics: usize,
+    /// The number of valid schemas that triggered lints
+    warnings: usize,
+    /// With `--fix`, the number of files that were rewritten
+    fixed: usize,
+    /// The number of schemas with validation errors that graph-node
+    /// would accept without `--strict`
+    recoverable: usize,
+    /// With `--api-only`, the number of schemas that we did not report
+    /// since they failed before the API schema could be generated
+    skipped: usize,
+}
+
+impl Summary {
+    fn add(&mut self, outcome: &Outcome) {
+        self.total += 1;
+        if !outcome.warnings.is_empty() {
+            self.warnings += 1;
+        }
+        if outcome.fixed {
+            self.fixed += 1;
+        }
+        // Schemas that only fail the `--api-version` check in strict mode
+        // are also accepted by graph-node
+        if outcome.status == Status::ApiVersionError
+            || (outcome.status == Status::InputSchemaError
+                && !outcome.errors.is_empty()
+                && outcome
+                    .errors
+                    .iter()
+                    .all(|error| error.severity != Severity::Error.as_str()))
+        {
+            self.recoverable += 1;
+        }
+        match outcome.status {
+            Status::Ok => self.ok += 1,
+            Status::ParseError => self.parse_errors += 1,
+            Status::InputSchemaError => self.input_schema_errors += 1,
+            Status::ApiSchemaError => self.api_schema_errors += 1,
+            Status::ManifestError => self.manifest_errors += 1,
+            Status::FetchError => self.fetch_errors += 1,
+            Status::GraftError => self.graft_errors += 1,
+            Status::ApiVersionError => self.api_version_errors += 1,
+            Status::QueryError => self.query_errors += 1,
+            Status::CollationError => self.collation_errors += 1,
+            Status::TooLarge => self.too_large += 1,
+            Status::Panic => self.panics += 1,
+        }
+    }
+
+    fn print(&self, format: Format, out: &mut dyn Write) -> io::Result<()> {
+        match format {
+            Format::Text => {
+                let mut extra = String::new();
+                if self.manifest_errors > 0 {
+                    extra.push_str(&format!(", {} manifest errors", self.manifest_errors));
+                }
+                if self.fetch_errors > 0 {
+                    extra.push_str(&format!(", {} fetch errors", self.fetch_errors));
+                }
+                if self.graft_errors > 0 {
+                    extra.push_str(&format!(", {} graft errors", self.graft_errors));
+                }
+                if self.api_version_errors > 0 {
+                    extra.push_str(&format!(", {} api version errors", self.api_version_errors));
+                }
+                if self.query_errors > 0 {
+                    extra.push_str(&format!(", {} query errors", self.query_errors));
+                }
+                if self.collation_errors > 0 {
+                    extra.push_str(&format!(", {} collation errors", self.collation_errors));
+                }
+                if self.too_large > 0 {
+                    extra.push_str(&format!(", {} too large", self.too_large));
+                }
+                if self.panics > 0 {
+                    extra.push_str(&format!(", {} panics", self.panics));
+                }
+                if self.warnings > 0 {
+                    extra.push_str(&format!(", {} with warnings", self.warnings));
+                }
+                if self.fixed > 0 {
+                    extra.push_str(&format!(", {} rewritten", self.fixed));
+                }
+                if self.recoverable > 0 {
+                    extra.push_str(&format!(", {} only strict errors", self.recoverable));
+                }
+                if self.skipped > 0 {
+                    extra.push_str(&format!(", {} skipped", self.skipped));
+                }
+                writeln!(
+                    out,
+                    "Validated {} schemas: {} ok, {} parse errors, {} input-schema errors, {} api-schema errors{}",
+                    self.total,
+                    self.ok,
+                    self.parse_errors,
+                    self.input_schema_errors,
+                    self.api_schema_errors,
+                    extra
+                )
+            }
+            Format::Json => {
+                let summary = serde_json::json!({
+                    "type": "summary",
+                    "total": self.total,
+                    "ok": self.ok,
+                    "parse_errors": self.parse_errors,
+                    "input_schema_errors": self.input_schema_errors,
+                    "api_schema_errors": self.api_schema_errors,
+                    "manifest_errors": self.manifest_errors,
+                    "fetch_errors": self.fetch_errors,
+                    "graft_errors": self.graft_errors,
+                    "api_version_errors": self.api_version_errors,
+                    "query_errors": self.query_errors,
+                    "collation_errors": self.collation_errors,
+                    "too_large": self.too_large,
+                    "panics": self.panics,
+                    "warnings": self.warnings,
+                    "fixed": self.fixed,
+                    "recoverable": self.recoverable,
+                    "skipped": self.skipped,
+                });
+                writeln!(out, "{}", summary)
+            }
+            Format::Csv | Format::Junit => {
+                // Keep stdout a valid CSV or XML file
+                eprintln!(
+                    "Validated {} schemas: {} ok, {} failed",
+                    self.total,
+                    self.ok,
+                    self.total - self.ok
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The header row for `--format csv`
+const CSV_HEADER: &str = "id,name,deployment,status,code,message";
+
+/// Quote `field` for CSV output if it contains a separator, a quote, or a
+/// line break
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escape `text` for use in XML attributes and character data
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// With `--format junit`, the test cases written so far. The
+/// `<testsuite>` element needs to say how many tests and failures it
+/// contains, so the test cases can only be written once all schemas have
+/// been validated
+struct JunitSuite {
+    name: String,
+    cases: Vec<u8>,
+    tests: usize,
+    failures: usize,
+}
+
+impl JunitSuite {
+    fn print(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            out,
+            "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+            xml_escape(&self.name),
+            self.tests,
+            self.failures
+        )?;
+        out.write_all(&self.cases)?;
+        writeln!(out, "</testsuite>")
+    }
+}
+
+/// Prints outcomes and keeps track of how many schemas failed
+struct Reporter {
+    format: Format,
+    /// Where outcomes are written, stdout or the file from `--output`
+    out: Box<dyn Write>,
+    /// Whether `out` is the file from `--output`. If it is, everything
+    /// that is not an outcome goes to stderr
+    to_file: bool,
+    fail_fast: bool,
+    quiet: bool,
+    deny_warnings: bool,
+    api_only: bool,
+    summary: Summary,
+    /// With `--timing`, the slowest schemas so far as `(elapsed, name)`,
+    /// slowest first
+    slowest: Option<Vec<(f64, String)>>,
+    /// With `--estimate`, the widest tables so far as `(columns, name)`
+    /// where `name` is `schema.table`, widest first
+    widest: Option<Vec<(usize, String)>>,
+    /// With `--canonical`, the names of the valid schemas by their hash
+    hashes: Option<BTreeMap<String, Vec<String>>>,
+    progress: Option<Progress>,
+    /// With `--expect`, the expected outcomes. When they are given, a run
+    /// only fails if an outcome differs from what was expected
+    expectations: Option<Expectations>,
+    /// With `--baseline`, the outcomes of a previous run
+    baseline: Option<Baseline>,
+    junit: Option<JunitSuite>,
+}
+
+/// With `--progress`, how often to print a progress line
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Periodically prints how many schemas have been validated to stderr
+struct Progress {
+    last: Instant,
+    /// The number of entries in all batch files if we could determine it
+    total: Option<usize>,
+}
+
+impl Progress {
+    fn print(&self, summary: &Summary) {
+        let failed = summary.total - summary.ok;
+        match self.total {
+            Some(total) if total > 0 => eprintln!(
+                "Progress: {}/{} schemas ({:.1}%), {} failed",
+                summary.total,
+                total,
+                100.0 * summary.total as f64 / total as f64,
+                failed
+            ),
+            _ => eprintln!("Progress: {} schemas, {} failed", summary.total, failed),
+        }
+    }
+}
+
+/// How many of the slowest schemas to list with `--timing`
+const SLOWEST: usize = 10;
+
+/// How many of the widest tables to list with `--estimate`
+const WIDEST: usize = 10;
+
+impl Reporter {
+    fn new(opt: &Opts) -> Self {
+        let mut out: Box<dyn Write> = match &opt.output {
+            Some(path) => Box::new(BufWriter::new(ensure(
+                File::create(path),
+                &format!("failed to create output file {}", path),
+            ))),
+            None => Box::new(io::stdout()),
+        };
+        if opt.format == Format::Csv {
+            ensure(writeln!(out, "{}", CSV_HEADER), "failed to write output");
+        }
+        Reporter {
+            format: opt.format,
+            out,
+            to_file: opt.output.is_some(),
+            fail_fast: opt.fail_fast,
+            quiet: opt.quiet,
+            deny_warnings: opt.deny_warnings,
+            api_only: opt.api_only,
+            summary: Summary::default(),
+            slowest: opt.timing.then(Vec::new),
+            widest: opt.estimate.then(Vec::new),
+            hashes: opt.canonical.then(BTreeMap::new),
+            progress: opt.progress.then(|| Progress {
+                last: Instant::now(),
+                total: None,
+            }),
+            expectations: opt.expect.as_deref().map(Expectations::load),
+            baseline: opt.baseline.as_deref().map(Baseline::load),
+            junit: (opt.format == Format::Junit).then(|| JunitSuite {
+                name: opt.suite_name.clone(),
+                cases: Vec::new(),
+                tests: 0,
+                failures: 0,
+            }),
+        }
+    }
+
+    /// Print `outcome`. Return `false` if we should stop validating
+    fn report(&mut self, outcome: &Outcome) -> bool {
+        if let Some(expectations) = &mut self.expectations {
+            expectations.check(outcome);
+        }
+        if let Some(baseline) = &mut self.baseline {
+            baseline.check(outcome);
+        }
+        if self.api_only
+            && matches!(
+                outcome.status,
+                Status::ParseError | Status::InputSchemaError
+            )
+        {
+            self.summary.skipped += 1;
+            return true;
+        }
+        let failure = !outcome.is_ok() || (self.deny_warnings && !outcome.warnings.is_empty());
+        if !self.quiet || failure {
+            let res = match &mut self.junit {
+                Some(junit) => {
+                    junit.tests += 1;
+                    if !outcome.is_ok() {
+                        junit.failures += 1;
+                    }
+                    outcome.print(self.format, &mut junit.cases)
+                }
+                None => outcome.print(self.format, &mut self.out),
+            };
+            ensure(res, "failed to write output");
+        }
+        self.summary.add(outcome);
+        if let Some(progress) = &mut self.progress {
+            if progress.last.elapsed() >= PROGRESS_INTERVAL {
+                progress.print(&self.summary);
+                progress.last = Instant::now();
+            }
+        }
+        if let (Some(slowest), Some(elapsed)) = (&mut self.slowest, outcome.elapsed) {
+            slowest.push((elapsed, outcome.name.clone()));
+            slowest.sort_by(|a, b| b.0.total_cmp(&a.0));
+            slowest.truncate(SLOWEST);
+        }
+        if let Some(widest) = &mut self.widest {
+            widest.extend(
+                outcome
+                    .tables
+                    .iter()
+                    .map(|table| (table.columns, format!("{}.{}", outcome.name, table.table))),
+            );
+            widest.sort_by(|a, b| b.0.cmp(&a.0));
+            widest.truncate(WIDEST);
+        }
+        if let (Some(hashes), Some(hash)) = (&mut self.hashes, &outcome.hash) {
+            hashes
+                .entry(hash.clone())
+                .or_default()
+                .push(outcome.name.clone());
+        }
+        !(self.failed() && self.fail_fast)
+    }
+
+    /// The exit code for a run that `failed`: 2 if graph-node would accept
+    /// all the schemas that failed without `--strict` or `--deny-warnings`,
+    /// and 1 otherwise
+    fn exit_code(&self) -> i32 {
+        if self.expectations.is_none()
+            && self.summary.ok + self.summary.recoverable == self.summary.total
+        {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn failed(&self) -> bool {
+        match &self.expectations {
+            Some(expectations) => !expectations.mismatches.is_empty(),
+            None => {
+                self.summary.ok < self.summary.total
+                    || (self.deny_warnings && self.summary.warnings > 0)
+            }
+        }
+    }
+
+    /// Print a line that announces what is being validated. These lines
+    /// are only useful for text output and are never written to the
+    /// `--output` file
+    fn announce(&self, line: &str) {
+        match (self.format, self.to_file) {
+            (Format::Text, false) => println!("{}", line),
+            (Format::Text, true) => eprintln!("{}", line),
+            (Format::Json | Format::Csv | Format::Junit, _) => {
+                /* keep stdout machine-readable */
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(slowest) = &self.slowest {
+            // Keep stdout a valid JSONL or CSV file
+            let print = |line: String| match (self.format, self.to_file) {
+                (Format::Text, false) => println!("{}", line),
+                _ => eprintln!("{}", line),
+            };
+            print(format!("Slowest {} schemas:", slowest.len()));
+            for (elapsed, name) in slowest {
+                print(format!("    {:>10.1}ms {}", elapsed, name));
+            }
+        }
+        if let Some(widest) = &self.widest {
+            let print = |line: String| match (self.format, self.to_file) {
+                (Format::Text, false) => println!("{}", line),
+                _ => eprintln!("{}", line),
+            };
+            print(format!("Widest {} tables:", widest.len()));
+            for (columns, name) in widest {
+                print(format!("    {:>5} columns {}", columns, name));
+            }
+        }
+        if let Some(hashes) = &self.hashes {
+            let print = |line: String| match (self.format, self.to_file) {
+                (Format::Text, false) => println!("{}", line),
+                _ => eprintln!("{}", line),
+            };
+            let total: usize = hashes.values().map(Vec::len).sum();
+            print(format!(
+                "{} distinct schemas among {} valid schemas",
+                hashes.len(),
+                total
+            ));
+            for (hash, names) in hashes.iter().filter(|(_, names)| names.len() > 1) {
+                print(format!("    {}: {}", hash, names.join(", ")));
+            }
+        }
+        if let Some(junit) = &self.junit {
+            ensure(junit.print(&mut self.out), "failed to write output");
+        }
+        let res = if self.to_file {
+            self.summary.print(self.format, &mut io::stderr())
+        } else {
+            self.summary.print(self.format, &mut self.out)
+        };
+        ensure(res.and_then(|_| self.out.flush()), "failed to write output");
+
+        if let Some(expectations) = &mut self.expectations {
+            expectations.finish();
+            if expectations.mismatches.is_empty() {
+                eprintln!("All {} outcomes as expected", expectations.seen.len());
+            } else {
+                eprintln!(
+                    "{} outcomes differ from expectations:",
+                    expectations.mismatches.len()
+                );
+                for mismatch in &expectations.mismatches {
+                    eprintln!("    {}", mismatch);
+                }
+            }
+        }
+        if let Some(baseline) = &self.baseline {
+            baseline.print();
+        }
+    }
+}
+
 #[derive(Parser)]
 #[clap(
     name = "validate",
@@ -90,66 +1389,1383 @@ struct Entry {
 )]
 struct Opts {
     /// Validate a batch of schemas in bulk. When this is set, the input
-    /// files must be JSONL files where each line has an `id` and a `schema`
+    /// files must be JSONL files where each line has an `id` and a
+    /// `schema`. Blank lines and lines starting with `#` are skipped
     #[clap(short, long)]
     batch: bool,
+    /// In batch mode, the key of each entry that holds its `id`
+    #[clap(long, default_value = "id")]
+    id_field: String,
+    /// In batch mode, the key of each entry that holds its schema
+    #[clap(long, default_value = "schema")]
+    schema_field: String,
+    /// In batch mode, only validate entries whose `id` is greater than
+    /// this
+    #[clap(long, requires = "batch")]
+    since_id: Option<i32>,
+    /// In batch mode, only validate the entry with this `id`
+    #[clap(long, requires = "batch", conflicts_with = "since_id")]
+    only_id: Option<i32>,
+    /// In batch mode, only validate a random sample of this many of the
+    /// selected entries across all input files. The files are still read
+    /// in a single pass, and only the sampled entries are kept in memory
+    #[clap(long, requires = "batch")]
+    sample: Option<usize>,
+    /// The seed for choosing the entries for `--sample`. Runs with the
+    /// same seed and input validate the same entries
+    #[clap(long, requires = "sample")]
+    seed: Option<u64>,
+    /// Also check that an API schema can be generated from each schema.
+    /// This is the default with `--profile full`
     #[clap(long)]
     api: bool,
-    /// Subgraph schemas to validate
+    /// Do not check the API schema, even with `--profile full`
+    #[clap(long, conflicts_with_all = &["api", "api_only", "dump_api", "query", "diff_api"])]
+    no_api: bool,
+    /// What to check by default: `input` only validates the input schema,
+    /// `full` also checks that an API schema can be generated, as if
+    /// `--api` had been given
+    #[clap(long, default_value = "input", possible_values = &["input", "full"])]
+    profile: Profile,
+    /// Generate the API schema, but only report on that step. Schemas
+    /// that can not be parsed or that are not valid input schemas are
+    /// skipped and only counted in the summary. Implies `--api`
+    #[clap(long)]
+    api_only: bool,
+    /// Reject directives and directive arguments that graph-node does not
+    /// interpret, entity types that reference each other in a cycle of
+    /// non-nullable fields, lists of references with nullable elements,
+    /// names with prefixes that graph-node reserves, interfaces that no
+    /// type implements, lists on immutable entity types of the entities
+    /// that reference them, fields other than `id` of type `ID`, and lists
+    /// of scalars or enums with nullable elements. With `--api-version`,
+    /// also reject schemas that use features the mappings can not write.
+    /// If all the schemas that fail would only fail because of this, exit
+    /// with status 2 instead of 1
+    #[clap(long)]
+    strict: bool,
+    /// Validate the schemas for subgraphs whose entities are written by
+    /// the entity changes of a substreams data source rather than by
+    /// mappings, and reject fields that entity changes can not set
+    #[clap(long)]
+    substreams: bool,
+    /// Describe the fulltext searches and aggregations of each valid
+    /// schema, and the lowest spec version it requires
+    #[clap(long)]
+    details: bool,
+    /// Like `--details`, but also list the entity types, which of them
+    /// are immutable, and the interfaces with the types implementing them
+    #[clap(long)]
+    explain: bool,
+    /// Estimate how many columns the table for each entity type of a
+    /// valid schema will have and flag tables with 100 or more columns.
+    /// At the end, list the widest tables across all schemas
+    #[clap(long)]
+    estimate: bool,
+    /// List the fields of each entity type of a valid schema that the
+    /// store will index by default, and the kind of index it will use
+    #[clap(long)]
+    indexes: bool,
+    /// For each valid schema, list the references whose target entity
+    /// type has no field `@derivedFrom` them, and suggest a derived field
+    /// for it
+    #[clap(long)]
+    audit_inverses: bool,
+    /// List the graph-node features that each valid schema uses as sorted
+    /// tags: the schema features that spec versions gate, like
+    /// `fullTextSearch` or `immutableEntities`, and `interfaces`, `enums`,
+    /// `references`, `derivedFields`, and `bytesId`
+    #[clap(long)]
+    list_features: bool,
+    /// Print the canonical hash of each valid schema. Schemas that only
+    /// differ in formatting, comments, descriptions, or the order of their
+    /// declarations have the same hash. At the end, list the schemas that
+    /// share a hash
+    #[clap(long)]
+    canonical: bool,
+    /// Rewrite each input file whose schema is valid in the canonical form
+    /// of `InputSchema::to_canonical_sdl`, with definitions and fields
+    /// sorted by name. Comments are not preserved. Files that already are
+    /// in canonical form and files with invalid schemas are left alone, so
+    /// that running this a second time changes nothing. Schemas from
+    /// stdin or from compressed files are never rewritten
+    #[clap(long)]
+    fix: bool,
+    /// Print the API schema generated for each valid schema as GraphQL
+    /// SDL. Implies `--api`
+    #[clap(long)]
+    dump_api: bool,
+    /// Print the resolved structure of each valid schema, i.e., its types,
+    /// fields, enums, fulltext searches, and aggregations, as JSON
+    #[clap(long)]
+    dump_schema: bool,
+    /// Write the references between the entity types of a valid schema to
+    /// this file as a GraphViz DOT graph. Derived fields are drawn as
+    /// dashed edges, and interfaces as boxes. Needs a single input schema
+    #[clap(long, conflicts_with_all = &["batch", "sample"])]
+    dot: Option<String>,
+    /// The output format, `text`, `json`, `csv`, or `junit`. With `json`,
+    /// one JSON object is printed per schema, with `csv` one row with the
+    /// columns `id,name,deployment,status,code,message`, and with `junit` a
+    /// JUnit XML test suite with one test case per schema
+    #[clap(long, default_value = "text", possible_values = &["text", "json", "csv", "junit"])]
+    format: Format,
+    /// With `--format junit`, the name of the test suite
+    #[clap(long, default_value = "schemas")]
+    suite_name: String,
+    /// The number of threads to use for validating schemas in batch mode.
+    /// Results are still printed in the order of the input
+    #[clap(short, long, default_value = "1")]
+    jobs: usize,
+    /// Stop at the first schema that fails validation instead of validating
+    /// all of them
+    #[clap(long)]
+    fail_fast: bool,
+    /// Only print the schemas that fail validation, and the summary
+    #[clap(short, long)]
+    quiet: bool,
+    /// Write the result for each schema to this file instead of stdout,
+    /// in the format given by `--format`. The summary and any other
+    /// messages are printed to stderr
+    #[clap(short, long)]
+    output: Option<String>,
+    /// In batch mode, print how many schemas have been validated and how
+    /// many of them failed to stderr every few seconds
+    #[clap(long, requires = "batch")]
+    progress: bool,
+    /// Compare the status of each schema with the one given in this file
+    /// and only fail if they differ. Each line of the file has the name of
+    /// a schema as it is reported, e.g., the path of the file, followed by
+    /// `ok`, `fail`, or a status like `input_schema_error`
+    #[clap(long)]
+    expect: Option<String>,
+    /// Compare the outcomes with those in this file, the output of a
+    /// previous run with `--format json`, and list the schemas that newly
+    /// fail, newly pass, or fail with different errors
+    #[clap(long)]
+    baseline: Option<String>,
+    /// Treat schemas that trigger lints as failures
+    #[clap(long)]
+    deny_warnings: bool,
+    /// Print how long validating each schema took, including generating
+    /// the API schema with `--api`, and list the slowest schemas at the end
+    #[clap(long)]
+    timing: bool,
+    /// The spec version to validate against
+    #[clap(long, default_value = "1.1.0", parse(try_from_str = Version::parse))]
+    spec_version: Version,
+    /// A JSON file that maps spec versions to the schema features they
+    /// support, like `{ "1.2.0": ["immutableEntities", "int8Id"] }`. When
+    /// it is given, only the spec versions in it are known, and schemas
+    /// are validated against the features the file lists for their spec
+    /// version instead of the ones built into graph-node. Features are
+    /// `fullTextSearch`, `immutableEntities`, `aggregations`,
+    /// `timeseries`, and `int8Id`
+    #[clap(long)]
+    spec_versions_file: Option<String>,
+    /// The spec versions loaded from `--spec-versions-file`
+    #[clap(skip)]
+    spec_registry: Option<BTreeMap<Version, BTreeSet<SchemaFeature>>>,
+    /// Also validate each schema against every known spec version and
+    /// report the ones under which it is valid
+    #[clap(long)]
+    all_spec_versions: bool,
+    /// Treat the inputs as subgraph manifests (`subgraph.yaml`) and
+    /// validate the schema they reference against the spec version they
+    /// declare
+    #[clap(short, long, conflicts_with = "batch")]
+    manifest: bool,
+    /// Concatenate all the input files into one schema and validate that
+    /// instead of validating each file on its own. Positions in error
+    /// messages refer to the concatenated schema
+    #[clap(long, conflicts_with_all = &["batch", "manifest"])]
+    merge: bool,
+    /// Fail schemas that declare more than this many object types,
+    /// interfaces, and aggregations
+    #[clap(long)]
+    max_types: Option<usize>,
+    /// Fail schemas that declare more than this many fields on object
+    /// types and interfaces
+    #[clap(long)]
+    max_fields: Option<usize>,
+    /// Fail schemas whose API schema would have more than this many
+    /// fields, counting the fields of the filter types
+    #[clap(long)]
+    max_api_fields: Option<usize>,
+    /// Check that each valid schema can be used for a subgraph that is
+    /// grafted onto a subgraph with the schema in this file
+    #[clap(long)]
+    graft_base: Option<String>,
+    /// The parsed schema from `--graft-base`
+    #[clap(skip)]
+    graft_base_schema: Option<InputSchema>,
+    /// Check that the GraphQL query in this file is valid against the API
+    /// schema of each valid schema, i.e., that the fields and arguments it
+    /// uses exist. Implies `--api`
+    #[clap(long)]
+    query: Option<String>,
+    /// The parsed query from `--query`
+    #[clap(skip)]
+    query_document: Option<q::Document>,
+    /// List the query fields and filters that the API schema of each
+    /// valid schema adds or removes compared to the API schema of the
+    /// schema in this file. Implies `--api`
+    #[clap(long)]
+    diff_api: Option<String>,
+    /// The API schema of the schema from `--diff-api`
+    #[clap(skip)]
+    diff_api_schema: Option<ApiSchema>,
+    /// A comma-separated list of the Postgres text search configurations
+    /// that the database supports, like `simple,english`. Fail schemas
+    /// with fulltext searches whose language needs a configuration that
+    /// is not in the list
+    #[clap(long, value_delimiter = ',')]
+    collations: Option<Vec<String>>,
+    /// The `apiVersion` of the mappings of the subgraphs. Warn about
+    /// valid schemas that use features that mappings with this API
+    /// version can not write; with `--strict`, fail them instead
+    #[clap(long, parse(try_from_str = Version::parse))]
+    api_version: Option<Version>,
+    /// Decompress the input read from stdin with gzip. Input files whose
+    /// name ends in `.gz` are always decompressed
+    #[clap(long)]
+    gzip: bool,
+    /// The extension of the files to validate when a directory is passed
+    /// as one of the `schemas`
+    #[clap(long, default_value = "graphql")]
+    ext: String,
+    /// The IPFS node to fetch `ipfs://` schemas from
+    #[clap(long, default_value = "http://localhost:5001")]
+    ipfs: String,
+    /// How long to wait for a schema from an `http(s)://` or `ipfs://`
+    /// URL, in seconds
+    #[clap(long, default_value = "30")]
+    fetch_timeout: u64,
+    /// Subgraph schemas to validate. Use `-` to read from stdin. Unless
+    /// `--batch` is given, directories are searched recursively for files
+    /// with the extension given by `--ext`. Schemas can also be given as
+    /// `http(s)://` or `ipfs://<cid>/<path>` URLs; they are fetched before
+    /// they are validated
     #[clap(required = true)]
     schemas: Vec<String>,
 }
 
-fn parse(raw: &str, name: &str, api: bool) {
-    let schema = ensure(
-        parse_schema(raw).map(|v| v.into_static()),
-        &format!("Failed to parse schema sgd{}", name),
-    );
-    let id = subgraph_id(&schema);
-    let input_schema = match InputSchema::parse(&SPEC_VERSION_1_1_0, raw, id.clone()) {
+impl Opts {
+    /// Whether the batch `entry` should be validated according to
+    /// `--since-id` and `--only-id`
+    fn selects(&self, entry: &Entry) -> bool {
+        self.since_id.map_or(true, |since| entry.id > since)
+            && self.only_id.map_or(true, |only| entry.id == only)
+    }
+
+    /// Whether to check that an API schema can be generated
+    fn api(&self) -> bool {
+        if self.no_api {
+            return false;
+        }
+        self.api
+            || self.api_only
+            || self.dump_api
+            || self.query.is_some()
+            || self.diff_api.is_some()
+            || self.profile == Profile::Full
+    }
+
+    /// The options to validate schemas with. Fulltext search is always
+    /// allowed since all we want to know is whether a schema is valid
+    fn options(&self, spec_version: &Version) -> ValidationOptions {
+        ValidationOptions {
+            strict: self.strict,
+            allow_fulltext: true,
+            features: self
+                .spec_registry
+                .as_ref()
+                .and_then(|registry| registry.get(spec_version).cloned()),
+            substreams: self.substreams,
+        }
+    }
+
+    /// The spec versions we know about, either the ones built into
+    /// graph-node or the ones from `--spec-versions-file`, in increasing
+    /// order
+    fn spec_versions(&self) -> Vec<Version> {
+        match &self.spec_registry {
+            Some(registry) => registry.keys().cloned().collect(),
+            None => SPEC_VERSIONS.to_vec(),
+        }
+    }
+}
+
+/// The name of the input that means 'read from stdin'
+const STDIN: &str = "-";
+
+/// Open the input `path` for reading, treating `-` as stdin. Files ending
+/// in `.gz` are decompressed while reading, and so is stdin if `gzip` is
+/// set
+fn open(path: &str, gzip: bool) -> std::io::Result<Box<dyn BufRead>> {
+    let (rdr, gzip): (Box<dyn Read>, bool) = if path == STDIN {
+        (Box::new(std::io::stdin()), gzip)
+    } else {
+        (Box::new(File::open(path)?), path.ends_with(".gz"))
+    };
+    if gzip {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(rdr))))
+    } else {
+        Ok(Box::new(BufReader::new(rdr)))
+    }
+}
+
+/// Read all of `path`, treating `-` as stdin
+fn read_to_string(path: &str, gzip: bool) -> std::io::Result<String> {
+    let mut raw = String::new();
+    open(path, gzip)?.read_to_string(&mut raw)?;
+    Ok(raw)
+}
+
+/// Read the schema in `path`, treating `-` as stdin. Schemas in dumps
+/// sometimes contain bytes that are not valid UTF-8; rather than aborting,
+/// we return an error message that can be reported for that schema
+fn read_schema(path: &str, gzip: bool) -> Result<String, String> {
+    let mut raw = Vec::new();
+    open(path, gzip)
+        .and_then(|mut rdr| rdr.read_to_end(&mut raw))
+        .map_err(|e| format!("failed to read {}: {}", input_name(path), e))?;
+    String::from_utf8(raw).map_err(|e| SchemaValidationError::from(e.utf8_error()).to_string())
+}
+
+/// Expand `path` into the list of files to validate. If `path` is a
+/// directory, that is all files under it with extension `ext`, in sorted
+/// order; otherwise, it is just `path`
+fn schema_files(path: &str, ext: &str) -> std::io::Result<Vec<String>> {
+    fn walk(dir: &Path, ext: &str, files: &mut Vec<String>) -> std::io::Result<()> {
+        let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|entry| entry.path());
+        for entry in entries {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, ext, files)?;
+            } else if path.extension().map_or(false, |e| e == ext) {
+                files.push(path.to_string_lossy().into_owned());
+            }
+        }
+        Ok(())
+    }
+
+    if path != STDIN && Path::new(path).is_dir() {
+        let mut files = Vec::new();
+        walk(Path::new(path), ext, &mut files)?;
+        Ok(files)
+    } else {
+        Ok(vec![path.to_string()])
+    }
+}
+
+/// Whether `path` is a URL that we need to fetch the schema from
+fn is_url(path: &str) -> bool {
+    ["http://", "https://", IPFS_SCHEME]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+}
+
+const IPFS_SCHEME: &str = "ipfs://";
+
+/// Fetch the schema at `url`. Schemas with an `ipfs://` URL are fetched
+/// from the IPFS node given by `--ipfs`
+fn fetch(url: &str, opt: &Opts) -> Result<String, String> {
+    let timeout = Duration::from_secs(opt.fetch_timeout);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let bytes = runtime.block_on(async {
+        match url.strip_prefix(IPFS_SCHEME) {
+            Some(path) => {
+                let client = IpfsClient::new(&opt.ipfs).map_err(|e| e.to_string())?;
+                client
+                    .cat_all(path, timeout)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            None => {
+                let client = reqwest::Client::builder()
+                    .timeout(timeout)
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                let res = client
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(|res| res.error_for_status())
+                    .map_err(|e| e.to_string())?;
+                res.bytes().await.map_err(|e| e.to_string())
+            }
+        }
+    })?;
+    String::from_utf8(bytes.to_vec())
+        .map_err(|e| SchemaValidationError::from(e.utf8_error()).to_string())
+}
+
+/// The name under which we report the schema from `path`
+fn input_name(path: &str) -> &str {
+    if path == STDIN {
+        "<stdin>"
+    } else {
+        path
+    }
+}
+
+fn parse(raw: &str, name: &str, opt: &Opts) -> Outcome {
+    parse_with_version(raw, name, &opt.spec_version, opt)
+}
+
+fn parse_with_version(raw: &str, name: &str, spec_version: &Version, opt: &Opts) -> Outcome {
+    let schema = match parse_schema(raw).map(|v| v.into_static()) {
         Ok(schema) => schema,
+        Err(e) => return Outcome::new(name, None, Status::ParseError).with_message(e),
+    };
+    let id = subgraph_id(&schema);
+    let start = Instant::now();
+    let mut outcome = parse_input_schema(raw, &schema, name, &id, spec_version, opt);
+    if opt.timing {
+        outcome.elapsed = Some(start.elapsed().as_secs_f64() * 1000.0);
+    }
+    if outcome.is_ok() {
+        let input_schema = InputSchema::parse_with_options(
+            spec_version,
+            raw,
+            id.clone(),
+            &opt.options(spec_version),
+        )
+        .expect("schema was validated");
+        outcome.warnings = input_schema
+            .lint()
+            .iter()
+            .map(|warning| Diagnostic::warning(warning, &schema))
+            .collect();
+        outcome.deprecations = input_schema
+            .deprecations()
+            .iter()
+            .map(|deprecation| Diagnostic::deprecation(deprecation, &schema))
+            .collect();
+        if opt.dump_api {
+            outcome.api_schema = Some(api_sdl(&input_schema));
+        }
+        if opt.dump_schema {
+            outcome.schema = Some(input_schema.to_json());
+        }
+        if opt.details || opt.explain {
+            outcome.min_spec_version = Some(input_schema.minimum_spec_version().to_string());
+            if opt.explain {
+                outcome.details = explain(&input_schema);
+            }
+            outcome.details.extend(describe(&input_schema));
+        }
+        if opt.estimate {
+            outcome.tables = estimate(&input_schema);
+        }
+        if opt.indexes {
+            outcome.indexes = indexes(&input_schema);
+        }
+        if opt.audit_inverses {
+            outcome.inverses = input_schema
+                .entities_without_derived_inverse()
+                .values()
+                .flatten()
+                .map(|missing| missing.to_string())
+                .collect();
+        }
+        if opt.canonical {
+            outcome.hash = Some(hex::encode(input_schema.schema_hash()));
+        }
+        if opt.list_features {
+            outcome.features = Some(feature_tags(&input_schema));
+        }
+        if let Some(path) = &opt.dot {
+            ensure(
+                fs::write(path, dot(&input_schema)),
+                &format!("failed to write DOT file {}", path),
+            );
+        }
+        if let Some(msg) = check_size(&input_schema, opt) {
+            outcome.status = Status::TooLarge;
+            outcome.message = Some(msg);
+        }
+        if let Some(base) = opt.graft_base_schema.as_ref().filter(|_| outcome.is_ok()) {
+            if let Err(e) = input_schema.is_graft_compatible_with(base) {
+                outcome.status = Status::GraftError;
+                outcome.message = Some(e.to_string());
+            }
+        }
+        if let Some(query) = opt.query_document.as_ref().filter(|_| outcome.is_ok()) {
+            let api_schema = input_schema.api_schema().expect("schema was validated");
+            if let Err(errors) = api_schema.validate_query(query) {
+                let errors: Vec<_> = errors
+                    .iter()
+                    .map(|e| {
+                        let pos = e.position();
+                        format!("{}:{}: {}", pos.line, pos.column, e)
+                    })
+                    .collect();
+                outcome.status = Status::QueryError;
+                outcome.message = Some(errors.join("; "));
+            }
+        }
+        if let Some(base) = opt.diff_api_schema.as_ref().filter(|_| outcome.is_ok()) {
+            let api_schema = input_schema.api_schema().expect("schema was validated");
+            outcome.api_changes = api_changes(base, &api_schema);
+        }
+        if let Some(collations) = opt.collations.as_ref().filter(|_| outcome.is_ok()) {
+            if let Some(msg) = check_collations(&input_schema, collations) {
+                outcome.status = Status::CollationError;
+                outcome.message = Some(msg);
+            }
+        }
+        if let Some(api_version) = opt.api_version.as_ref().filter(|_| outcome.is_ok()) {
+            if let Err(e) = input_schema.is_compatible_with_api_version(api_version) {
+                if opt.strict {
+                    outcome.status = Status::ApiVersionError;
+                    outcome.message = Some(e.to_string());
+                } else {
+                    let document = &input_schema.schema().document;
+                    outcome.warnings.push(Diagnostic::new(&e, document));
+                }
+            }
+        }
+    }
+    if opt.all_spec_versions {
+        let versions = opt
+            .spec_versions()
+            .iter()
+            .filter(|version| parse_input_schema(raw, &schema, name, &id, version, opt).is_ok())
+            .map(|version| version.to_string())
+            .collect();
+        Outcome {
+            spec_versions: Some(versions),
+            ..outcome
+        }
+    } else {
+        outcome
+    }
+}
+
+fn parse_input_schema(
+    raw: &str,
+    document: &s::Document,
+    name: &str,
+    id: &DeploymentHash,
+    spec_version: &Version,
+    opt: &Opts,
+) -> Outcome {
+    let api = opt.api();
+    match InputSchema::validate(
+        spec_version,
+        raw,
+        id.clone(),
+        api,
+        &opt.options(spec_version),
+    ) {
+        Ok(()) => Outcome::new(name, Some(id), Status::Ok),
+        Err(SchemaValidationError::SchemaParseError(msg)) => {
+            Outcome::new(name, Some(id), Status::ParseError).with_message(msg)
+        }
+        Err(SchemaValidationError::ApiSchemaInvalid(msg)) => {
+            Outcome::new(name, Some(id), Status::ApiSchemaError).with_message(msg)
+        }
         Err(e) => {
-            println!("InputSchema: {}[{}]: {}", name, id, e);
-            return;
+            let errors = e
+                .errors()
+                .into_iter()
+                .map(|error| Diagnostic::new(error, document))
+                .collect();
+            Outcome {
+                errors,
+                ..Outcome::new(name, Some(id), Status::InputSchemaError).with_message(e)
+            }
         }
-    };
-    if api {
-        let _api_schema = match input_schema.api_schema() {
-            Ok(schema) => schema,
-            Err(e) => {
-                println!("ApiSchema: {}[{}]: {}", name, id, e);
-                return;
+    }
+}
+
+/// Check `schema` against `--max-types` and `--max-fields` and describe
+/// the limits it exceeds, if any. All object types, interfaces, and
+/// aggregations count as types
+fn check_size(schema: &InputSchema, opt: &Opts) -> Option<String> {
+    let types =
+        schema.object_type_count() + schema.interface_count() + schema.aggregations().count();
+    let fields = schema.field_count();
+
+    let mut exceeded = Vec::new();
+    if let Some(max) = opt.max_types.filter(|max| types > *max) {
+        exceeded.push(format!("{} types, more than the maximum of {}", types, max));
+    }
+    if let Some(max) = opt.max_fields.filter(|max| fields > *max) {
+        exceeded.push(format!(
+            "{} fields, more than the maximum of {}",
+            fields, max
+        ));
+    }
+    if let Some(max) = opt.max_api_fields {
+        // A schema whose API schema can't be generated fails elsewhere
+        if let Some(api_fields) = schema
+            .estimated_api_schema_size()
+            .ok()
+            .filter(|api_fields| api_fields > &max)
+        {
+            exceeded.push(format!(
+                "{} fields in its API schema, more than the maximum of {}",
+                api_fields, max
+            ));
+        }
+    }
+    if exceeded.is_empty() {
+        None
+    } else {
+        Some(format!("the schema has {}", exceeded.join(" and ")))
+    }
+}
+
+/// Check that the text search configurations for the languages of the
+/// fulltext searches of `schema` are in `collations`
+fn check_collations(schema: &InputSchema, collations: &[String]) -> Option<String> {
+    let unsupported: Vec<_> = schema
+        .fulltext_definitions()
+        .into_iter()
+        .filter(|defn| {
+            let config = defn.config.language.text_search_config();
+            !collations.iter().any(|collation| collation == config)
+        })
+        .map(|defn| {
+            format!(
+                "fulltext search `{}` uses `{}`",
+                defn.name,
+                defn.config.language.text_search_config()
+            )
+        })
+        .collect();
+    if unsupported.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "unsupported text search configurations: {}",
+            unsupported.join(", ")
+        ))
+    }
+}
+
+/// Estimate the columns of the tables that the store will create for
+/// `schema`. This mirrors how the relational layout maps entity types to
+/// tables: every field that is not derived becomes one column, whether it
+/// holds a scalar, a reference, or a list, and every fulltext search on
+/// the type adds another
+fn estimate(schema: &InputSchema) -> Vec<TableEstimate> {
+    let mut tables: Vec<_> = schema
+        .entity_types()
+        .into_iter()
+        .chain(schema.ts_entity_types())
+        .map(|entity_type| {
+            let fields = schema
+                .fields_for(&entity_type)
+                .filter(|field| !field.is_derived)
+                .count();
+            let fulltexts = schema
+                .entity_fulltext_definitions(entity_type.as_str())
+                .len();
+            let columns = fields + fulltexts + SYSTEM_COLUMNS;
+            TableEstimate {
+                table: entity_type.as_str().to_string(),
+                columns,
+                wide: columns >= WIDE_TABLE,
             }
+        })
+        .collect();
+    tables.sort_by(|a, b| {
+        b.columns
+            .cmp(&a.columns)
+            .then_with(|| a.table.cmp(&b.table))
+    });
+    tables
+}
+
+/// The indexes that the store will create for the entity types of
+/// `schema`, as determined by `InputSchema::indexed_fields`
+fn indexes(schema: &InputSchema) -> Vec<TableIndex> {
+    schema
+        .entity_types()
+        .into_iter()
+        .chain(schema.ts_entity_types())
+        .flat_map(|entity_type| {
+            schema
+                .indexed_fields(&entity_type)
+                .into_iter()
+                .map(move |index| TableIndex {
+                    table: entity_type.as_str().to_string(),
+                    field: index.name,
+                    method: index.method.as_str(),
+                })
+        })
+        .collect()
+}
+
+/// The tags for `--list-features`, sorted by name
+fn feature_tags(schema: &InputSchema) -> Vec<String> {
+    let mut tags: BTreeSet<&str> = schema
+        .features()
+        .iter()
+        .map(SchemaFeature::as_str)
+        .collect();
+    if schema.interface_count() > 0 {
+        tags.insert("interfaces");
+    }
+    if schema.enums().next().is_some() {
+        tags.insert("enums");
+    }
+    for (_, _, kind) in schema.reference_graph() {
+        match kind {
+            RefKind::Direct => tags.insert("references"),
+            RefKind::Derived => tags.insert("derivedFields"),
         };
     }
-    println!("Schema {}[{}]: OK", name, id);
+    if schema
+        .entity_types()
+        .iter()
+        .any(|entity_type| matches!(entity_type.id_type(), Ok(IdType::Bytes)))
+    {
+        tags.insert("bytesId");
+    }
+    tags.into_iter().map(str::to_string).collect()
 }
 
-pub fn main() {
-    // Allow fulltext search in schemas
-    std::env::set_var("GRAPH_ALLOW_NON_DETERMINISTIC_FULLTEXT_SEARCH", "true");
+/// Render the references between the entity types of `schema` as a
+/// GraphViz DOT graph with one edge per referencing field
+fn dot(schema: &InputSchema) -> String {
+    let mut nodes: BTreeMap<String, TypeKind> = schema
+        .entity_types()
+        .into_iter()
+        .chain(schema.ts_entity_types())
+        .map(|entity_type| (entity_type.as_str().to_string(), entity_type.kind()))
+        .collect();
+    let mut edges: Vec<_> = schema
+        .reference_graph()
+        .map(|(from, to, kind)| {
+            for entity_type in [&from, &to] {
+                nodes.insert(entity_type.as_str().to_string(), entity_type.kind());
+            }
+            (from.as_str().to_string(), to.as_str().to_string(), kind)
+        })
+        .collect();
+    edges.sort();
+
+    let mut out = String::from("digraph schema {\n");
+    for (name, kind) in &nodes {
+        match kind {
+            TypeKind::Interface => out.push_str(&format!("  \"{}\" [shape=box];\n", name)),
+            TypeKind::Object | TypeKind::Aggregation => out.push_str(&format!("  \"{}\";\n", name)),
+        }
+    }
+    for (from, to, kind) in &edges {
+        match kind {
+            RefKind::Direct => out.push_str(&format!("  \"{}\" -> \"{}\";\n", from, to)),
+            RefKind::Derived => {
+                out.push_str(&format!("  \"{}\" -> \"{}\" [style=dashed];\n", from, to))
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// The query fields, and the filters of the query fields that both have,
+/// that `schema` adds or removes compared to `base`. Removed fields come
+/// first, in the order of `base`, then added ones in the order of `schema`
+fn api_changes(base: &ApiSchema, schema: &ApiSchema) -> Vec<ApiChange> {
+    fn query_fields(schema: &ApiSchema) -> Vec<&str> {
+        match schema.get_root_query_type_def() {
+            Some(s::TypeDefinition::Object(query_type)) => query_type
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn filters(schema: &ApiSchema) -> BTreeMap<String, Vec<String>> {
+        schema
+            .query_filters()
+            .into_iter()
+            .map(|filters| {
+                let names = filters.filters.into_iter().map(|(name, _)| name).collect();
+                (filters.query_field, names)
+            })
+            .collect()
+    }
+
+    fn change(added: bool, query_field: &str, filter: Option<&str>) -> ApiChange {
+        ApiChange {
+            added,
+            query_field: query_field.to_string(),
+            filter: filter.map(str::to_string),
+        }
+    }
+
+    let base_fields = query_fields(base);
+    let fields = query_fields(schema);
+    let mut changes: Vec<_> = base_fields
+        .iter()
+        .filter(|field| !fields.contains(field))
+        .map(|field| change(false, field, None))
+        .collect();
+    changes.extend(
+        fields
+            .iter()
+            .filter(|field| !base_fields.contains(field))
+            .map(|field| change(true, field, None)),
+    );
+
+    let base_filters = filters(base);
+    let filters = filters(schema);
+    let no_filters = Vec::new();
+    for field in fields.iter().filter(|field| base_fields.contains(field)) {
+        let old = base_filters.get(*field).unwrap_or(&no_filters);
+        let new = filters.get(*field).unwrap_or(&no_filters);
+        changes.extend(
+            old.iter()
+                .filter(|filter| !new.contains(filter))
+                .map(|filter| change(false, field, Some(filter.as_str()))),
+        );
+        changes.extend(
+            new.iter()
+                .filter(|filter| !old.contains(filter))
+                .map(|filter| change(true, field, Some(filter.as_str()))),
+        );
+    }
+    changes
+}
+
+/// Generate the API schema for `schema` and render it as GraphQL SDL. Must
+/// only be called for schemas that passed validation with `api` set
+fn api_sdl(schema: &InputSchema) -> String {
+    schema
+        .api_schema()
+        .expect("schema was validated")
+        .document()
+        .to_string()
+}
+
+/// Describe the entity types and interfaces that `schema` declares
+fn explain(schema: &InputSchema) -> Vec<String> {
+    let names = |types: Vec<EntityType>| -> String {
+        let mut names: Vec<_> = types.iter().map(|t| t.as_str().to_string()).collect();
+        names.sort();
+        names.join(", ")
+    };
+
+    let mut details = Vec::new();
+    let entity_types = schema.entity_types();
+    details.push(format!(
+        "{} entity types: {}",
+        entity_types.len(),
+        names(entity_types)
+    ));
+    let immutable = schema.immutable_types();
+    if !immutable.is_empty() {
+        details.push(format!("immutable entity types: {}", names(immutable)));
+    }
+    details.push(format!(
+        "longest chain of references: {}",
+        schema.max_reference_depth()
+    ));
+    let mut interfaces: Vec<_> = schema
+        .schema()
+        .document
+        .get_interface_type_definitions()
+        .into_iter()
+        .map(|intf| intf.name.as_str())
+        .collect();
+    interfaces.sort();
+    for intf in interfaces {
+        let intf = schema
+            .entity_type(intf)
+            .expect("interfaces are known types");
+        let implementers = schema.implementers_of(&intf);
+        if implementers.is_empty() {
+            details.push(format!("interface `{}` is not implemented", intf.as_str()));
+        } else {
+            details.push(format!(
+                "interface `{}` implemented by {}",
+                intf.as_str(),
+                names(implementers)
+            ));
+        }
+    }
+    details
+}
+
+/// Describe the fulltext searches and aggregations that `schema`
+/// declares
+fn describe(schema: &InputSchema) -> Vec<String> {
+    let mut details = Vec::new();
+    for defn in schema.fulltext_definitions() {
+        let mut fields: Vec<_> = defn.included_fields.into_iter().collect();
+        fields.sort();
+        details.push(format!(
+            "fulltext search `{}` on {}({}) with language {} and algorithm {:?}",
+            defn.name,
+            defn.entity,
+            fields.join(", "),
+            defn.config.language.as_sql().trim_matches('\''),
+            defn.config.algorithm
+        ));
+    }
+    for agg in schema.aggregations() {
+        let intervals: Vec<_> = agg.intervals.iter().map(|i| i.as_str()).collect();
+        let aggregates: Vec<_> = agg
+            .aggregates
+            .iter()
+            .map(|aggregate| {
+                let arg = aggregate.arg.as_ref().map_or("", |arg| arg.name.as_str());
+                format!("{}: {}({})", aggregate.name, aggregate.func.as_str(), arg)
+            })
+            .collect();
+        details.push(format!(
+            "aggregation `{}` of timeseries `{}` by {}: {}",
+            agg.type_name(&schema),
+            agg.source_type(&schema).as_str(),
+            intervals.join(", "),
+            aggregates.join(", ")
+        ));
+    }
+    details
+}
+
+/// The parts of a subgraph manifest that we need to validate its schema
+type Manifest = BaseSubgraphManifest<(), ManifestSchema, IgnoredAny, IgnoredAny>;
+
+#[derive(Deserialize)]
+struct ManifestSchema {
+    file: ManifestLink,
+}
+
+/// Read the subgraph manifest at `path` and validate the schema it
+/// references using the spec version from the manifest
+fn parse_manifest(path: &str, opt: &Opts) -> Outcome {
+    let name = input_name(path);
+    let manifest_error =
+        |msg: String| Outcome::new(name, None, Status::ManifestError).with_message(msg);
+
+    let raw = match read_to_string(path, opt.gzip) {
+        Ok(raw) => raw,
+        Err(e) => return manifest_error(format!("failed to read manifest: {}", e)),
+    };
+    let manifest: Manifest = match parse_undeployed_manifest(&raw) {
+        Ok(manifest) => manifest,
+        Err(e) => return manifest_error(format!("invalid manifest: {}", e)),
+    };
+
+    if !opt.spec_versions().contains(&manifest.spec_version) {
+        return manifest_error(format!(
+            "the manifest uses unknown spec version {}",
+            manifest.spec_version
+        ));
+    }
+
+    let schema_path = match manifest.schema.file {
+        ManifestLink::Path(file) => Path::new(path)
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(file),
+        ManifestLink::Link(link) => {
+            return manifest_error(format!(
+                "the schema is the IPFS link `{}` but only local files are supported",
+                link.link
+            ))
+        }
+    };
+    let raw_schema = match fs::read_to_string(&schema_path) {
+        Ok(raw_schema) => raw_schema,
+        Err(e) => {
+            return manifest_error(format!(
+                "failed to read schema file {}: {}",
+                schema_path.display(),
+                e
+            ))
+        }
+    };
+
+    parse_with_version(&raw_schema, name, &manifest.spec_version, opt)
+}
+
+/// How many entries each thread validates at a time in batch mode
+const JOB_CHUNK_SIZE: usize = 64;
+
+/// Validate a batch entry, turning a panic into a failed outcome
+fn parse_entry(entry: &Entry, opt: &Opts) -> Outcome {
+    let name = format!("sgd{}", entry.id);
+    let outcome = catch_unwind(AssertUnwindSafe(|| parse(&entry.schema, &name, opt)))
+        .unwrap_or_else(|panic| {
+            let msg = panic
+                .downcast_ref::<&str>()
+                .map(|msg| msg.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            Outcome::new(&name, None, Status::Panic).with_message(msg)
+        });
+    Outcome {
+        id: Some(entry.id),
+        ..outcome
+    }
+}
+
+/// Validate `entries` using up to `jobs` threads. The outcomes are returned
+/// in the same order as `entries`
+fn parse_entries(entries: &[Entry], opt: &Opts) -> Vec<Outcome> {
+    let jobs = opt.jobs;
+    if jobs <= 1 || entries.len() <= 1 {
+        return entries
+            .iter()
+            .map(|entry| parse_entry(entry, opt))
+            .collect();
+    }
 
-    let opt = Opts::parse();
+    let chunk_size = (entries.len() + jobs - 1) / jobs;
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = entries
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|entry| parse_entry(entry, opt))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("parse_entry catches panics"))
+            .collect()
+    })
+}
+
+/// Whether `line` in a batch file should be skipped because it is blank
+/// or a comment starting with `#`
+fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
 
-    if opt.batch {
+/// Validate and report all of `entries` and clear it. Return `false` if
+/// `reporter` says we should stop validating
+fn report_entries(entries: &mut Vec<Entry>, opt: &Opts, reporter: &mut Reporter) -> bool {
+    for outcome in parse_entries(entries, opt) {
+        if !reporter.report(&outcome) {
+            return false;
+        }
+    }
+    entries.clear();
+    true
+}
+
+/// Count the entries in the batch file `path` for `--progress`. That is
+/// only done for files that we can cheaply read twice, i.e., not for
+/// stdin or compressed files
+fn count_entries(path: &str) -> Option<usize> {
+    if path == STDIN || path.ends_with(".gz") {
+        return None;
+    }
+    let rdr = BufReader::new(File::open(path).ok()?);
+    let mut count = 0;
+    for line in rdr.lines() {
+        if !is_comment(&line.ok()?) {
+            count += 1;
+        }
+    }
+    Some(count)
+}
+
+/// Validate all the schemas given in `opt`. Stops early if `reporter`
+/// says so
+fn run(opt: &Opts, reporter: &mut Reporter) {
+    if let Some(progress) = &mut reporter.progress {
+        progress.total = opt
+            .schemas
+            .iter()
+            .map(|path| count_entries(path))
+            .sum::<Option<usize>>();
+        if let (Some(total), Some(size)) = (&mut progress.total, opt.sample) {
+            *total = (*total).min(size);
+        }
+    }
+    if let Some(size) = opt.sample {
+        run_sample(opt, reporter, size);
+    } else if opt.batch {
         for schema in &opt.schemas {
-            println!("Validating schemas from {schema}");
-            let file = File::open(schema).expect("file exists");
-            let rdr = BufReader::new(file);
-            for line in rdr.lines() {
-                let line = line.expect("invalid line").replace("\\\\", "\\");
-                let entry = serde_json::from_str::<Entry>(&line).expect("line is valid json");
+            reporter.announce(&format!("Validating schemas from {}", input_name(schema)));
+            let rdr = open(schema, opt.gzip).expect("file exists");
+            // With multiple jobs, validate a chunk of entries at a time so
+            // that we never need to hold the whole file in memory
+            let batch_size = if opt.jobs > 1 {
+                opt.jobs * JOB_CHUNK_SIZE
+            } else {
+                1
+            };
+            let mut entries = Vec::with_capacity(batch_size);
+            for (idx, line) in rdr.lines().enumerate() {
+                let entry = match line {
+                    Ok(line) if is_comment(&line) => continue,
+                    Ok(line) => Entry::parse(&line.replace("\\\\", "\\"), opt),
+                    Err(e) => Err(e.to_string()),
+                };
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        // Report the entries before this line first so
+                        // that outcomes stay in the order of the input
+                        if !report_entries(&mut entries, opt, reporter) {
+                            return;
+                        }
+                        let name = format!("{}:{}", input_name(schema), idx + 1);
+                        let outcome = Outcome::new(&name, None, Status::ParseError)
+                            .with_message(format!("invalid batch entry: {}", e));
+                        if !reporter.report(&outcome) {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+                if !opt.selects(&entry) {
+                    continue;
+                }
+                entries.push(entry);
 
-                let raw = &entry.schema;
-                let name = format!("sgd{}", entry.id);
-                parse(raw, &name, opt.api);
+                if entries.len() >= batch_size && !report_entries(&mut entries, opt, reporter) {
+                    return;
+                }
+            }
+            if !report_entries(&mut entries, opt, reporter) {
+                return;
+            }
+        }
+    } else if opt.manifest {
+        for manifest in &opt.schemas {
+            reporter.announce(&format!(
+                "Validating schema from manifest {}",
+                input_name(manifest)
+            ));
+            if !reporter.report(&parse_manifest(manifest, opt)) {
+                return;
+            }
+        }
+    } else if opt.merge {
+        let mut names = Vec::new();
+        let mut raw = String::new();
+        for path in &opt.schemas {
+            for schema in schema_files(path, &opt.ext).expect("directory can be read") {
+                match read_schema(&schema, opt.gzip) {
+                    Ok(contents) => raw.push_str(&contents),
+                    Err(e) => {
+                        let outcome = Outcome::new(input_name(&schema), None, Status::ParseError)
+                            .with_message(e);
+                        reporter.report(&outcome);
+                        return;
+                    }
+                }
+                names.push(input_name(&schema).to_string());
+                raw.push('\n');
             }
         }
+        let name = format!("merged({})", names.join(", "));
+        reporter.announce(&format!("Validating schema from {}", name));
+        reporter.report(&parse(&raw, &name, opt));
     } else {
-        for schema in &opt.schemas {
-            println!("Validating schema from {schema}");
-            let raw = std::fs::read_to_string(schema).expect("file exists");
-            parse(&raw, schema, opt.api);
+        for path in &opt.schemas {
+            for schema in schema_files(path, &opt.ext).expect("directory can be read") {
+                reporter.announce(&format!("Validating schema from {}", input_name(&schema)));
+                let outcome = if is_url(&schema) {
+                    match fetch(&schema, opt) {
+                        Ok(raw) => parse(&raw, &schema, opt),
+                        Err(e) => Outcome::new(&schema, None, Status::FetchError).with_message(e),
+                    }
+                } else {
+                    match read_schema(&schema, opt.gzip) {
+                        Ok(raw) => {
+                            let mut outcome = parse(&raw, input_name(&schema), opt);
+                            if opt.fix {
+                                fix(&schema, &raw, &mut outcome, opt);
+                            }
+                            outcome
+                        }
+                        Err(e) => Outcome::new(input_name(&schema), None, Status::ParseError)
+                            .with_message(e),
+                    }
+                };
+                if !reporter.report(&outcome) {
+                    return;
+                }
+            }
         }
     }
 }
+
+/// Rewrite the file `path` with the canonical form of the schema `raw` if
+/// `outcome` says that it is valid and the canonical form differs from
+/// `raw`. Since the canonical form of a schema in canonical form is the
+/// schema itself, files are only ever rewritten once
+fn fix(path: &str, raw: &str, outcome: &mut Outcome, opt: &Opts) {
+    if !outcome.is_ok() || path == STDIN || path.ends_with(".gz") {
+        return;
+    }
+    let document = parse_schema(raw)
+        .expect("schema was validated")
+        .into_static();
+    let schema = InputSchema::parse_with_options(
+        &opt.spec_version,
+        raw,
+        subgraph_id(&document),
+        &opt.options(&opt.spec_version),
+    )
+    .expect("schema was validated");
+    let canonical = schema.to_canonical_sdl();
+    if canonical != raw {
+        ensure(
+            fs::write(path, &canonical),
+            &format!("failed to rewrite {}", path),
+        );
+        outcome.fixed = true;
+    }
+}
+
+/// Validate a random sample of `size` entries from the batch files in
+/// `opt`, chosen with reservoir sampling. Lines that are not valid batch
+/// entries take part in the sampling like any other entry. Outcomes are
+/// reported in the order of the input
+fn run_sample(opt: &Opts, reporter: &mut Reporter, size: usize) {
+    let mut rng = match opt.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    // The sampled entries with their position among all the entries we
+    // have seen so far
+    let mut reservoir: Vec<(usize, Result<Entry, Outcome>)> = Vec::with_capacity(size);
+    let mut seen = 0;
+    for schema in &opt.schemas {
+        reporter.announce(&format!("Sampling schemas from {}", input_name(schema)));
+        let rdr = open(schema, opt.gzip).expect("file exists");
+        for (idx, line) in rdr.lines().enumerate() {
+            let entry = match line {
+                Ok(line) if is_comment(&line) => continue,
+                Ok(line) => Entry::parse(&line.replace("\\\\", "\\"), opt),
+                Err(e) => Err(e.to_string()),
+            };
+            let entry = match entry {
+                Ok(entry) if !opt.selects(&entry) => continue,
+                Ok(entry) => Ok(entry),
+                Err(e) => {
+                    let name = format!("{}:{}", input_name(schema), idx + 1);
+                    Err(Outcome::new(&name, None, Status::ParseError)
+                        .with_message(format!("invalid batch entry: {}", e)))
+                }
+            };
+            if reservoir.len() < size {
+                reservoir.push((seen, entry));
+            } else {
+                let pos = rng.gen_range(0..=seen);
+                if pos < size {
+                    reservoir[pos] = (seen, entry);
+                }
+            }
+            seen += 1;
+        }
+    }
+    reporter.announce(&format!(
+        "Validating {} of {} schemas",
+        reservoir.len(),
+        seen
+    ));
+
+    reservoir.sort_by_key(|(pos, _)| *pos);
+    let mut entries = Vec::new();
+    for (_, entry) in reservoir {
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(outcome) => {
+                if !report_entries(&mut entries, opt, reporter) || !reporter.report(&outcome) {
+                    return;
+                }
+            }
+        }
+    }
+    report_entries(&mut entries, opt, reporter);
+}
+
+/// Read and parse the query from `--query`
+fn load_query(path: &str) -> q::Document {
+    let raw = ensure(
+        fs::read_to_string(path),
+        &format!("failed to read query {}", path),
+    );
+    ensure(
+        graphql_parser::parse_query(&raw),
+        &format!("failed to parse query {}", path),
+    )
+    .into_static()
+}
+
+/// Read and validate the schema from `--graft-base` or `--diff-api`;
+/// `what` describes the schema for error messages
+fn load_base(path: &str, what: &str, opt: &Opts) -> InputSchema {
+    let raw = ensure(
+        read_to_string(path, opt.gzip),
+        &format!("failed to read {} {}", what, path),
+    );
+    let document = ensure(
+        parse_schema(&raw),
+        &format!("failed to parse {} {}", what, path),
+    );
+    let id = subgraph_id(&document.into_static());
+    ensure(
+        InputSchema::parse_with_options(
+            &opt.spec_version,
+            &raw,
+            id,
+            &opt.options(&opt.spec_version),
+        ),
+        &format!("the {} {} is not valid", what, path),
+    )
+}
+
+pub fn main() {
+    let mut opt = Opts::parse();
+    opt.spec_registry = opt.spec_versions_file.as_deref().map(load_spec_versions);
+    ensure(
+        check_spec_version(&opt.spec_version, &opt.spec_versions()),
+        "invalid --spec-version",
+    );
+    opt.graft_base_schema = opt
+        .graft_base
+        .as_ref()
+        .map(|path| load_base(path, "graft base", &opt));
+    opt.diff_api_schema = opt.diff_api.as_ref().map(|path| {
+        ensure(
+            load_base(path, "API diff base", &opt).api_schema(),
+            &format!("failed to generate the API schema for {}", path),
+        )
+    });
+    opt.query_document = opt.query.as_deref().map(load_query);
+    if opt.dot.is_some()
+        && !opt.merge
+        && (opt.schemas.len() != 1 || Path::new(&opt.schemas[0]).is_dir())
+    {
+        usage("--dot can only be used with a single input schema or with --merge");
+    }
+    if opt.fix && (opt.batch || opt.manifest || opt.merge || opt.sample.is_some()) {
+        usage("--fix can only be used with schema files");
+    }
+
+    let mut reporter = Reporter::new(&opt);
+    run(&opt, &mut reporter);
+    reporter.finish();
+
+    if reporter.failed() {
+        exit(reporter.exit_code());
+    }
+}
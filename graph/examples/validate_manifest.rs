@@ -0,0 +1,439 @@
+/// Validate subgraph manifests without deploying them by checking that
+/// the manifest can be parsed, that its schema is valid, and that the data
+/// sources and templates are consistent with the schema and the spec
+/// version the manifest declares
+///
+/// In batch mode, the input files must be JSONL files where each line has
+/// an `id`, the `manifest` as YAML, and its `schema`. They can be generated
+/// by running this script against graph-node shard(s). Before running it,
+/// change the `dbs` variable to list all databases against which it should
+/// run. Since ABIs are not stored in the database, batch mode can not check
+/// that ABI files can be loaded.
+///
+/// ```
+/// #! /bin/bash
+///
+/// read -r -d '' query <<EOF
+/// \copy (select to_jsonb(a.*) from (select id, raw_yaml as manifest, schema from subgraphs.subgraph_manifest where raw_yaml is not null) a) to '%s'
+/// EOF
+///
+/// dbs="shard1 shard2 .."
+///
+/// dir=/var/tmp/manifests
+/// mkdir -p $dir
+///
+/// for db in $dbs
+/// do
+///     echo "Dump $db"
+///     q=$(printf "$query" "$dir/$db.json")
+///     psql -qXt service=$db -c "$q"
+///     sed -r -i -e 's/\\\\/\\/g' "$dir/$db.json"
+/// done
+///
+/// ```
+use clap::Parser;
+
+use graph::data::subgraph::{
+    parse_undeployed_manifest, BaseSubgraphManifest, DifferentMappingApiVersions, ManifestLink,
+    API_VERSION_0_0_5, SPEC_VERSIONS, SPEC_VERSION_0_0_7, SPEC_VERSION_0_0_9, SPEC_VERSION_1_0_0,
+};
+use graph::data_source::offchain::OFFCHAIN_KINDS;
+use graph::env::ENV_VARS;
+use graph::schema::{InputSchema, ValidationOptions};
+use graph::semver::Version;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::exit;
+
+pub fn usage(msg: &str) -> ! {
+    println!("{}", msg);
+    println!("usage: validate_manifest subgraph.yaml ...");
+    println!("\nValidate subgraph manifests");
+    std::process::exit(1);
+}
+
+pub fn ensure<T, E: std::fmt::Display>(res: Result<T, E>, msg: &str) -> T {
+    match res {
+        Ok(ok) => ok,
+        Err(err) => {
+            eprintln!("{}:\n    {}", msg, err);
+            exit(1)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    id: i32,
+    manifest: String,
+    schema: String,
+}
+
+/// The parts of a subgraph manifest that we check
+type Manifest = BaseSubgraphManifest<(), ManifestSchema, DataSource, DataSource>;
+
+#[derive(Deserialize)]
+struct ManifestSchema {
+    file: ManifestLink,
+}
+
+/// A data source or a template. All the chains that graph-node supports
+/// declare them with the same basic structure
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataSource {
+    kind: String,
+    name: String,
+    #[serde(default)]
+    source: Source,
+    mapping: Mapping,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Source {
+    abi: Option<String>,
+    end_block: Option<IgnoredAny>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Mapping {
+    api_version: Version,
+    #[serde(default)]
+    entities: Vec<String>,
+    #[serde(default)]
+    abis: Vec<MappingAbi>,
+}
+
+#[derive(Deserialize)]
+struct MappingAbi {
+    name: String,
+    file: ManifestLink,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Ok,
+    /// The manifest can not be read or parsed
+    ParseError,
+    /// The manifest can be parsed, but has problems
+    Invalid,
+}
+
+/// The result of validating one manifest
+#[derive(Debug, Serialize)]
+struct Outcome {
+    /// The `id` of the entry in batch mode
+    id: Option<i32>,
+    name: String,
+    status: Status,
+    /// Everything that is wrong with the manifest
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    problems: Vec<String>,
+}
+
+impl Outcome {
+    fn new(name: &str, problems: Vec<String>) -> Self {
+        let status = if problems.is_empty() {
+            Status::Ok
+        } else {
+            Status::Invalid
+        };
+        Outcome {
+            id: None,
+            name: name.to_string(),
+            status,
+            problems,
+        }
+    }
+
+    fn parse_error(name: &str, msg: String) -> Self {
+        Outcome {
+            status: Status::ParseError,
+            ..Outcome::new(name, vec![msg])
+        }
+    }
+
+    fn is_ok(&self) -> bool {
+        self.status == Status::Ok
+    }
+
+    fn print(&self, json: bool) {
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string(self).expect("outcomes can be serialized")
+            );
+            return;
+        }
+        match self.status {
+            Status::Ok => println!("Manifest {}: OK", self.name),
+            Status::ParseError => println!("Failed to parse manifest {}:", self.name),
+            Status::Invalid => println!("Manifest {}:", self.name),
+        }
+        for problem in &self.problems {
+            println!("    {}", problem);
+        }
+    }
+}
+
+#[derive(Parser)]
+#[clap(
+    name = "validate_manifest",
+    version = env!("CARGO_PKG_VERSION"),
+    author = env!("CARGO_PKG_AUTHORS"),
+    about = "Validate subgraph manifests"
+)]
+struct Opts {
+    /// Validate a batch of manifests in bulk. When this is set, the input
+    /// files must be JSONL files where each line has an `id`, a `manifest`,
+    /// and a `schema`. Blank lines and lines starting with `#` are skipped
+    #[clap(short, long)]
+    batch: bool,
+    /// Validate the schema in strict mode, see the `validate` example
+    #[clap(long)]
+    strict: bool,
+    /// Print one JSON object per manifest instead of human-readable text
+    #[clap(long)]
+    json: bool,
+    /// Only print the manifests that fail validation
+    #[clap(short, long)]
+    quiet: bool,
+    /// Subgraph manifests to validate
+    #[clap(required = true)]
+    manifests: Vec<String>,
+}
+
+/// Validate the manifest `raw`. Files that the manifest references are
+/// looked up relative to `base`; without a `base`, `schema` must be given
+/// and ABIs are not checked
+fn validate(
+    raw: &str,
+    name: &str,
+    base: Option<&Path>,
+    schema: Option<&str>,
+    opt: &Opts,
+) -> Outcome {
+    let manifest: Manifest = match parse_undeployed_manifest(raw) {
+        Ok(manifest) => manifest,
+        Err(e) => return Outcome::parse_error(name, format!("invalid manifest: {}", e)),
+    };
+
+    let mut problems = Vec::new();
+    let spec_version = &manifest.spec_version;
+    if !SPEC_VERSIONS.contains(spec_version) {
+        problems.push(format!(
+            "the manifest uses unknown spec version {}",
+            spec_version
+        ));
+    }
+
+    let schema = match (schema, base) {
+        (Some(schema), _) => Ok(schema.to_string()),
+        (None, Some(base)) => match manifest.schema.file.path(base) {
+            Some(path) => fs::read_to_string(&path)
+                .map_err(|e| format!("failed to read schema file {}: {}", path.display(), e)),
+            None => Err("the schema is an IPFS link but only local files are supported".into()),
+        },
+        (None, None) => unreachable!("either the schema or the base directory is known"),
+    };
+    let schema = schema.and_then(|schema| {
+        let options = ValidationOptions {
+            strict: opt.strict,
+            allow_fulltext: true,
+            features: None,
+            substreams: false,
+        };
+        InputSchema::parse_with_options(spec_version, &schema, manifest.id.clone(), &options)
+            .map_err(|e| format!("invalid schema: {}", e))
+    });
+    let schema = match schema {
+        Ok(schema) => Some(schema),
+        Err(e) => {
+            problems.push(e);
+            None
+        }
+    };
+
+    if manifest.data_sources.is_empty() {
+        problems.push("the manifest has no data sources".to_string());
+    }
+    let data_sources = manifest
+        .data_sources
+        .iter()
+        .map(|ds| ("data source", ds))
+        .chain(manifest.templates.iter().map(|ds| ("template", ds)));
+    for (what, ds) in data_sources {
+        let mut problem = |msg: String| problems.push(format!("{} `{}`: {}", what, ds.name, msg));
+
+        if let Some(schema) = &schema {
+            for entity in &ds.mapping.entities {
+                if schema.entity_type(entity.as_str()).is_err() {
+                    problem(format!("entity `{}` is not defined in the schema", entity));
+                }
+            }
+        }
+
+        if let Some(abi) = &ds.source.abi {
+            if !ds
+                .mapping
+                .abis
+                .iter()
+                .any(|mapping_abi| &mapping_abi.name == abi)
+            {
+                problem(format!(
+                    "the source ABI `{}` is not one of the ABIs of the mapping",
+                    abi
+                ));
+            }
+        }
+        if let Some(base) = base {
+            for abi in &ds.mapping.abis {
+                if let Some(path) = abi.file.path(base) {
+                    let res = File::open(&path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|file| {
+                            ethabi::Contract::load(BufReader::new(file)).map_err(|e| e.to_string())
+                        });
+                    if let Err(e) = res {
+                        problem(format!(
+                            "failed to load ABI `{}` from {}: {}",
+                            abi.name,
+                            path.display(),
+                            e
+                        ));
+                    }
+                }
+            }
+        }
+
+        let max_api_version = &ENV_VARS.mappings.max_api_version;
+        if &ds.mapping.api_version > max_api_version {
+            problem(format!(
+                "uses mapping API version {} but graph-node supports at most {}",
+                ds.mapping.api_version, max_api_version
+            ));
+        }
+        if spec_version < &SPEC_VERSION_0_0_7 && OFFCHAIN_KINDS.contains_key(ds.kind.as_str()) {
+            problem(format!(
+                "offchain data sources are not supported prior to spec version {}",
+                SPEC_VERSION_0_0_7
+            ));
+        }
+        if spec_version < &SPEC_VERSION_0_0_9 && ds.source.end_block.is_some() {
+            problem(format!(
+                "`endBlock` is not supported prior to spec version {}",
+                SPEC_VERSION_0_0_9
+            ));
+        }
+    }
+
+    // Same rule as `UnifiedMappingApiVersion`: once one mapping uses API
+    // version 0.0.5 or later, all mappings must use the same version
+    let api_versions: BTreeSet<_> = manifest
+        .data_sources
+        .iter()
+        .chain(manifest.templates.iter())
+        .map(|ds| ds.mapping.api_version.clone())
+        .collect();
+    if api_versions.len() > 1 && api_versions.iter().any(|v| v >= &API_VERSION_0_0_5) {
+        problems.push(DifferentMappingApiVersions(api_versions).to_string());
+    }
+
+    if spec_version < &SPEC_VERSION_1_0_0 && manifest.indexer_hints.is_some() {
+        problems.push(format!(
+            "`indexerHints` are not supported prior to spec version {}",
+            SPEC_VERSION_1_0_0
+        ));
+    }
+
+    Outcome::new(name, problems)
+}
+
+/// Read the manifest at `path` and validate it together with the files it
+/// references
+fn validate_file(path: &str, opt: &Opts) -> Outcome {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) => return Outcome::parse_error(path, format!("failed to read manifest: {}", e)),
+    };
+    let base = Path::new(path).parent().unwrap_or_else(|| Path::new("."));
+    validate(&raw, path, Some(base), None, opt)
+}
+
+fn validate_entry(entry: &Entry, opt: &Opts) -> Outcome {
+    let name = format!("sgd{}", entry.id);
+    Outcome {
+        id: Some(entry.id),
+        ..validate(&entry.manifest, &name, None, Some(&entry.schema), opt)
+    }
+}
+
+/// Whether `line` in a batch file should be skipped because it is blank
+/// or a comment starting with `#`
+fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+pub fn main() {
+    let opt = Opts::parse();
+
+    let mut outcomes = Vec::new();
+    let mut report = |outcome: Outcome| {
+        if !opt.quiet || !outcome.is_ok() {
+            outcome.print(opt.json);
+        }
+        outcomes.push(outcome.is_ok());
+    };
+
+    for path in &opt.manifests {
+        if opt.batch {
+            let file = ensure(File::open(path), &format!("failed to open {}", path));
+            for (idx, line) in BufReader::new(file).lines().enumerate() {
+                let entry = match line {
+                    Ok(line) if is_comment(&line) => continue,
+                    Ok(line) => serde_json::from_str::<Entry>(&line.replace("\\\\", "\\"))
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                match entry {
+                    Ok(entry) => report(validate_entry(&entry, &opt)),
+                    Err(e) => {
+                        let name = format!("{}:{}", path, idx + 1);
+                        report(Outcome::parse_error(
+                            &name,
+                            format!("invalid batch entry: {}", e),
+                        ));
+                    }
+                }
+            }
+        } else {
+            if !Path::new(path).is_file() {
+                usage(&format!("{} is not a file", path));
+            }
+            report(validate_file(path, &opt));
+        }
+    }
+
+    let failed = outcomes.iter().filter(|ok| !**ok).count();
+    if !opt.json {
+        println!(
+            "Validated {} manifests: {} ok, {} failed",
+            outcomes.len(),
+            outcomes.len() - failed,
+            failed
+        );
+    }
+    if failed > 0 {
+        exit(1);
+    }
+}
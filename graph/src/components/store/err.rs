@@ -47,8 +47,6 @@ pub enum StoreError {
     DeploymentNotFound(String),
     #[error("shard not found: {0} (this usually indicates a misconfiguration)")]
     UnknownShard(String),
-    #[error("Fulltext search not yet deterministic")]
-    FulltextSearchNonDeterministic,
     #[error("operation was canceled")]
     Canceled,
     #[error("database unavailable")]
@@ -110,7 +108,6 @@ impl Clone for StoreError {
             Self::ConstraintViolation(arg0) => Self::ConstraintViolation(arg0.clone()),
             Self::DeploymentNotFound(arg0) => Self::DeploymentNotFound(arg0.clone()),
             Self::UnknownShard(arg0) => Self::UnknownShard(arg0.clone()),
-            Self::FulltextSearchNonDeterministic => Self::FulltextSearchNonDeterministic,
             Self::Canceled => Self::Canceled,
             Self::DatabaseUnavailable => Self::DatabaseUnavailable,
             Self::DatabaseDisabled => Self::DatabaseDisabled,
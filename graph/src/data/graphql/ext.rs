@@ -5,7 +5,7 @@ use crate::prelude::s::{
     Definition, Directive, Document, EnumType, Field, InterfaceType, ObjectType, Type,
     TypeDefinition, Value,
 };
-use crate::prelude::{ValueType, ENV_VARS};
+use crate::prelude::ValueType;
 use crate::schema::{META_FIELD_TYPE, SCHEMA_TYPE_NAME};
 use std::collections::{BTreeMap, HashMap};
 
@@ -47,7 +47,7 @@ pub trait DocumentExt {
 
     fn find_interface(&self, name: &str) -> Option<&InterfaceType>;
 
-    fn get_fulltext_directives(&self) -> Result<Vec<&Directive>, anyhow::Error>;
+    fn get_fulltext_directives(&self) -> Vec<&Directive>;
 
     fn get_root_query_type(&self) -> Option<&ObjectType>;
 
@@ -126,22 +126,15 @@ impl DocumentExt for Document {
         })
     }
 
-    fn get_fulltext_directives(&self) -> Result<Vec<&Directive>, anyhow::Error> {
-        let directives = self.get_object_type_definition(SCHEMA_TYPE_NAME).map_or(
-            vec![],
-            |subgraph_schema_type| {
+    fn get_fulltext_directives(&self) -> Vec<&Directive> {
+        self.get_object_type_definition(SCHEMA_TYPE_NAME)
+            .map_or(vec![], |subgraph_schema_type| {
                 subgraph_schema_type
                     .directives
                     .iter()
                     .filter(|directives| directives.name.eq("fulltext"))
                     .collect()
-            },
-        );
-        if !ENV_VARS.allow_non_deterministic_fulltext_search && !directives.is_empty() {
-            Err(anyhow::anyhow!("Fulltext search is not yet deterministic"))
-        } else {
-            Ok(directives)
-        }
+            })
     }
 
     /// Returns the root query type (if there is one).
@@ -98,7 +98,8 @@ impl<'a> TryFrom<&s::ObjectType> for IdType {
             "Bytes" => Ok(IdType::Bytes),
             "Int8" => Ok(IdType::Int8),
             s => Err(anyhow!(
-                "Entity type {} uses illegal type {} for id column",
+                "Entity type {} uses illegal type {} for id column; the id must be \
+                 one of `ID`, `String`, `Bytes`, or `Int8`",
                 obj_type.name,
                 s
             )),
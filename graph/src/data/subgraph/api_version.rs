@@ -1,6 +1,7 @@
 use itertools::Itertools;
 use semver::Version;
 use std::collections::BTreeSet;
+use std::str::FromStr;
 use thiserror::Error;
 
 pub const API_VERSION_0_0_2: Version = Version::new(0, 0, 2);
@@ -53,6 +54,97 @@ pub const LATEST_VERSION: &Version = &SPEC_VERSION_1_1_0;
 
 pub const MIN_SPEC_VERSION: Version = Version::new(0, 0, 2);
 
+/// All the spec versions that graph-node knows about, in increasing order
+pub const SPEC_VERSIONS: [Version; 10] = [
+    MIN_SPEC_VERSION,
+    SPEC_VERSION_0_0_3,
+    SPEC_VERSION_0_0_4,
+    SPEC_VERSION_0_0_5,
+    SPEC_VERSION_0_0_6,
+    SPEC_VERSION_0_0_7,
+    SPEC_VERSION_0_0_8,
+    SPEC_VERSION_0_0_9,
+    SPEC_VERSION_1_0_0,
+    SPEC_VERSION_1_1_0,
+];
+
+/// Features that a subgraph schema can use and that might require a
+/// minimum spec version. Any feature that is gated by a spec version must
+/// be listed here
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchemaFeature {
+    FullTextSearch,
+    ImmutableEntities,
+    Aggregations,
+    Timeseries,
+    Int8Id,
+}
+
+impl SchemaFeature {
+    pub const ALL: [SchemaFeature; 5] = [
+        SchemaFeature::FullTextSearch,
+        SchemaFeature::ImmutableEntities,
+        SchemaFeature::Aggregations,
+        SchemaFeature::Timeseries,
+        SchemaFeature::Int8Id,
+    ];
+
+    /// The name of this feature, e.g., for listing features in
+    /// configuration files
+    pub fn as_str(&self) -> &'static str {
+        use SchemaFeature::*;
+
+        match self {
+            FullTextSearch => "fullTextSearch",
+            ImmutableEntities => "immutableEntities",
+            Aggregations => "aggregations",
+            Timeseries => "timeseries",
+            Int8Id => "int8Id",
+        }
+    }
+
+    /// The lowest spec version that supports this feature
+    pub fn min_spec_version(&self) -> Version {
+        use SchemaFeature::*;
+
+        match self {
+            FullTextSearch | ImmutableEntities => MIN_SPEC_VERSION,
+            Aggregations | Timeseries | Int8Id => SPEC_VERSION_1_1_0,
+        }
+    }
+
+    /// Return `true` if `spec_version` supports this feature
+    pub fn is_supported_by(&self, spec_version: &Version) -> bool {
+        spec_version >= &self.min_spec_version()
+    }
+
+    /// The lowest mapping API version, i.e., the `apiVersion` of a data
+    /// source's mapping, with which mappings can write entities that use
+    /// this feature. Int8 ids and timeseries, which have them, as well as
+    /// aggregations of timeseries need mappings that can set `Int8`
+    /// values, and `graph-ts` only supports that for API version 0.0.7
+    /// and later
+    pub fn min_api_version(&self) -> Version {
+        use SchemaFeature::*;
+
+        match self {
+            FullTextSearch | ImmutableEntities => API_VERSION_0_0_2,
+            Aggregations | Timeseries | Int8Id => API_VERSION_0_0_7,
+        }
+    }
+}
+
+impl FromStr for SchemaFeature {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SchemaFeature::ALL
+            .into_iter()
+            .find(|feature| feature.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown schema feature `{}`", s))
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct UnifiedMappingApiVersion(Option<Version>);
 
@@ -112,15 +112,7 @@ fn detect_grafting<C: Blockchain>(manifest: &SubgraphManifest<C>) -> Option<Subg
 }
 
 fn detect_full_text_search(schema: &InputSchema) -> Option<SubgraphFeature> {
-    match schema.get_fulltext_directives() {
-        Ok(directives) => (!directives.is_empty()).then_some(SubgraphFeature::FullTextSearch),
-
-        Err(_) => {
-            // Currently we return an error from `get_fulltext_directives` function if the
-            // fullTextSearch directive is found.
-            Some(SubgraphFeature::FullTextSearch)
-        }
-    }
+    (!schema.get_fulltext_directives().is_empty()).then_some(SubgraphFeature::FullTextSearch)
 }
 
 pub struct InvalidMapping;
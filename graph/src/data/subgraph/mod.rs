@@ -26,6 +26,7 @@ use stable_hash_legacy::SequenceNumber;
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
     marker::PhantomData,
+    path::{Path, PathBuf},
 };
 use thiserror::Error;
 use wasmparser;
@@ -129,6 +130,26 @@ impl DeploymentHash {
         Ok(DeploymentHash(s))
     }
 
+    /// Create a `DeploymentHash` from an IPFS link like `/ipfs/Qm..` or
+    /// `ipfs://Qm..`, or from a bare hash. The hash must be a valid CID,
+    /// either a v0 (`Qm..`) or a v1 (`bafy..`) CID. Since v1 CIDs are
+    /// longer than what `new` allows, they are not subject to its length
+    /// limit. On invalid input, return `link` (as a `String`) as the error
+    pub fn from_ipfs_link(link: &str) -> Result<Self, String> {
+        let hash = link
+            .strip_prefix("/ipfs/")
+            .or_else(|| link.strip_prefix("ipfs://"))
+            .unwrap_or(link);
+
+        if cid::Cid::from_str(hash).is_err()
+            || !hash.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(link.to_string());
+        }
+
+        Ok(DeploymentHash(hash.to_string()))
+    }
+
     pub fn to_ipfs_link(&self) -> Link {
         Link {
             link: format!("/ipfs/{}", self),
@@ -401,6 +422,26 @@ impl<S: ToString> From<S> for Link {
     }
 }
 
+/// A file referenced from a manifest. Before a subgraph is deployed, that
+/// is the path to a local file, afterwards, it is an IPFS link
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestLink {
+    Path(String),
+    Link(Link),
+}
+
+impl ManifestLink {
+    /// The path of the file relative to the directory `base` that
+    /// contains the manifest, or `None` if this is an IPFS link
+    pub fn path(&self, base: &Path) -> Option<PathBuf> {
+        match self {
+            ManifestLink::Path(file) => Some(base.join(file)),
+            ManifestLink::Link(_) => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
 pub struct UnresolvedSchema {
     pub file: Link,
@@ -854,6 +895,17 @@ impl<C: Blockchain> SubgraphManifest<C> {
     }
 }
 
+/// Parse the manifest `raw` of a subgraph that has not been deployed and
+/// therefore has no id yet. Like `UnresolvedSubgraphManifest::parse`, this
+/// injects an id, but uses the placeholder `unknown` for it
+pub fn parse_undeployed_manifest<M: de::DeserializeOwned>(
+    raw: &str,
+) -> Result<M, serde_yaml::Error> {
+    let mut raw: serde_yaml::Mapping = serde_yaml::from_str(raw)?;
+    raw.insert("id".into(), "unknown".into());
+    serde_yaml::from_value(raw.into())
+}
+
 impl<C: Blockchain> UnresolvedSubgraphManifest<C> {
     pub fn parse(
         id: DeploymentHash,
@@ -1079,6 +1131,37 @@ fn test_subgraph_name_validation() {
     assert!(SubgraphName::new("this-component-is-very-long-but-we-dont-care").is_ok());
 }
 
+#[test]
+fn test_deployment_hash_from_ipfs_link() {
+    const V0: &str = "QmWmyoMoctfbAaiEs2G46gpeUmhqFRDW6KWo64y5r581Vz";
+    const V1: &str = "bafyreibjo4xmgaevkgud7mbifn3dzp4v4lyaui4yvqp3f2bqwtxcjrdqg4";
+
+    for hash in [V0, V1] {
+        for link in [
+            hash.to_string(),
+            format!("/ipfs/{}", hash),
+            format!("ipfs://{}", hash),
+        ] {
+            assert_eq!(
+                hash,
+                DeploymentHash::from_ipfs_link(&link).unwrap().as_str()
+            );
+        }
+    }
+    assert_eq!(
+        DeploymentHash::new(V0),
+        DeploymentHash::from_ipfs_link(&format!("/ipfs/{}", V0))
+    );
+
+    let ipns = format!("/ipns/{}", V0);
+    for invalid in ["", "/ipfs/", "ipfs://", "Qm123", ipns.as_str(), "subgraphs"] {
+        assert_eq!(
+            Err(invalid.to_string()),
+            DeploymentHash::from_ipfs_link(invalid)
+        );
+    }
+}
+
 #[test]
 fn test_display_vector() {
     let manifest_validation_error = SubgraphRegistrarError::ManifestValidationError(vec![
@@ -5,7 +5,7 @@ use graphql_parser::Pos;
 use inflector::Inflector;
 use lazy_static::lazy_static;
 
-use crate::data::graphql::{ObjectOrInterface, ObjectTypeExt};
+use crate::data::graphql::{ObjectOrInterface, ObjectTypeExt, TypeExt};
 use crate::data::store::IdType;
 use crate::schema::{ast, META_FIELD_NAME, META_FIELD_TYPE};
 
@@ -22,12 +22,48 @@ pub enum APISchemaError {
     TypeExists(String),
     #[error("Type {0} not found")]
     TypeNotFound(String),
-    #[error("Fulltext search is not yet deterministic")]
-    FulltextSearchNonDeterministic,
     #[error("Illegal type for `id`: {0}")]
     IllegalIdType(String),
 }
 
+/// A problem with a GraphQL query found by `ApiSchema::validate_query`
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum QueryValidationError {
+    #[error("Type `{0}` has no field `{1}`")]
+    UnknownField(String, String, Pos),
+    #[error("Field `{0}.{1}` has no argument `{2}`")]
+    UnknownArgument(String, String, String, Pos),
+    #[error("Field `{0}.{1}` requires the argument `{2}`")]
+    MissingArgument(String, String, String, Pos),
+    #[error("Field `{0}.{1}` has type `{2}` and must have a selection of subfields")]
+    MissingSelection(String, String, String, Pos),
+    #[error("Field `{0}.{1}` has type `{2}` and can not have a selection of subfields")]
+    UnexpectedSelection(String, String, String, Pos),
+    #[error("Unknown fragment `{0}`")]
+    UnknownFragment(String, Pos),
+    #[error("Unknown type `{0}` in type condition")]
+    UnknownType(String, Pos),
+    #[error("The schema does not support {0} operations")]
+    UnsupportedOperation(&'static str, Pos),
+}
+
+impl QueryValidationError {
+    /// The position in the query that the error is about
+    pub fn position(&self) -> Pos {
+        use QueryValidationError::*;
+        match self {
+            UnknownField(_, _, pos)
+            | UnknownArgument(_, _, _, pos)
+            | MissingArgument(_, _, _, pos)
+            | MissingSelection(_, _, _, pos)
+            | UnexpectedSelection(_, _, _, pos)
+            | UnknownFragment(_, pos)
+            | UnknownType(_, pos)
+            | UnsupportedOperation(_, pos) => *pos,
+        }
+    }
+}
+
 // The followoing types are defined in meta.graphql
 const BLOCK_HEIGHT: &str = "Block_height";
 const CHANGE_BLOCK_FILTER_NAME: &str = "BlockChangedFilter";
@@ -242,12 +278,283 @@ impl ApiSchema {
         }
     }
 
+    /// List the filters that can be passed in the `where` argument of each
+    /// field of the `Query` type, in the order in which the fields appear
+    /// in the `Query` type. Query fields without a `where` argument, like
+    /// the ones that look up a single entity by its id, are omitted
+    pub fn query_filters(&self) -> Vec<QueryFilters> {
+        self.query_type
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let arg = field.arguments.iter().find(|arg| arg.name == "where")?;
+                let filter_type = arg.value_type.get_base_type();
+                let filters = match self.get_named_type(filter_type)? {
+                    s::TypeDefinition::InputObject(input) => input
+                        .fields
+                        .iter()
+                        .map(|filter| (filter.name.clone(), filter.value_type.clone()))
+                        .collect(),
+                    _ => return None,
+                };
+                Some(QueryFilters {
+                    query_field: field.name.clone(),
+                    filter_type: filter_type.to_string(),
+                    filters,
+                })
+            })
+            .collect()
+    }
+
+    /// The number of fields of the `Query` type, including `_meta` and
+    /// the introspection fields `__schema` and `__type`
+    pub fn query_field_count(&self) -> usize {
+        self.query_type.fields.len()
+    }
+
+    /// Break the fields of the `Query` type down by what kind of query
+    /// they were generated for. The counts add up to `query_field_count`
+    pub fn query_field_counts(&self) -> QueryFieldCounts {
+        let mut counts = QueryFieldCounts::default();
+        for field in &self.query_type.fields {
+            if field.name == META_FIELD_NAME {
+                counts.meta += 1;
+            } else if is_introspection_field(&field.name) {
+                counts.introspection += 1;
+            } else if field.directives.iter().any(|dir| dir.name == "fulltext") {
+                counts.fulltext += 1;
+            } else if field.field_type.is_list() {
+                counts.plural += 1;
+            } else {
+                counts.singular += 1;
+            }
+        }
+        counts
+    }
+
+    /// Check that the fields, arguments, fragments, and type conditions
+    /// that `query` uses exist in this schema, that required arguments are
+    /// given, and that exactly the fields of object and interface types
+    /// have a selection of subfields. Argument values and variables are
+    /// not checked. All operations and fragments in `query` are checked,
+    /// and all errors are reported
+    pub fn validate_query(&self, query: &q::Document) -> Result<(), Vec<QueryValidationError>> {
+        let fragments: HashMap<&str, &q::FragmentDefinition> = query
+            .definitions
+            .iter()
+            .filter_map(|defn| match defn {
+                q::Definition::Fragment(fragment) => Some((fragment.name.as_str(), fragment)),
+                q::Definition::Operation(_) => None,
+            })
+            .collect();
+
+        let mut errors = Vec::new();
+        for defn in &query.definitions {
+            let (root_type, selection_set) = match defn {
+                q::Definition::Operation(q::OperationDefinition::SelectionSet(set)) => {
+                    (Some(self.query_type.as_ref()), set)
+                }
+                q::Definition::Operation(q::OperationDefinition::Query(query)) => {
+                    (Some(self.query_type.as_ref()), &query.selection_set)
+                }
+                q::Definition::Operation(q::OperationDefinition::Subscription(subscription)) => {
+                    let root_type = self.subscription_type.as_deref();
+                    if root_type.is_none() {
+                        errors.push(QueryValidationError::UnsupportedOperation(
+                            "subscription",
+                            subscription.position,
+                        ));
+                    }
+                    (root_type, &subscription.selection_set)
+                }
+                q::Definition::Operation(q::OperationDefinition::Mutation(mutation)) => {
+                    errors.push(QueryValidationError::UnsupportedOperation(
+                        "mutation",
+                        mutation.position,
+                    ));
+                    (None, &mutation.selection_set)
+                }
+                q::Definition::Fragment(fragment) => {
+                    let q::TypeCondition::On(name) = &fragment.type_condition;
+                    match self.object_or_interface(name) {
+                        Some(ty) => self.validate_selection_set(
+                            ty,
+                            &fragment.selection_set,
+                            &fragments,
+                            &mut errors,
+                        ),
+                        None => errors.push(QueryValidationError::UnknownType(
+                            name.clone(),
+                            fragment.position,
+                        )),
+                    }
+                    continue;
+                }
+            };
+            if let Some(root_type) = root_type {
+                self.validate_selection_set(
+                    ObjectOrInterface::Object(root_type),
+                    selection_set,
+                    &fragments,
+                    &mut errors,
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check `selection_set`, a selection of fields of `parent`, for
+    /// `validate_query`. Fragment spreads are only checked for whether the
+    /// fragment exists, since `validate_query` checks each fragment
+    /// definition on its own
+    fn validate_selection_set(
+        &self,
+        parent: ObjectOrInterface<'_>,
+        selection_set: &q::SelectionSet,
+        fragments: &HashMap<&str, &q::FragmentDefinition>,
+        errors: &mut Vec<QueryValidationError>,
+    ) {
+        use QueryValidationError as E;
+
+        for selection in &selection_set.items {
+            match selection {
+                q::Selection::Field(field) => {
+                    let has_selection = !field.selection_set.items.is_empty();
+                    if field.name == "__typename" {
+                        if has_selection {
+                            errors.push(E::UnexpectedSelection(
+                                parent.name().to_string(),
+                                field.name.clone(),
+                                "String!".to_string(),
+                                field.position,
+                            ));
+                        }
+                        continue;
+                    }
+                    let field_def = match parent.fields().iter().find(|f| f.name == field.name) {
+                        Some(field_def) => field_def,
+                        None => {
+                            errors.push(E::UnknownField(
+                                parent.name().to_string(),
+                                field.name.clone(),
+                                field.position,
+                            ));
+                            continue;
+                        }
+                    };
+                    for (name, _) in &field.arguments {
+                        if !field_def.arguments.iter().any(|arg| &arg.name == name) {
+                            errors.push(E::UnknownArgument(
+                                parent.name().to_string(),
+                                field.name.clone(),
+                                name.clone(),
+                                field.position,
+                            ));
+                        }
+                    }
+                    for arg in &field_def.arguments {
+                        let required = matches!(arg.value_type, s::Type::NonNullType(_))
+                            && arg.default_value.is_none();
+                        if required && !field.arguments.iter().any(|(name, _)| name == &arg.name) {
+                            errors.push(E::MissingArgument(
+                                parent.name().to_string(),
+                                field.name.clone(),
+                                arg.name.clone(),
+                                field.position,
+                            ));
+                        }
+                    }
+                    match self.object_or_interface(field_def.field_type.get_base_type()) {
+                        Some(ty) if has_selection => {
+                            self.validate_selection_set(ty, &field.selection_set, fragments, errors)
+                        }
+                        Some(_) => errors.push(E::MissingSelection(
+                            parent.name().to_string(),
+                            field.name.clone(),
+                            field_def.field_type.to_string(),
+                            field.position,
+                        )),
+                        None if has_selection => errors.push(E::UnexpectedSelection(
+                            parent.name().to_string(),
+                            field.name.clone(),
+                            field_def.field_type.to_string(),
+                            field.position,
+                        )),
+                        None => { /* a scalar or enum */ }
+                    }
+                }
+                q::Selection::FragmentSpread(spread) => {
+                    if !fragments.contains_key(spread.fragment_name.as_str()) {
+                        errors.push(E::UnknownFragment(
+                            spread.fragment_name.clone(),
+                            spread.position,
+                        ));
+                    }
+                }
+                q::Selection::InlineFragment(fragment) => {
+                    let ty = match &fragment.type_condition {
+                        Some(q::TypeCondition::On(name)) => match self.object_or_interface(name) {
+                            Some(ty) => ty,
+                            None => {
+                                errors.push(E::UnknownType(name.clone(), fragment.position));
+                                continue;
+                            }
+                        },
+                        None => parent,
+                    };
+                    self.validate_selection_set(ty, &fragment.selection_set, fragments, errors);
+                }
+            }
+        }
+    }
+
     #[cfg(debug_assertions)]
     pub fn definitions(&self) -> impl Iterator<Item = &s::Definition> {
         self.schema.document.definitions.iter()
     }
 }
 
+/// The filters that the `where` argument of a query field accepts, as
+/// returned by `ApiSchema::query_filters`
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryFilters {
+    /// The name of the field on the `Query` type, e.g., `users`
+    pub query_field: String,
+    /// The name of the input type of the `where` argument, e.g.,
+    /// `User_filter`
+    pub filter_type: String,
+    /// The name and type of each field of the filter type in the order in
+    /// which they are generated, e.g., `("name_gt", String)`
+    pub filters: Vec<(String, s::Type)>,
+}
+
+/// How many fields of each kind the `Query` type has, as returned by
+/// `ApiSchema::query_field_counts`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryFieldCounts {
+    /// Fields that look up one entity by its id, e.g., `user`
+    pub singular: usize,
+    /// Fields that return a list of entities, e.g., `users`
+    pub plural: usize,
+    /// Fields generated for `@fulltext` directives
+    pub fulltext: usize,
+    /// The `_meta` field
+    pub meta: usize,
+    /// The fields `__schema` and `__type`
+    pub introspection: usize,
+}
+
+impl QueryFieldCounts {
+    pub fn total(&self) -> usize {
+        self.singular + self.plural + self.fulltext + self.meta + self.introspection
+    }
+}
+
 lazy_static! {
     static ref INTROSPECTION_SCHEMA: Document = {
         let schema = include_str!("introspection.graphql");
@@ -845,9 +1152,8 @@ fn add_query_type(
         .collect::<Vec<Field>>();
     let mut fulltext_fields = schema
         .get_fulltext_directives()
-        .map_err(|_| APISchemaError::FulltextSearchNonDeterministic)?
         .iter()
-        .filter_map(|fulltext| query_field_for_fulltext(fulltext))
+        .map(|fulltext| query_field_for_fulltext(fulltext))
         .collect();
     fields.append(&mut fulltext_fields);
     fields.push(meta_field());
@@ -865,7 +1171,7 @@ fn add_query_type(
     Ok(())
 }
 
-fn query_field_for_fulltext(fulltext: &Directive) -> Option<Field> {
+fn query_field_for_fulltext(fulltext: &Directive) -> Field {
     let name = fulltext.argument("name").unwrap().as_str().unwrap().into();
 
     let includes = fulltext.argument("include").unwrap().as_list().unwrap();
@@ -913,7 +1219,7 @@ fn query_field_for_fulltext(fulltext: &Directive) -> Option<Field> {
 
     arguments.push(subgraph_error_argument());
 
-    Some(Field {
+    Field {
         position: Pos::default(),
         description: None,
         name,
@@ -922,7 +1228,7 @@ fn query_field_for_fulltext(fulltext: &Directive) -> Option<Field> {
             Box::new(Type::NamedType(entity_name.into())),
         ))))), // included entity type name
         directives: vec![fulltext.clone()],
-    })
+    }
 }
 
 /// Adds a root `Subscription` object type to the schema.
@@ -1183,7 +1489,7 @@ mod tests {
     use graphql_parser::schema::*;
     use lazy_static::lazy_static;
 
-    use super::ApiSchema;
+    use super::{ApiSchema, QueryFieldCounts, QueryValidationError};
     use crate::schema::ast;
 
     lazy_static! {
@@ -2018,4 +2324,172 @@ type Gravatar @entity {
         // object type
         let _schema = parse(SCHEMA);
     }
+
+    #[test]
+    fn query_filters() {
+        let schema = parse("type Token @entity { id: ID!, symbol: String!, supply: BigInt! }");
+
+        let filters = schema.query_filters();
+        let tokens = filters
+            .iter()
+            .find(|filters| filters.query_field == "tokens")
+            .expect("the query field `tokens` has filters");
+        assert_eq!("Token_filter", tokens.filter_type);
+        assert!(filters.iter().all(|filters| filters.query_field != "token"));
+
+        let filters_for = |field: &str| {
+            tokens
+                .filters
+                .iter()
+                .filter(|(name, _)| name == field || name.starts_with(&format!("{}_", field)))
+                .map(|(name, typ)| format!("{}: {}", name, typ))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![
+                "supply: BigInt",
+                "supply_not: BigInt",
+                "supply_gt: BigInt",
+                "supply_lt: BigInt",
+                "supply_gte: BigInt",
+                "supply_lte: BigInt",
+                "supply_in: [BigInt!]",
+                "supply_not_in: [BigInt!]",
+            ],
+            filters_for("supply")
+        );
+        assert_eq!(
+            vec![
+                "symbol: String",
+                "symbol_not: String",
+                "symbol_gt: String",
+                "symbol_lt: String",
+                "symbol_gte: String",
+                "symbol_lte: String",
+                "symbol_in: [String!]",
+                "symbol_not_in: [String!]",
+                "symbol_contains: String",
+                "symbol_contains_nocase: String",
+                "symbol_not_contains: String",
+                "symbol_not_contains_nocase: String",
+                "symbol_starts_with: String",
+                "symbol_starts_with_nocase: String",
+                "symbol_not_starts_with: String",
+                "symbol_not_starts_with_nocase: String",
+                "symbol_ends_with: String",
+                "symbol_ends_with_nocase: String",
+                "symbol_not_ends_with: String",
+                "symbol_not_ends_with_nocase: String",
+            ],
+            filters_for("symbol")
+        );
+        assert!(tokens
+            .filters
+            .iter()
+            .any(|(name, _)| name == "_change_block"));
+    }
+
+    #[test]
+    fn query_field_counts() {
+        const SCHEMA: &str = r#"
+        type _Schema_ @fulltext(
+            name: "search"
+            language: en
+            algorithm: rank
+            include: [{ entity: "Token", fields: [{ name: "symbol" }] }]
+        )
+        interface Named { name: String! }
+        type Token implements Named @entity { id: ID!, name: String!, symbol: String! }
+        type Holder @entity { id: ID!, token: Token! }"#;
+
+        let schema = parse(SCHEMA);
+        let counts = schema.query_field_counts();
+        assert_eq!(
+            QueryFieldCounts {
+                singular: 3,
+                plural: 3,
+                fulltext: 1,
+                meta: 1,
+                introspection: 2,
+            },
+            counts
+        );
+        assert_eq!(10, schema.query_field_count());
+        assert_eq!(schema.query_field_count(), counts.total());
+    }
+
+    #[test]
+    fn validate_query() {
+        const SCHEMA: &str = r#"
+        interface Named { id: ID!, name: String! }
+        type Token implements Named @entity { id: ID!, name: String!, holders: [Holder!]! @derivedFrom(field: "token") }
+        type Holder @entity { id: ID!, token: Token!, balance: BigInt! }"#;
+
+        let schema = parse(SCHEMA);
+        let validate = |query: &str| {
+            let query = graphql_parser::parse_query(query)
+                .expect("query parses")
+                .into_static();
+            schema
+                .validate_query(&query)
+                .map_err(|errors| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>())
+        };
+
+        // Valid queries
+        assert_eq!(
+            Ok(()),
+            validate(
+                "query tokens($first: Int) {
+                   tokens(first: $first, where: { name: \"GRT\" }) { id name holders { balance } }
+                   token(id: \"1\") { ...details }
+                   tokens { __typename ... on Named { name } ... { holders(first: 1) { id } } }
+                   _meta { block { number } }
+                   __schema { types { name } }
+                 }
+                 fragment details on Token { name holders { id token { id } } }"
+            )
+        );
+        assert_eq!(Ok(()), validate("{ holders { token { name } } }"));
+
+        // Invalid queries
+        assert_eq!(
+            Err(vec![
+                "Field `Query.tokens` has no argument `limit`".to_string(),
+                "Type `Token` has no field `symbol`".to_string(),
+                "Field `Query.token` requires the argument `id`".to_string(),
+                "Field `Token.holders` has type `[Holder!]!` and must have a selection of subfields"
+                    .to_string(),
+                "Field `Holder.balance` has type `BigInt!` and can not have a selection of subfields"
+                    .to_string(),
+                "Unknown fragment `missing`".to_string(),
+                "Unknown type `Wallet` in type condition".to_string(),
+            ]),
+            validate(
+                "{
+                   tokens(limit: 1) { symbol }
+                   token { holders }
+                   holders { balance { value } ...missing ... on Wallet { id } }
+                 }"
+            )
+        );
+        assert_eq!(
+            Err(vec![
+                "The schema does not support mutation operations".to_string()
+            ]),
+            validate("mutation { tokens { id } }")
+        );
+
+        // Errors point at the offending field
+        let query = graphql_parser::parse_query("{\n  tokens { symbol }\n}")
+            .unwrap()
+            .into_static();
+        let errors = schema.validate_query(&query).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert!(matches!(
+            &errors[0],
+            QueryValidationError::UnknownField(ty, field, _) if ty == "Token" && field == "symbol"
+        ));
+        assert_eq!(2, errors[0].position().line);
+        assert_eq!(12, errors[0].position().column);
+    }
 }
@@ -0,0 +1,412 @@
+use std::collections::BTreeMap;
+
+use graphql_parser::Pos;
+use semver::Version;
+
+use crate::data::store::{IdType, ValueType};
+use crate::prelude::{s, DeploymentHash};
+
+use super::input_schema::kw;
+use super::{
+    FulltextDefinition, InputSchema, SchemaValidationError, ValidationOptions, SCHEMA_TYPE_NAME,
+};
+
+/// The type of a field that a `FieldBuilder` declares, without its
+/// nullability and list-ness
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    /// One of the built-in scalars
+    Scalar(ValueType),
+    /// An enum, entity type, or interface that the schema declares
+    Named(String),
+}
+
+/// A field of an entity type or interface. Fields are nullable, and not
+/// lists, unless `required` or `list` say otherwise
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldBuilder {
+    name: String,
+    field_type: FieldType,
+    required: bool,
+    list: bool,
+    derived_from: Option<String>,
+}
+
+impl FieldBuilder {
+    pub fn new(name: &str, field_type: FieldType) -> Self {
+        FieldBuilder {
+            name: name.to_string(),
+            field_type,
+            required: false,
+            list: false,
+            derived_from: None,
+        }
+    }
+
+    /// A field whose type is the built-in scalar `value_type`
+    pub fn scalar(name: &str, value_type: ValueType) -> Self {
+        Self::new(name, FieldType::Scalar(value_type))
+    }
+
+    /// A field whose type is the enum, entity type, or interface `target`
+    pub fn named(name: &str, target: &str) -> Self {
+        Self::new(name, FieldType::Named(target.to_string()))
+    }
+
+    /// Make the field non-nullable
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Make the field a list. The elements of the list are never null
+    pub fn list(mut self) -> Self {
+        self.list = true;
+        self
+    }
+
+    /// Derive the field from the field `field` of the type it references
+    pub fn derived_from(mut self, field: &str) -> Self {
+        self.derived_from = Some(field.to_string());
+        self
+    }
+
+    fn field(&self) -> s::Field {
+        let base = match &self.field_type {
+            FieldType::Scalar(value_type) => value_type.to_str().to_string(),
+            FieldType::Named(name) => name.clone(),
+        };
+        let mut field_type = s::Type::NamedType(base);
+        if self.list {
+            field_type = s::Type::ListType(Box::new(s::Type::NonNullType(Box::new(field_type))));
+        }
+        if self.required {
+            field_type = s::Type::NonNullType(Box::new(field_type));
+        }
+        let directives = match &self.derived_from {
+            Some(field) => vec![directive(
+                "derivedFrom",
+                vec![("field", s::Value::String(field.clone()))],
+            )],
+            None => vec![],
+        };
+        s::Field {
+            position: Pos::default(),
+            description: None,
+            name: self.name.clone(),
+            arguments: vec![],
+            field_type,
+            directives,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Kind {
+    Entity { immutable: bool },
+    Interface,
+}
+
+/// An entity type or an interface. Each of them gets an `id` field of the
+/// given `IdType` before the fields that are added with `field`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeBuilder {
+    name: String,
+    kind: Kind,
+    id_type: IdType,
+    interfaces: Vec<String>,
+    fields: Vec<FieldBuilder>,
+}
+
+impl TypeBuilder {
+    pub fn entity(name: &str, id_type: IdType) -> Self {
+        Self::new(name, Kind::Entity { immutable: false }, id_type)
+    }
+
+    pub fn interface(name: &str, id_type: IdType) -> Self {
+        Self::new(name, Kind::Interface, id_type)
+    }
+
+    fn new(name: &str, kind: Kind, id_type: IdType) -> Self {
+        TypeBuilder {
+            name: name.to_string(),
+            kind,
+            id_type,
+            interfaces: vec![],
+            fields: vec![],
+        }
+    }
+
+    /// Mark an entity type as immutable. This has no effect on interfaces
+    pub fn immutable(mut self) -> Self {
+        if let Kind::Entity { immutable } = &mut self.kind {
+            *immutable = true;
+        }
+        self
+    }
+
+    /// Declare that an entity type implements `interface`
+    pub fn implements(mut self, interface: &str) -> Self {
+        self.interfaces.push(interface.to_string());
+        self
+    }
+
+    pub fn field(mut self, field: FieldBuilder) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    fn definition(&self) -> s::Definition {
+        let id_type = match self.id_type {
+            IdType::String => "ID",
+            IdType::Bytes | IdType::Int8 => self.id_type.as_str(),
+        };
+        let id = s::Field {
+            position: Pos::default(),
+            description: None,
+            name: "id".to_string(),
+            arguments: vec![],
+            field_type: s::Type::NonNullType(Box::new(s::Type::NamedType(id_type.to_string()))),
+            directives: vec![],
+        };
+        let fields = std::iter::once(id)
+            .chain(self.fields.iter().map(FieldBuilder::field))
+            .collect();
+
+        let typedef = match self.kind {
+            Kind::Entity { immutable } => {
+                let args = if immutable {
+                    vec![(kw::IMMUTABLE, s::Value::Boolean(true))]
+                } else {
+                    vec![]
+                };
+                let mut obj_type = s::ObjectType::new(self.name.clone());
+                obj_type.implements_interfaces = self.interfaces.clone();
+                obj_type.directives = vec![directive(kw::ENTITY, args)];
+                obj_type.fields = fields;
+                s::TypeDefinition::Object(obj_type)
+            }
+            Kind::Interface => {
+                let mut intf_type = s::InterfaceType::new(self.name.clone());
+                intf_type.fields = fields;
+                s::TypeDefinition::Interface(intf_type)
+            }
+        };
+        s::Definition::TypeDefinition(typedef)
+    }
+}
+
+/// Construct an `InputSchema` in code rather than by writing GraphQL SDL
+/// and parsing it. The schema is only validated by `build`, which runs
+/// the same validations as `InputSchema::parse`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaBuilder {
+    types: Vec<TypeBuilder>,
+    enums: Vec<(String, Vec<String>)>,
+    fulltexts: Vec<FulltextDefinition>,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an entity type or interface
+    pub fn with_type(mut self, type_builder: TypeBuilder) -> Self {
+        self.types.push(type_builder);
+        self
+    }
+
+    /// Add an enum with the given `values`
+    pub fn with_enum(mut self, name: &str, values: &[&str]) -> Self {
+        let values = values.iter().map(|value| value.to_string()).collect();
+        self.enums.push((name.to_string(), values));
+        self
+    }
+
+    /// Add a fulltext search. The `@fulltext` directive for it is put on
+    /// the `_Schema_` type
+    pub fn with_fulltext(mut self, fulltext: FulltextDefinition) -> Self {
+        self.fulltexts.push(fulltext);
+        self
+    }
+
+    /// The GraphQL document for the schema, in the order in which things
+    /// were added, with fulltext searches first and enums last
+    pub fn document(&self) -> s::Document {
+        let mut definitions = Vec::new();
+        if !self.fulltexts.is_empty() {
+            let mut schema_type = s::ObjectType::new(SCHEMA_TYPE_NAME.to_string());
+            schema_type.directives = self.fulltexts.iter().map(fulltext_directive).collect();
+            definitions.push(s::Definition::TypeDefinition(s::TypeDefinition::Object(
+                schema_type,
+            )));
+        }
+        definitions.extend(self.types.iter().map(TypeBuilder::definition));
+        definitions.extend(self.enums.iter().map(|(name, values)| {
+            let mut enum_type = s::EnumType::new(name.clone());
+            enum_type.values = values
+                .iter()
+                .map(|value| s::EnumValue::new(value.clone()))
+                .collect();
+            s::Definition::TypeDefinition(s::TypeDefinition::Enum(enum_type))
+        }));
+        s::Document { definitions }
+    }
+
+    /// Validate the schema in the same way as `InputSchema::parse` and
+    /// turn it into an `InputSchema`
+    pub fn build(
+        &self,
+        spec_version: &Version,
+        id: DeploymentHash,
+    ) -> Result<InputSchema, SchemaValidationError> {
+        InputSchema::from_document(
+            spec_version,
+            self.document(),
+            id,
+            &ValidationOptions::default(),
+        )
+    }
+}
+
+fn directive(name: &str, arguments: Vec<(&str, s::Value)>) -> s::Directive {
+    s::Directive {
+        position: Pos::default(),
+        name: name.to_string(),
+        arguments: arguments
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    }
+}
+
+fn fulltext_directive(fulltext: &FulltextDefinition) -> s::Directive {
+    // Sort the fields so that the document does not depend on the order
+    // in which the `HashSet` returns them
+    let mut fields: Vec<_> = fulltext.included_fields.iter().collect();
+    fields.sort();
+    let fields = fields
+        .into_iter()
+        .map(|field| {
+            let name = BTreeMap::from([("name".to_string(), s::Value::String(field.clone()))]);
+            s::Value::Object(name)
+        })
+        .collect();
+    let include = BTreeMap::from([
+        (
+            "entity".to_string(),
+            s::Value::String(fulltext.entity.clone()),
+        ),
+        ("fields".to_string(), s::Value::List(fields)),
+    ]);
+    directive(
+        "fulltext",
+        vec![
+            ("name", s::Value::String(fulltext.name.clone())),
+            (
+                "language",
+                s::Value::Enum(fulltext.config.language.as_str().to_string()),
+            ),
+            (
+                "algorithm",
+                s::Value::Enum(fulltext.config.algorithm.as_str().to_string()),
+            ),
+            ("include", s::Value::List(vec![s::Value::Object(include)])),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::data::store::{IdType, ValueType};
+    use crate::data::subgraph::LATEST_VERSION;
+    use crate::prelude::DeploymentHash;
+    use crate::schema::{
+        FulltextAlgorithm, FulltextConfig, FulltextDefinition, FulltextLanguage, InputSchema,
+        SchemaValidationError,
+    };
+
+    use super::{FieldBuilder, SchemaBuilder, TypeBuilder};
+
+    #[test]
+    fn build() {
+        const SCHEMA: &str = r#"
+        type _Schema_ @fulltext(
+            name: "search"
+            language: en
+            algorithm: rank
+            include: [{ entity: "User", fields: [{ name: "name" }] }]
+        )
+
+        interface Named { id: ID!, name: String! }
+        type User implements Named @entity {
+            id: ID!
+            name: String!
+            pets: [Pet!]! @derivedFrom(field: "owner")
+        }
+        type Pet @entity(immutable: true) { id: Bytes!, owner: User!, kind: Kind, tags: [String!] }
+        enum Kind { Cat, Dog }
+        "#;
+
+        let id = DeploymentHash::new("hash").unwrap();
+        let builder = SchemaBuilder::new()
+            .with_fulltext(FulltextDefinition {
+                config: FulltextConfig {
+                    language: FulltextLanguage::English,
+                    algorithm: FulltextAlgorithm::Rank,
+                },
+                entity: "User".to_string(),
+                included_fields: HashSet::from(["name".to_string()]),
+                name: "search".to_string(),
+            })
+            .with_type(
+                TypeBuilder::interface("Named", IdType::String)
+                    .field(FieldBuilder::scalar("name", ValueType::String).required()),
+            )
+            .with_type(
+                TypeBuilder::entity("User", IdType::String)
+                    .implements("Named")
+                    .field(FieldBuilder::scalar("name", ValueType::String).required())
+                    .field(
+                        FieldBuilder::named("pets", "Pet")
+                            .list()
+                            .required()
+                            .derived_from("owner"),
+                    ),
+            )
+            .with_type(
+                TypeBuilder::entity("Pet", IdType::Bytes)
+                    .immutable()
+                    .field(FieldBuilder::named("owner", "User").required())
+                    .field(FieldBuilder::named("kind", "Kind"))
+                    .field(FieldBuilder::scalar("tags", ValueType::String).list()),
+            )
+            .with_enum("Kind", &["Cat", "Dog"]);
+
+        let built = builder.build(LATEST_VERSION, id.clone()).unwrap();
+        let parsed = InputSchema::parse(LATEST_VERSION, SCHEMA, id.clone()).unwrap();
+        assert_eq!(parsed.to_canonical_sdl(), built.to_canonical_sdl());
+        assert!(built.entity_type("Pet").unwrap().is_immutable());
+
+        // `build` reports the same errors as `parse`
+        let err = SchemaBuilder::new()
+            .with_type(
+                TypeBuilder::entity("User", IdType::String).field(
+                    FieldBuilder::named("pets", "Pet")
+                        .list()
+                        .derived_from("owner"),
+                ),
+            )
+            .with_type(TypeBuilder::entity("Pet", IdType::String))
+            .build(LATEST_VERSION, id)
+            .unwrap_err();
+        let SchemaValidationError::SchemaInvalid(_, errors) = err else {
+            panic!("expected validation errors, got {}", err);
+        };
+        let codes: Vec<_> = errors.0.iter().map(|e| e.code()).collect();
+        assert_eq!(vec!["BAD_DERIVED_FROM"], codes);
+    }
+}
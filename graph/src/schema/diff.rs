@@ -0,0 +1,687 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::data::graphql::ext::{DirectiveFinder, DocumentExt};
+use crate::data::graphql::TypeExt;
+use crate::data::store::{IdType, ValueType};
+use crate::prelude::s;
+
+use super::input_schema::kw;
+use super::{InputSchema, TypeKind, SCHEMA_TYPE_NAME};
+
+/// One difference between two subgraph schemas
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaChange {
+    TypeAdded {
+        kind: TypeKind,
+        type_name: String,
+    },
+    TypeRemoved {
+        kind: TypeKind,
+        type_name: String,
+    },
+    FieldAdded {
+        kind: TypeKind,
+        type_name: String,
+        field_name: String,
+        field_type: s::Type,
+        derived: bool,
+    },
+    FieldRemoved {
+        kind: TypeKind,
+        type_name: String,
+        field_name: String,
+        field_type: s::Type,
+        derived: bool,
+    },
+    FieldTypeChanged {
+        kind: TypeKind,
+        type_name: String,
+        field_name: String,
+        old_type: s::Type,
+        new_type: s::Type,
+        derived: bool,
+        /// Whether the store can copy values of `old_type` into a column
+        /// for `new_type`, ignoring nullability and list-ness
+        assignable: bool,
+    },
+    EnumValueAdded {
+        type_name: String,
+        value: String,
+    },
+    EnumValueRemoved {
+        type_name: String,
+        value: String,
+    },
+}
+
+impl SchemaChange {
+    /// Return `true` if this change would keep a deployment with the new
+    /// schema from being grafted onto a deployment with the old schema.
+    ///
+    /// This follows the rules the store uses when it checks whether data
+    /// can be copied from a graft base: types and fields can be removed,
+    /// new types can be added, and nullable fields can be added to
+    /// existing types; but new non-nullable fields must not be added to
+    /// existing types, and existing fields must keep the column type and
+    /// list-ness they are stored with and must not become non-nullable.
+    /// Interfaces and derived fields are not stored, and changes to them
+    /// never prevent grafting. Values can be added to enums, but not
+    /// removed from them
+    pub fn is_breaking(&self) -> bool {
+        use SchemaChange::*;
+
+        match self {
+            TypeAdded { .. } | TypeRemoved { .. } | FieldRemoved { .. } | EnumValueAdded { .. } => {
+                false
+            }
+            EnumValueRemoved { .. } => true,
+            FieldAdded {
+                kind,
+                field_type,
+                derived,
+                ..
+            } => is_stored(*kind, *derived) && field_type.is_non_null(),
+            FieldTypeChanged {
+                kind,
+                old_type,
+                new_type,
+                derived,
+                assignable,
+                ..
+            } => {
+                is_stored(*kind, *derived)
+                    && (!assignable
+                        || old_type.is_list() != new_type.is_list()
+                        || (new_type.is_non_null() && !old_type.is_non_null()))
+            }
+        }
+    }
+}
+
+fn is_stored(kind: TypeKind, derived: bool) -> bool {
+    kind != TypeKind::Interface && !derived
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SchemaChange::*;
+
+        match self {
+            TypeAdded { type_name, .. } => write!(f, "type `{}` was added", type_name),
+            TypeRemoved { type_name, .. } => write!(f, "type `{}` was removed", type_name),
+            FieldAdded {
+                type_name,
+                field_name,
+                field_type,
+                ..
+            } => write!(
+                f,
+                "field `{}.{}: {}` was added",
+                type_name, field_name, field_type
+            ),
+            FieldRemoved {
+                type_name,
+                field_name,
+                field_type,
+                ..
+            } => write!(
+                f,
+                "field `{}.{}: {}` was removed",
+                type_name, field_name, field_type
+            ),
+            FieldTypeChanged {
+                type_name,
+                field_name,
+                old_type,
+                new_type,
+                ..
+            } => write!(
+                f,
+                "field `{}.{}` changed its type from `{}` to `{}`",
+                type_name, field_name, old_type, new_type
+            ),
+            EnumValueAdded { type_name, value } => {
+                write!(f, "value `{}` was added to enum `{}`", value, type_name)
+            }
+            EnumValueRemoved { type_name, value } => {
+                write!(f, "value `{}` was removed from enum `{}`", value, type_name)
+            }
+        }
+    }
+}
+
+/// The differences between two subgraph schemas, as produced by
+/// `InputSchema::diff`. Changes are ordered by type name, and within each
+/// type by field name
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaDiff {
+    pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    pub(in crate::schema) fn new(old: &InputSchema, new: &InputSchema) -> Self {
+        let old_types = declared_types(old);
+        let new_types = declared_types(new);
+
+        let mut changes = Vec::new();
+        for (name, (kind, old_fields)) in &old_types {
+            match new_types.get(name) {
+                None => changes.push(SchemaChange::TypeRemoved {
+                    kind: *kind,
+                    type_name: name.to_string(),
+                }),
+                Some((kind, new_fields)) => {
+                    diff_fields(old, new, *kind, name, old_fields, new_fields, &mut changes)
+                }
+            }
+        }
+        // The fields of added types are not listed separately; the store
+        // creates a new table for them, and nothing needs to be copied
+        for (name, (kind, _)) in &new_types {
+            if old_types.contains_key(name) {
+                continue;
+            }
+            changes.push(SchemaChange::TypeAdded {
+                kind: *kind,
+                type_name: name.to_string(),
+            });
+        }
+        diff_enums(old, new, &mut changes);
+        changes.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+
+        SchemaDiff { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Return `true` if any of the changes is breaking
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(SchemaChange::is_breaking)
+    }
+
+    /// Return `true` if there are changes, but none of them are breaking
+    pub fn is_additive(&self) -> bool {
+        !self.is_empty() && !self.is_breaking()
+    }
+
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+}
+
+/// The breaking changes that keep a schema from being grafted onto a base
+/// schema, as returned by `InputSchema::is_graft_compatible_with`
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraftIncompatibility(pub Vec<SchemaChange>);
+
+impl fmt::Display for GraftIncompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the schema can not be grafted onto the base schema:")?;
+        for (n, change) in self.0.iter().enumerate() {
+            write!(f, "\n  ({}) - {}", n + 1, change)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GraftIncompatibility {}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            let marker = if change.is_breaking() { "!" } else { " " };
+            writeln!(f, "{} {}", marker, change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sort changes by type name and then by field name, with changes to the
+/// type itself first
+fn sort_key(change: &SchemaChange) -> (&str, Option<&str>) {
+    use SchemaChange::*;
+
+    match change {
+        TypeAdded { type_name, .. } | TypeRemoved { type_name, .. } => (type_name.as_str(), None),
+        EnumValueAdded { type_name, value } | EnumValueRemoved { type_name, value } => {
+            (type_name.as_str(), Some(value.as_str()))
+        }
+        FieldAdded {
+            type_name,
+            field_name,
+            ..
+        }
+        | FieldRemoved {
+            type_name,
+            field_name,
+            ..
+        }
+        | FieldTypeChanged {
+            type_name,
+            field_name,
+            ..
+        } => (type_name.as_str(), Some(field_name.as_str())),
+    }
+}
+
+/// The object types, aggregations, and interfaces that the user declared
+/// in the schema, keyed by their name
+fn declared_types(schema: &InputSchema) -> BTreeMap<&str, (TypeKind, &[s::Field])> {
+    schema
+        .schema()
+        .document
+        .definitions
+        .iter()
+        .filter_map(|defn| match defn {
+            s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type))
+                if obj_type.name != SCHEMA_TYPE_NAME =>
+            {
+                let kind = if obj_type.find_directive(kw::AGGREGATION).is_some() {
+                    TypeKind::Aggregation
+                } else {
+                    TypeKind::Object
+                };
+                Some((obj_type.name.as_str(), (kind, obj_type.fields.as_slice())))
+            }
+            s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => Some((
+                intf_type.name.as_str(),
+                (TypeKind::Interface, intf_type.fields.as_slice()),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Compare the values of the enums that both schemas declare. Enums are
+/// not stored on their own, and adding or removing a whole enum is
+/// therefore not a change
+fn diff_enums(old: &InputSchema, new: &InputSchema, changes: &mut Vec<SchemaChange>) {
+    let new_enums = new.schema().document.get_enum_definitions();
+    for old_enum in old.schema().document.get_enum_definitions() {
+        let Some(new_enum) = new_enums.iter().find(|e| e.name == old_enum.name) else {
+            continue;
+        };
+        let has =
+            |enum_type: &s::EnumType, value: &str| enum_type.values.iter().any(|v| v.name == value);
+        for value in &old_enum.values {
+            if !has(new_enum, &value.name) {
+                changes.push(SchemaChange::EnumValueRemoved {
+                    type_name: old_enum.name.clone(),
+                    value: value.name.clone(),
+                });
+            }
+        }
+        for value in &new_enum.values {
+            if !has(old_enum, &value.name) {
+                changes.push(SchemaChange::EnumValueAdded {
+                    type_name: new_enum.name.clone(),
+                    value: value.name.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// The type of the column in which the store keeps the values of fields
+/// with a given base type
+#[derive(PartialEq)]
+enum ColumnType {
+    Scalar(ValueType),
+    Enum(Arc<BTreeSet<String>>),
+}
+
+impl ColumnType {
+    /// References to entities are stored with the type of the id of the
+    /// referenced entity, and `ID` is stored as a `String`
+    fn new(schema: &InputSchema, base_type: &str) -> Option<Self> {
+        let id_type = schema
+            .entity_type(base_type)
+            .ok()
+            .and_then(|entity_type| entity_type.id_type().ok());
+        if let Some(id_type) = id_type {
+            let value_type = match id_type {
+                IdType::String => ValueType::String,
+                IdType::Bytes => ValueType::Bytes,
+                IdType::Int8 => ValueType::Int8,
+            };
+            return Some(ColumnType::Scalar(value_type));
+        }
+        if let Some(values) = schema.enum_values(base_type) {
+            return Some(ColumnType::Enum(values));
+        }
+        ValueType::from_str(base_type).ok().map(ColumnType::Scalar)
+    }
+
+    /// The same check as `Column::is_assignable_from` in the store, except
+    /// for nullability and list-ness
+    fn is_assignable_from(&self, source: &Self) -> bool {
+        match (self, source) {
+            (ColumnType::Enum(values), ColumnType::Enum(source_values)) => {
+                source_values.is_subset(values)
+            }
+            (ColumnType::Scalar(value_type), ColumnType::Scalar(source_type)) => {
+                value_type == source_type
+            }
+            _ => false,
+        }
+    }
+}
+
+fn diff_fields(
+    old_schema: &InputSchema,
+    new_schema: &InputSchema,
+    kind: TypeKind,
+    type_name: &str,
+    old_fields: &[s::Field],
+    new_fields: &[s::Field],
+    changes: &mut Vec<SchemaChange>,
+) {
+    let added = |field: &s::Field| SchemaChange::FieldAdded {
+        kind,
+        type_name: type_name.to_string(),
+        field_name: field.name.clone(),
+        field_type: field.field_type.clone(),
+        derived: field.is_derived(),
+    };
+    let removed = |field: &s::Field| SchemaChange::FieldRemoved {
+        kind,
+        type_name: type_name.to_string(),
+        field_name: field.name.clone(),
+        field_type: field.field_type.clone(),
+        derived: field.is_derived(),
+    };
+
+    for old in old_fields {
+        match new_fields.iter().find(|new| new.name == old.name) {
+            None => changes.push(removed(old)),
+            Some(new) if new.is_derived() != old.is_derived() => {
+                // Going from a stored to a derived field or vice versa
+                // removes or adds a column
+                changes.push(removed(old));
+                changes.push(added(new));
+            }
+            Some(new) if new.field_type != old.field_type => {
+                let old_column = ColumnType::new(old_schema, old.field_type.get_base_type());
+                let new_column = ColumnType::new(new_schema, new.field_type.get_base_type());
+                let assignable = match (old_column, new_column) {
+                    (Some(old_column), Some(new_column)) => {
+                        new_column.is_assignable_from(&old_column)
+                    }
+                    _ => false,
+                };
+                changes.push(SchemaChange::FieldTypeChanged {
+                    kind,
+                    type_name: type_name.to_string(),
+                    field_name: new.name.clone(),
+                    old_type: old.field_type.clone(),
+                    new_type: new.field_type.clone(),
+                    derived: new.is_derived(),
+                    assignable,
+                })
+            }
+            Some(_) => { /* unchanged */ }
+        }
+    }
+    for new in new_fields {
+        if !old_fields.iter().any(|old| old.name == new.name) {
+            changes.push(added(new));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::s;
+    use crate::schema::{InputSchema, TypeKind};
+
+    use super::{GraftIncompatibility, SchemaChange};
+
+    const OLD: &str = r#"
+        type Thing @entity {
+            id: ID!
+            name: String!
+            count: Int
+            size: Int!
+            gone: String
+        }
+
+        type Old @entity {
+            id: ID!
+        }
+    "#;
+
+    const NEW: &str = r#"
+        type Thing @entity {
+            id: ID!
+            name: String
+            count: Int!
+            size: BigInt!
+            extra: String
+            required: String!
+        }
+
+        type New @entity {
+            id: ID!
+        }
+    "#;
+
+    #[test]
+    fn diff() {
+        let old = InputSchema::raw(OLD, "old");
+        let new = InputSchema::raw(NEW, "new");
+
+        let diff = old.diff(&new);
+        let changes: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|change| (change.to_string(), change.is_breaking()))
+            .collect();
+        let exp = vec![
+            ("type `New` was added".to_string(), false),
+            ("type `Old` was removed".to_string(), false),
+            (
+                "field `Thing.count` changed its type from `Int` to `Int!`".to_string(),
+                true,
+            ),
+            ("field `Thing.extra: String` was added".to_string(), false),
+            ("field `Thing.gone: String` was removed".to_string(), false),
+            (
+                "field `Thing.name` changed its type from `String!` to `String`".to_string(),
+                false,
+            ),
+            (
+                "field `Thing.required: String!` was added".to_string(),
+                true,
+            ),
+            (
+                "field `Thing.size` changed its type from `Int!` to `BigInt!`".to_string(),
+                true,
+            ),
+        ];
+        assert_eq!(exp, changes);
+        assert!(diff.is_breaking());
+
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn additive() {
+        const NEW: &str = r#"
+        type Thing @entity {
+            id: ID!
+            name: String!
+            count: Int
+            size: Int!
+            gone: String
+            extra: [String!]
+        }
+        "#;
+        let old = InputSchema::raw(OLD, "old");
+        let new = InputSchema::raw(NEW, "new");
+
+        let diff = old.diff(&new);
+        assert!(diff.is_additive());
+        assert!(matches!(
+            diff.changes.as_slice(),
+            [
+                SchemaChange::TypeRemoved { .. },
+                SchemaChange::FieldAdded { .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn column_types() {
+        const OLD: &str = r#"
+        enum Color { RED, GREEN }
+        type Thing @entity {
+            id: ID!
+            owner: Owner!
+            label: String!
+            color: Color!
+            amount: Int!
+        }
+        type Owner @entity {
+            id: Bytes!
+        }
+        "#;
+        const NEW: &str = r#"
+        enum Color { RED, GREEN }
+        enum Shade { RED, GREEN, BLUE }
+        type Thing @entity {
+            id: String!
+            owner: Bytes!
+            label: ID!
+            color: Shade!
+            amount: Int8!
+        }
+        type Owner @entity {
+            id: Bytes!
+        }
+        "#;
+        let old = InputSchema::raw(OLD, "old");
+        let new = InputSchema::raw(NEW, "new");
+
+        let changes: Vec<_> = old
+            .diff(&new)
+            .changes
+            .iter()
+            .map(|change| (change.to_string(), change.is_breaking()))
+            .collect();
+        let exp = vec![
+            (
+                "field `Thing.amount` changed its type from `Int!` to `Int8!`".to_string(),
+                true,
+            ),
+            (
+                "field `Thing.color` changed its type from `Color!` to `Shade!`".to_string(),
+                false,
+            ),
+            (
+                "field `Thing.id` changed its type from `ID!` to `String!`".to_string(),
+                false,
+            ),
+            (
+                "field `Thing.label` changed its type from `String!` to `ID!`".to_string(),
+                false,
+            ),
+            (
+                "field `Thing.owner` changed its type from `Owner!` to `Bytes!`".to_string(),
+                false,
+            ),
+        ];
+        assert_eq!(exp, changes);
+
+        // Values of `Shade` can not all be stored as a `Color`
+        let changes = new.diff(&old);
+        let breaking: Vec<_> = changes
+            .breaking_changes()
+            .map(|change| change.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "field `Thing.amount` changed its type from `Int8!` to `Int!`",
+                "field `Thing.color` changed its type from `Shade!` to `Color!`",
+            ],
+            breaking
+        );
+    }
+
+    #[test]
+    fn graft_compatibility() {
+        const BASE: &str = r#"
+        enum Color { RED, GREEN }
+        type Thing @entity {
+            id: ID!
+            color: Color!
+        }
+        "#;
+        const MORE_COLORS: &str = r#"
+        enum Color { RED, GREEN, BLUE }
+        type Thing @entity {
+            id: ID!
+            color: Color!
+            name: String
+        }
+        "#;
+        const FEWER_COLORS: &str = r#"
+        enum Color { RED }
+        type Thing @entity {
+            id: ID!
+            color: Color!
+            size: Int!
+        }
+        "#;
+        const NEW_TYPE: &str = r#"
+        enum Color { RED, GREEN }
+        type Thing @entity {
+            id: ID!
+            color: Color!
+        }
+        type Other @entity {
+            id: ID!
+            thing: Thing!
+            count: Int!
+        }
+        "#;
+        let base = InputSchema::raw(BASE, "base");
+
+        let schema = InputSchema::raw(MORE_COLORS, "more");
+        assert_eq!(Ok(()), schema.is_graft_compatible_with(&base));
+
+        let schema = InputSchema::raw(NEW_TYPE, "new_type");
+        assert_eq!(Ok(()), schema.is_graft_compatible_with(&base));
+
+        let schema = InputSchema::raw(FEWER_COLORS, "fewer");
+        let err = schema.is_graft_compatible_with(&base).unwrap_err();
+        assert_eq!(
+            GraftIncompatibility(vec![
+                SchemaChange::EnumValueRemoved {
+                    type_name: "Color".to_string(),
+                    value: "GREEN".to_string()
+                },
+                SchemaChange::FieldAdded {
+                    kind: TypeKind::Object,
+                    type_name: "Thing".to_string(),
+                    field_name: "size".to_string(),
+                    field_type: s::Type::NonNullType(Box::new(s::Type::NamedType(
+                        "Int".to_string()
+                    ))),
+                    derived: false,
+                },
+            ]),
+            err
+        );
+        assert_eq!(
+            "the schema can not be grafted onto the base schema:\n  \
+             (1) - value `GREEN` was removed from enum `Color`\n  \
+             (2) - field `Thing.size: Int!` was added",
+            err.to_string()
+        );
+    }
+}
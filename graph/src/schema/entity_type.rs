@@ -52,7 +52,7 @@ impl EntityType {
     }
 
     pub fn id_type(&self) -> Result<IdType, Error> {
-        self.schema.id_type(self.atom)
+        self.schema.id_type_of(self.atom)
     }
 
     /// Return the object type for this entity type. It is an error to call
@@ -78,7 +78,7 @@ impl EntityType {
         let id = id.into();
         let id_type = self
             .schema
-            .id_type(self.atom)
+            .id_type_of(self.atom)
             .with_context(|| format!("error determining id_type for {}[{}]", self.as_str(), id))?;
         id_type.parse(id)
     }
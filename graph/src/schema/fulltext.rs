@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::str::FromStr;
 
 use crate::data::graphql::{DirectiveExt, ValueExt};
 use crate::prelude::s;
@@ -52,6 +53,16 @@ impl TryFrom<&str> for FulltextLanguage {
     }
 }
 
+impl FromStr for FulltextLanguage {
+    type Err = String;
+
+    /// Parse the language code that the `language` argument of the
+    /// `@fulltext` directive uses, e.g., `en`
+    fn from_str(language: &str) -> Result<Self, Self::Err> {
+        Self::try_from(language)
+    }
+}
+
 impl FulltextLanguage {
     /// Return the language as a valid SQL string. The string is safe to
     /// directly use verbatim in a query, i.e., doesn't require being passed
@@ -76,6 +87,36 @@ impl FulltextLanguage {
             Self::Turkish => "'turkish'",
         }
     }
+
+    /// The name of the Postgres text search configuration that fulltext
+    /// searches in this language use, i.e., `as_sql` without the quotes.
+    /// The database must list it in `pg_ts_config` for the search to work
+    pub fn text_search_config(&self) -> &'static str {
+        self.as_sql().trim_matches('\'')
+    }
+
+    /// The code for the language as it is used in the `language` argument
+    /// of the `@fulltext` directive, e.g., `en`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Simple => "simple",
+            Self::Danish => "da",
+            Self::Dutch => "nl",
+            Self::English => "en",
+            Self::Finnish => "fi",
+            Self::French => "fr",
+            Self::German => "de",
+            Self::Hungarian => "hu",
+            Self::Italian => "it",
+            Self::Norwegian => "no",
+            Self::Portugese => "pt",
+            Self::Romanian => "ro",
+            Self::Russian => "ru",
+            Self::Spanish => "es",
+            Self::Swedish => "sv",
+            Self::Turkish => "tr",
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -84,6 +125,16 @@ pub enum FulltextAlgorithm {
     ProximityRank,
 }
 
+impl FulltextAlgorithm {
+    /// The name of the algorithm as it is used in the `@fulltext` directive
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FulltextAlgorithm::Rank => "rank",
+            FulltextAlgorithm::ProximityRank => "proximityRank",
+        }
+    }
+}
+
 impl TryFrom<&str> for FulltextAlgorithm {
     type Error = String;
     fn try_from(algorithm: &str) -> Result<Self, Self::Error> {
@@ -98,14 +149,27 @@ impl TryFrom<&str> for FulltextAlgorithm {
     }
 }
 
+impl FromStr for FulltextAlgorithm {
+    type Err = String;
+
+    /// Parse the value of the `algorithm` argument of the `@fulltext`
+    /// directive, e.g., `rank`
+    fn from_str(algorithm: &str) -> Result<Self, Self::Err> {
+        Self::try_from(algorithm)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct FulltextConfig {
     pub language: FulltextLanguage,
     pub algorithm: FulltextAlgorithm,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct FulltextDefinition {
     pub config: FulltextConfig,
+    /// The name of the entity type whose fields are indexed
+    pub entity: String,
     pub included_fields: HashSet<String>,
     pub name: String,
 }
@@ -128,6 +192,7 @@ impl From<&s::Directive> for FulltextDefinition {
         let included_entity_list = directive.argument("include").unwrap().as_list().unwrap();
         // Currently fulltext query fields are limited to 1 entity, so we just take the first (and only) included Entity
         let included_entity = included_entity_list.first().unwrap().as_object().unwrap();
+        let entity = included_entity.get("entity").unwrap().as_str().unwrap();
         let included_field_values = included_entity.get("fields").unwrap().as_list().unwrap();
         let included_fields: HashSet<String> = included_field_values
             .iter()
@@ -148,8 +213,60 @@ impl From<&s::Directive> for FulltextDefinition {
                 language,
                 algorithm,
             },
+            entity: entity.into(),
             included_fields,
             name: name.into(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FulltextAlgorithm, FulltextLanguage};
+
+    #[test]
+    fn parse_language() {
+        assert_eq!(Ok(FulltextLanguage::English), "en".parse());
+        assert_eq!(Ok(FulltextLanguage::Simple), "simple".parse());
+        assert_eq!(Ok(FulltextLanguage::Portugese), "pt".parse());
+        assert!("english".parse::<FulltextLanguage>().is_err());
+        assert!("EN".parse::<FulltextLanguage>().is_err());
+        assert!("".parse::<FulltextLanguage>().is_err());
+
+        for language in [
+            FulltextLanguage::Simple,
+            FulltextLanguage::English,
+            FulltextLanguage::Turkish,
+        ] {
+            assert_eq!(Ok(language.clone()), language.as_str().parse());
+        }
+    }
+
+    #[test]
+    fn text_search_config() {
+        assert_eq!("english", FulltextLanguage::English.text_search_config());
+        assert_eq!("simple", FulltextLanguage::Simple.text_search_config());
+        for language in [FulltextLanguage::Danish, FulltextLanguage::Portugese] {
+            assert_eq!(
+                language.as_sql(),
+                format!("'{}'", language.text_search_config())
+            );
+        }
+    }
+
+    #[test]
+    fn parse_algorithm() {
+        assert_eq!(Ok(FulltextAlgorithm::Rank), "rank".parse());
+        assert_eq!(
+            Ok(FulltextAlgorithm::ProximityRank),
+            "proximityRank".parse()
+        );
+        let err = "proximity_rank".parse::<FulltextAlgorithm>().unwrap_err();
+        assert!(err.contains("It must be one of: rank, proximityRank"));
+        assert!("Rank".parse::<FulltextAlgorithm>().is_err());
+
+        for algorithm in [FulltextAlgorithm::Rank, FulltextAlgorithm::ProximityRank] {
+            assert_eq!(Ok(algorithm.clone()), algorithm.as_str().parse());
+        }
+    }
+}
@@ -17,14 +17,19 @@ use crate::data::graphql::{DirectiveExt, DocumentExt, ObjectTypeExt, TypeExt, Va
 use crate::data::store::{
     self, EntityValidationError, IdType, IntoEntityIterator, TryIntoEntityIterator, ValueType, ID,
 };
+use crate::data::subgraph::{SchemaFeature, MIN_SPEC_VERSION};
 use crate::data::value::Word;
 use crate::prelude::q::Value;
-use crate::prelude::{s, DeploymentHash};
+use crate::prelude::{s, DeploymentHash, ENV_VARS};
 use crate::schema::api::api_schema;
 use crate::util::intern::{Atom, AtomPool};
 
 use super::fulltext::FulltextDefinition;
-use super::{ApiSchema, AsEntityTypeName, EntityType, Schema};
+use super::lint;
+use super::{
+    ApiSchema, AsEntityTypeName, Deprecation, EntityType, GraftIncompatibility, Schema, SchemaDiff,
+    SchemaValidationError, SchemaWarning, Strings, ValidationErrors,
+};
 
 /// The name of the PoI entity type
 pub(crate) const POI_OBJECT: &str = "Poi$";
@@ -33,7 +38,7 @@ const POI_DIGEST: &str = "digest";
 /// The name of the PoI attribute for storing the block time
 const POI_BLOCK_TIME: &str = "blockTime";
 
-mod kw {
+pub(super) mod kw {
     pub const ENTITY: &str = "entity";
     pub const IMMUTABLE: &str = "immutable";
     pub const TIMESERIES: &str = "timeseries";
@@ -69,6 +74,17 @@ pub enum TypeKind {
     Aggregation,
 }
 
+/// How a field of one entity type refers to another entity type; see
+/// `InputSchema::reference_graph`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RefKind {
+    /// The field stores the id of the other entity
+    Direct,
+    /// The field is `@derivedFrom` a field of the other entity and is not
+    /// stored
+    Derived,
+}
+
 #[derive(Debug, PartialEq)]
 enum TypeInfo {
     Object(ObjectType),
@@ -213,6 +229,16 @@ impl Field {
         }
     }
 
+    /// Return `true` if the field holds a list of values
+    pub fn is_list(&self) -> bool {
+        self.field_type.is_list()
+    }
+
+    /// Return `true` if the field can be `null`
+    pub fn is_nullable(&self) -> bool {
+        !self.field_type.is_non_null()
+    }
+
     fn scalar_value_type(schema: &Schema, field_type: &s::Type) -> ValueType {
         use s::TypeDefinition as t;
         match field_type {
@@ -257,6 +283,60 @@ impl Field {
     }
 }
 
+/// The kind of database index that the store creates for a column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexMethod {
+    BTree,
+    Gist,
+    Gin,
+}
+
+impl IndexMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IndexMethod::BTree => "btree",
+            IndexMethod::Gist => "gist",
+            IndexMethod::Gin => "gin",
+        }
+    }
+}
+
+/// A field, or a fulltext search, of an entity type that the store will
+/// index, as returned by `InputSchema::indexed_fields`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldIndex {
+    /// The name of the field, or of the fulltext search
+    pub name: String,
+    pub method: IndexMethod,
+}
+
+/// A field `source.field` that references the entity type `target`
+/// without `target` having a field that is `@derivedFrom` it, as returned
+/// by `InputSchema::entities_without_derived_inverse`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingInverse {
+    pub target: EntityType,
+    pub source: EntityType,
+    pub field: String,
+}
+
+impl std::fmt::Display for MissingInverse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use inflector::Inflector;
+
+        write!(
+            f,
+            "`{source}.{field}` references `{target}`, but `{target}` has no field derived \
+             from it; consider adding `{name}: [{source}!]! @derivedFrom(field: \"{field}\")` \
+             to `{target}`",
+            source = self.source,
+            field = self.field,
+            target = self.target,
+            name = self.source.as_str().to_camel_case().to_plural()
+        )
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub struct ObjectType {
     pub name: Atom,
@@ -470,7 +550,7 @@ impl AggregateFn {
         }
     }
 
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         use AggregateFn::*;
         match self {
             Sum => "sum",
@@ -493,7 +573,7 @@ pub enum AggregationInterval {
 }
 
 impl AggregationInterval {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
             AggregationInterval::Hour => "hour",
             AggregationInterval::Day => "day",
@@ -675,6 +755,18 @@ pub struct Aggregation {
 }
 
 impl Aggregation {
+    /// The name of this aggregation, i.e., the name of the type with the
+    /// `@aggregation` directive
+    pub fn type_name<'a>(&self, schema: &'a InputSchema) -> &'a str {
+        // unwrap: the aggregation was constructed from the schema's pool
+        schema.pool().get(self.name).unwrap()
+    }
+
+    /// The timeseries that this aggregation aggregates
+    pub fn source_type(&self, schema: &InputSchema) -> EntityType {
+        EntityType::new(schema.cheap_clone(), self.source)
+    }
+
     pub fn new(schema: &Schema, pool: &AtomPool, agg_type: &s::ObjectType) -> Self {
         let name = pool.lookup(&agg_type.name).unwrap();
         let id_type = IdType::try_from(agg_type).expect("validation caught any issues here");
@@ -782,11 +874,129 @@ impl CheapClone for InputSchema {
     }
 }
 
+/// Options that control how `InputSchema::parse_with_options` and
+/// `InputSchema::validate` check a schema. The default is what graph-node
+/// uses when it deploys a subgraph
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationOptions {
+    /// Reject directives and directive arguments that graph-node does not
+    /// interpret instead of ignoring them, cycles of entity types that
+    /// reference each other through non-nullable fields, lists of
+    /// references whose elements are nullable, fields whose names clash
+    /// with the arguments and filters that graph-node generates for the
+    /// API schema, type and field names with prefixes that graph-node
+    /// reserves, interfaces that no type implements, lists on immutable
+    /// entity types that should be derived, fields other than `id` that
+    /// have type `ID`, lists of scalars or enums whose elements are
+    /// nullable, and directives that graph-node interprets but that are
+    /// used more than once or repeat an argument
+    pub strict: bool,
+    /// Accept `@fulltext` directives even though fulltext search is not
+    /// deterministic. Defaults to the value of the environment variable
+    /// `GRAPH_ALLOW_NON_DETERMINISTIC_FULLTEXT_SEARCH`
+    pub allow_fulltext: bool,
+    /// The features that the spec version supports. If this is `None`,
+    /// they are determined with `SchemaFeature::is_supported_by`. Setting
+    /// it makes it possible to check schemas against spec versions that
+    /// graph-node does not know about yet
+    pub features: Option<BTreeSet<SchemaFeature>>,
+    /// Validate the schema for a subgraph whose entities are written by
+    /// the entity changes of a substreams data source instead of by
+    /// mappings. graph-node does not relax any rules for such subgraphs,
+    /// but entity changes can not carry `Int8` values, and such schemas
+    /// must therefore not have fields of type `Int8` other than `id`
+    pub substreams: bool,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            allow_fulltext: ENV_VARS.allow_non_deterministic_fulltext_search,
+            features: None,
+            substreams: false,
+        }
+    }
+}
+
 impl InputSchema {
     /// A convenience function for creating an `InputSchema` from the string
     /// representation of the subgraph's GraphQL schema `raw` and its
     /// deployment hash `id`. The returned schema is fully validated.
     pub fn parse(spec_version: &Version, raw: &str, id: DeploymentHash) -> Result<Self, Error> {
+        Self::parse_with_options(spec_version, raw, id, &ValidationOptions::default())
+    }
+
+    /// Like `parse`, but validate the schema according to `options`
+    pub fn parse_with_options(
+        spec_version: &Version,
+        raw: &str,
+        id: DeploymentHash,
+        options: &ValidationOptions,
+    ) -> Result<Self, Error> {
+        let schema = Schema::parse(raw, id.clone())?;
+        validations::validate(spec_version, &schema, options).map_err(|errors| {
+            SchemaValidationError::SchemaInvalid(id.to_string(), ValidationErrors(errors))
+        })?;
+
+        Ok(Self::create(schema))
+    }
+
+    /// Like `parse_with_options`, but for a schema that is not known to be
+    /// valid UTF-8, e.g., because it was read from a dump of a database.
+    /// If it is not, this fails with `SchemaValidationError::Encoding`
+    pub fn parse_bytes(
+        spec_version: &Version,
+        raw: &[u8],
+        id: DeploymentHash,
+        options: &ValidationOptions,
+    ) -> Result<Self, Error> {
+        let raw = std::str::from_utf8(raw).map_err(SchemaValidationError::from)?;
+        Self::parse_with_options(spec_version, raw, id, options)
+    }
+
+    /// Validate the schema `raw` in exactly the same way as
+    /// `parse_with_options` does, and, if `api` is `true`, also check that
+    /// an `ApiSchema` can be generated from it. This is useful for tools
+    /// that only want to know whether a schema is valid, and why not
+    pub fn validate(
+        spec_version: &Version,
+        raw: &str,
+        id: DeploymentHash,
+        api: bool,
+        options: &ValidationOptions,
+    ) -> Result<(), SchemaValidationError> {
+        let document = graphql_parser::parse_schema(raw)
+            .map_err(|e| SchemaValidationError::SchemaParseError(e.to_string()))?
+            .into_static();
+        let schema = Self::from_document(spec_version, document, id, options)?;
+
+        if api {
+            schema
+                .api_schema()
+                .map_err(|e| SchemaValidationError::ApiSchemaInvalid(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Like `parse_with_options`, but for a schema that has already been
+    /// turned into a GraphQL document, e.g., by a `SchemaBuilder`
+    pub(in crate::schema) fn from_document(
+        spec_version: &Version,
+        document: s::Document,
+        id: DeploymentHash,
+        options: &ValidationOptions,
+    ) -> Result<Self, SchemaValidationError> {
+        let schema = Schema::new(id.clone(), document)?;
+        validations::validate(spec_version, &schema, options).map_err(|errors| {
+            SchemaValidationError::SchemaInvalid(id.to_string(), ValidationErrors(errors))
+        })?;
+        Ok(Self::create(schema))
+    }
+
+    /// Construct an `InputSchema` from a `schema` that has passed
+    /// validation
+    fn create(schema: Schema) -> Self {
         fn agg_mappings(ts_types: &[TypeInfo]) -> Box<[AggregationMapping]> {
             let mut mappings: Vec<_> = ts_types
                 .iter()
@@ -809,20 +1019,6 @@ impl InputSchema {
             mappings.into_boxed_slice()
         }
 
-        let schema = Schema::parse(raw, id.clone())?;
-        validations::validate(spec_version, &schema).map_err(|errors| {
-            anyhow!(
-                "Validation errors in subgraph `{}`:\n{}",
-                id,
-                errors
-                    .into_iter()
-                    .enumerate()
-                    .map(|(n, e)| format!("  ({}) - {}", n + 1, e))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            )
-        })?;
-
         let pool = Arc::new(atom_pool(&schema.document));
 
         // There are a lot of unwraps in this code; they are all safe
@@ -860,7 +1056,7 @@ impl InputSchema {
 
         let agg_mappings = agg_mappings(&type_infos);
 
-        Ok(Self {
+        Self {
             inner: Arc::new(Inner {
                 schema,
                 type_infos,
@@ -868,7 +1064,7 @@ impl InputSchema {
                 pool,
                 agg_mappings,
             }),
-        })
+        }
     }
 
     /// Parse with the latest spec version
@@ -894,6 +1090,391 @@ impl InputSchema {
         &self.inner.schema
     }
 
+    /// Compute the changes that turn `self` into `other`, i.e., `self` is
+    /// the old and `other` the new version of the schema
+    pub fn diff(&self, other: &InputSchema) -> SchemaDiff {
+        SchemaDiff::new(self, other)
+    }
+
+    /// Check that a subgraph with this schema can be grafted onto a
+    /// subgraph with the schema `base`, using the same rules as the store
+    /// uses when it copies data from the graft base; see
+    /// `SchemaChange::is_breaking` for what those are
+    pub fn is_graft_compatible_with(&self, base: &InputSchema) -> Result<(), GraftIncompatibility> {
+        let breaking: Vec<_> = base.diff(self).breaking_changes().cloned().collect();
+        if breaking.is_empty() {
+            Ok(())
+        } else {
+            Err(GraftIncompatibility(breaking))
+        }
+    }
+
+    /// Produce a new schema in which the type `from` is called `to`. The
+    /// rename is applied to the definition of the type, to the types of
+    /// all fields that reference it, to the interfaces that object types
+    /// implement, to the entities in `@fulltext` includes, and to the
+    /// `source` of aggregations. The result is validated again in the same
+    /// way as `parse_with_options` does for `spec_version` and `options`.
+    /// Fails if there is no type `from`, or if `to` is already the name of
+    /// a type
+    pub fn rename_type(
+        &self,
+        spec_version: &Version,
+        from: &str,
+        to: &str,
+        options: &ValidationOptions,
+    ) -> Result<InputSchema, SchemaValidationError> {
+        fn rename_field_type(field_type: &mut s::Type, from: &str, to: &str) {
+            match field_type {
+                s::Type::NamedType(name) => {
+                    if *name == from {
+                        *name = to.to_string();
+                    }
+                }
+                s::Type::ListType(inner) | s::Type::NonNullType(inner) => {
+                    rename_field_type(inner, from, to)
+                }
+            }
+        }
+
+        fn rename_string(value: &mut s::Value, from: &str, to: &str) {
+            if let s::Value::String(name) = value {
+                if *name == from {
+                    *name = to.to_string();
+                }
+            }
+        }
+
+        fn rename_directives(directives: &mut Vec<s::Directive>, from: &str, to: &str) {
+            directives.retain(|dir| dir.name != "subgraphId");
+            for dir in directives {
+                for (arg, value) in &mut dir.arguments {
+                    match (dir.name.as_str(), arg.as_str()) {
+                        ("fulltext", "include") => {
+                            if let s::Value::List(includes) = value {
+                                for include in includes {
+                                    if let s::Value::Object(include) = include {
+                                        if let Some(entity) = include.get_mut("entity") {
+                                            rename_string(entity, from, to);
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        (kw::AGGREGATION, kw::SOURCE) => rename_string(value, from, to),
+                        _ => { /* does not reference types */ }
+                    }
+                }
+            }
+        }
+
+        fn rename_fields(fields: &mut [s::Field], from: &str, to: &str) {
+            for field in fields {
+                rename_field_type(&mut field.field_type, from, to);
+            }
+        }
+
+        let document = &self.inner.schema.document;
+        if document.get_named_type(from).is_none() {
+            return Err(SchemaValidationError::RenameTypeUndefined(from.to_string()));
+        }
+        if document.get_named_type(to).is_some() || ValueType::is_scalar(to) {
+            return Err(SchemaValidationError::RenameTypeConflict(
+                from.to_string(),
+                to.to_string(),
+            ));
+        }
+
+        let rename = |name: &mut String| {
+            if *name == from {
+                *name = to.to_string();
+            }
+        };
+
+        let mut document = document.clone();
+        for defn in &mut document.definitions {
+            use s::TypeDefinition as T;
+
+            match defn {
+                s::Definition::TypeDefinition(T::Object(obj_type)) => {
+                    rename(&mut obj_type.name);
+                    obj_type.implements_interfaces.iter_mut().for_each(rename);
+                    rename_directives(&mut obj_type.directives, from, to);
+                    rename_fields(&mut obj_type.fields, from, to);
+                }
+                s::Definition::TypeDefinition(T::Interface(intf_type)) => {
+                    rename(&mut intf_type.name);
+                    rename_directives(&mut intf_type.directives, from, to);
+                    rename_fields(&mut intf_type.fields, from, to);
+                }
+                s::Definition::TypeDefinition(T::Enum(enum_type)) => {
+                    rename(&mut enum_type.name);
+                    rename_directives(&mut enum_type.directives, from, to);
+                }
+                s::Definition::TypeDefinition(T::Scalar(scalar_type)) => {
+                    rename(&mut scalar_type.name);
+                    rename_directives(&mut scalar_type.directives, from, to);
+                }
+                s::Definition::TypeDefinition(T::InputObject(input_type)) => {
+                    rename(&mut input_type.name);
+                    rename_directives(&mut input_type.directives, from, to);
+                    for field in &mut input_type.fields {
+                        rename_field_type(&mut field.value_type, from, to);
+                    }
+                }
+                s::Definition::TypeDefinition(T::Union(union_type)) => {
+                    rename(&mut union_type.name);
+                    rename_directives(&mut union_type.directives, from, to);
+                    union_type.types.iter_mut().for_each(rename);
+                }
+                s::Definition::SchemaDefinition(_)
+                | s::Definition::DirectiveDefinition(_)
+                | s::Definition::TypeExtension(_) => { /* nothing to rename */ }
+            }
+        }
+
+        let id = self.id().clone();
+        let schema = Schema::new(id.clone(), document)?;
+        validations::validate(spec_version, &schema, options).map_err(|errors| {
+            SchemaValidationError::SchemaInvalid(id.to_string(), ValidationErrors(errors))
+        })?;
+        Ok(Self::create(schema))
+    }
+
+    /// Check the schema for things that are not errors, but that are
+    /// almost certainly mistakes
+    pub fn lint(&self) -> Vec<SchemaWarning> {
+        lint::lint(&self.inner.schema)
+    }
+
+    /// List the directives and directive arguments that the schema uses,
+    /// but that will be removed in a future spec version
+    pub fn deprecations(&self) -> Vec<Deprecation> {
+        lint::deprecations(&self.inner.schema, lint::DEPRECATED)
+    }
+
+    /// Render the schema the user declared as GraphQL SDL in a canonical
+    /// form: definitions are sorted by name, fields of object types and
+    /// interfaces by name, and the `@subgraphId` directives that are added
+    /// when the schema is parsed are left out. Schemas that only differ in
+    /// formatting or in the order of their declarations produce the same
+    /// result, and parsing the result again produces the same result
+    pub fn to_canonical_sdl(&self) -> String {
+        self.canonical_document(true).to_string()
+    }
+
+    /// A hash of the semantic content of this schema. It is computed from
+    /// the same canonical form as `to_canonical_sdl`, but also ignores
+    /// descriptions; schemas that only differ in formatting, comments,
+    /// descriptions, the order of their declarations, or their deployment
+    /// hash have the same hash
+    pub fn schema_hash(&self) -> [u8; 32] {
+        let sdl = self.canonical_document(false).to_string();
+        tiny_keccak::keccak256(sdl.as_bytes())
+    }
+
+    /// The resolved structure of this schema as JSON, meant for exporting
+    /// it to other tools. The output has the keys `types`, `enums`,
+    /// `fulltext`, and `aggregations`; types are sorted by name, and their
+    /// fields and the values of enums are listed in the order in which they
+    /// are declared. New keys might be added in the future, but the
+    /// existing ones will keep their meaning
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        let name = |atom: Atom| self.inner.pool.get(atom).expect("names are interned");
+
+        let mut types: Vec<_> = self
+            .inner
+            .type_infos
+            .iter()
+            .filter(|ti| name(ti.name()) != POI_OBJECT)
+            .collect();
+        types.sort_by_key(|ti| name(ti.name()));
+        let types: Vec<_> = types
+            .into_iter()
+            .map(|ti| {
+                let kind = match ti.kind() {
+                    TypeKind::Object => "object",
+                    TypeKind::Interface => "interface",
+                    TypeKind::Aggregation => "aggregation",
+                };
+                let fields: Vec<_> = ti
+                    .fields()
+                    .iter()
+                    .map(|field| {
+                        json!({
+                            "name": field.name.as_str(),
+                            "type": field.field_type.to_string(),
+                            "value_type": field.value_type.to_str(),
+                            "derived": field.is_derived,
+                        })
+                    })
+                    .collect();
+                let timeseries = match ti {
+                    TypeInfo::Object(obj_type) => obj_type.timeseries,
+                    TypeInfo::Interface(_) | TypeInfo::Aggregation(_) => false,
+                };
+                json!({
+                    "name": name(ti.name()),
+                    "kind": kind,
+                    "id_type": ti.id_type().map(|id_type| id_type.to_string()),
+                    "immutable": ti.is_immutable(),
+                    "timeseries": timeseries,
+                    "interfaces": ti.interfaces().collect::<Vec<_>>(),
+                    "fields": fields,
+                })
+            })
+            .collect();
+
+        let enums: serde_json::Map<_, _> = self
+            .enums()
+            .map(|(name, values)| (name.to_string(), json!(values)))
+            .collect();
+
+        let mut fulltext = self.fulltext_definitions();
+        fulltext.sort_by(|a, b| a.name.cmp(&b.name));
+        let fulltext: Vec<_> = fulltext
+            .into_iter()
+            .map(|defn| {
+                let mut fields: Vec<_> = defn.included_fields.into_iter().collect();
+                fields.sort();
+                json!({
+                    "name": defn.name,
+                    "entity": defn.entity,
+                    "fields": fields,
+                    "language": defn.config.language.as_sql().trim_matches('\''),
+                    "algorithm": defn.config.algorithm.as_str(),
+                })
+            })
+            .collect();
+
+        let mut aggregations: Vec<_> = self.aggregations().collect();
+        aggregations.sort_by_key(|agg| agg.type_name(self));
+        let aggregations: Vec<_> = aggregations
+            .into_iter()
+            .map(|agg| {
+                let aggregates: Vec<_> = agg
+                    .aggregates
+                    .iter()
+                    .map(|aggregate| {
+                        json!({
+                            "name": aggregate.name.as_str(),
+                            "fn": aggregate.func.as_str(),
+                            "arg": aggregate.arg.as_ref().map(|arg| arg.name.as_str()),
+                            "type": aggregate.field_type.to_string(),
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": agg.type_name(self),
+                    "source": agg.source_type(self).as_str(),
+                    "intervals": agg.intervals.iter().map(|i| i.as_str()).collect::<Vec<_>>(),
+                    "aggregates": aggregates,
+                })
+            })
+            .collect();
+
+        json!({
+            "types": types,
+            "enums": enums,
+            "fulltext": fulltext,
+            "aggregations": aggregations,
+        })
+    }
+
+    /// The document the user declared in canonical form, see
+    /// `to_canonical_sdl`. Descriptions are removed unless `descriptions`
+    /// is `true`
+    fn canonical_document(&self, descriptions: bool) -> s::Document {
+        fn strip_subgraph_id(directives: &mut Vec<s::Directive>) {
+            directives.retain(|dir| dir.name != "subgraphId");
+        }
+
+        fn strip_field(field: &mut s::Field) {
+            field.description = None;
+            for arg in &mut field.arguments {
+                arg.description = None;
+            }
+        }
+
+        fn sort_key(defn: &s::Definition) -> (u8, &str) {
+            use s::TypeDefinition as T;
+
+            match defn {
+                s::Definition::SchemaDefinition(_) => (0, ""),
+                s::Definition::DirectiveDefinition(dir) => (1, dir.name.as_str()),
+                s::Definition::TypeDefinition(T::Scalar(t)) => (2, t.name.as_str()),
+                s::Definition::TypeDefinition(T::Enum(t)) => (2, t.name.as_str()),
+                s::Definition::TypeDefinition(T::Interface(t)) => (2, t.name.as_str()),
+                s::Definition::TypeDefinition(T::Object(t)) => (2, t.name.as_str()),
+                s::Definition::TypeDefinition(T::InputObject(t)) => (2, t.name.as_str()),
+                s::Definition::TypeDefinition(T::Union(t)) => (2, t.name.as_str()),
+                s::Definition::TypeExtension(_) => (3, ""),
+            }
+        }
+
+        let mut document = self.inner.schema.document.clone();
+        for defn in &mut document.definitions {
+            match defn {
+                s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => {
+                    strip_subgraph_id(&mut obj_type.directives);
+                    obj_type.implements_interfaces.sort();
+                    obj_type.fields.sort_by(|a, b| a.name.cmp(&b.name));
+                    if !descriptions {
+                        obj_type.description = None;
+                        obj_type.fields.iter_mut().for_each(strip_field);
+                    }
+                }
+                s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                    strip_subgraph_id(&mut intf_type.directives);
+                    intf_type.fields.sort_by(|a, b| a.name.cmp(&b.name));
+                    if !descriptions {
+                        intf_type.description = None;
+                        intf_type.fields.iter_mut().for_each(strip_field);
+                    }
+                }
+                s::Definition::TypeDefinition(s::TypeDefinition::Enum(enum_type)) => {
+                    strip_subgraph_id(&mut enum_type.directives);
+                    if !descriptions {
+                        enum_type.description = None;
+                        for value in &mut enum_type.values {
+                            value.description = None;
+                        }
+                    }
+                }
+                s::Definition::TypeDefinition(s::TypeDefinition::Scalar(scalar_type)) => {
+                    strip_subgraph_id(&mut scalar_type.directives);
+                    if !descriptions {
+                        scalar_type.description = None;
+                    }
+                }
+                s::Definition::TypeDefinition(s::TypeDefinition::InputObject(input_type)) => {
+                    strip_subgraph_id(&mut input_type.directives);
+                    if !descriptions {
+                        input_type.description = None;
+                        for field in &mut input_type.fields {
+                            field.description = None;
+                        }
+                    }
+                }
+                s::Definition::TypeDefinition(s::TypeDefinition::Union(union_type)) => {
+                    strip_subgraph_id(&mut union_type.directives);
+                    if !descriptions {
+                        union_type.description = None;
+                    }
+                }
+                s::Definition::SchemaDefinition(_)
+                | s::Definition::DirectiveDefinition(_)
+                | s::Definition::TypeExtension(_) => { /* nothing to normalize */ }
+            }
+        }
+        document
+            .definitions
+            .sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+        document
+    }
+
     /// Generate the `ApiSchema` for use with GraphQL queries for this
     /// `InputSchema`
     pub fn api_schema(&self) -> Result<ApiSchema, anyhow::Error> {
@@ -995,7 +1576,21 @@ impl InputSchema {
         Err(err)
     }
 
-    pub(in crate::schema) fn id_type(&self, entity_type: Atom) -> Result<store::IdType, Error> {
+    /// Return the type of the `id` of `entity_type`. This is the type
+    /// that the store uses for the primary key of the entity's table, and
+    /// for any column that references `entity_type`
+    ///
+    /// # Panics
+    ///
+    /// If `entity_type` does not belong to this schema
+    pub fn id_type(&self, entity_type: &EntityType) -> store::IdType {
+        match self.id_type_of(entity_type.atom) {
+            Ok(id_type) => id_type,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub(in crate::schema) fn id_type_of(&self, entity_type: Atom) -> Result<store::IdType, Error> {
         let type_info = self.type_info(entity_type)?;
 
         type_info.id_type().ok_or_else(|| {
@@ -1012,6 +1607,120 @@ impl InputSchema {
             .unwrap_or(false)
     }
 
+    /// Return the fields of `entity_type` in the order in which they are
+    /// declared in the schema. This is the same view of the fields that
+    /// the store uses; in particular, the `value_type` of a field that
+    /// references another entity is the type of that entity's `id`
+    pub fn fields_for(&self, entity_type: &EntityType) -> impl Iterator<Item = &Field> {
+        let fields = match self.type_info(entity_type.atom) {
+            // For the object types of an aggregation like `Stats_hour`,
+            // `type_info` returns the aggregation
+            Ok(TypeInfo::Aggregation(_)) => self
+                .object_type(entity_type.atom)
+                .map(|obj_type| obj_type.fields.as_ref())
+                .unwrap_or(&[]),
+            Ok(ti) => ti.fields(),
+            Err(_) => &[],
+        };
+        fields.iter()
+    }
+
+    /// Resolve a path like `Token.owner` to the field `owner` of the
+    /// object type, interface, or aggregation `Token`. Return `None` if
+    /// the path is not of that form, or if there is no such type or field
+    pub fn resolve_field_path(&self, path: &str) -> Option<&Field> {
+        let (type_name, field_name) = path.split_once('.')?;
+        let entity_type = self.entity_type(type_name).ok()?;
+        self.fields_for(&entity_type)
+            .find(|field| field.name.as_str() == field_name)
+    }
+
+    /// List the fields of `entity_type` that the store will create an
+    /// index for when it creates the table for `entity_type`, followed by
+    /// its fulltext searches. This mirrors the default indexing strategy of
+    /// the relational layout: every column is indexed except for derived
+    /// fields, which have no column, and lists of enums or numbers. Other
+    /// lists are only indexed if `GRAPH_STORE_CREATE_GIN_INDEXES` is set.
+    /// Single references use a GiST index on the reference and the block
+    /// range for mutable entity types
+    pub fn indexed_fields(&self, entity_type: &EntityType) -> Vec<FieldIndex> {
+        let immutable = entity_type.is_immutable();
+        let mut indexes: Vec<_> = self
+            .fields_for(entity_type)
+            .filter(|field| !field.is_derived)
+            .filter_map(|field| {
+                let base_type = field.field_type.get_base_type();
+                let method = if field.is_list() {
+                    let numeric = matches!(
+                        field.value_type,
+                        ValueType::BigDecimal | ValueType::BigInt | ValueType::Int
+                    );
+                    if numeric || self.is_enum_type(base_type) || !ENV_VARS.store.create_gin_indexes
+                    {
+                        return None;
+                    }
+                    IndexMethod::Gin
+                } else if self.is_reference(base_type) && !immutable {
+                    IndexMethod::Gist
+                } else {
+                    IndexMethod::BTree
+                };
+                Some(FieldIndex {
+                    name: field.name.to_string(),
+                    method,
+                })
+            })
+            .collect();
+        indexes.extend(
+            self.entity_fulltext_definitions(entity_type.as_str())
+                .into_iter()
+                .map(|fulltext| FieldIndex {
+                    name: fulltext.name,
+                    method: IndexMethod::Gin,
+                }),
+        );
+        indexes
+    }
+
+    /// Return the directives on the type `name` in the order in which they
+    /// appear in the schema, including the ones that graph-node does not
+    /// interpret, but not the `@subgraphId` directive that is added when
+    /// the schema is parsed. Return `None` if there is no type `name`
+    pub fn directives_on_type(&self, name: &str) -> Option<Vec<&s::Directive>> {
+        use s::TypeDefinition as T;
+
+        let directives = match self.inner.schema.document.get_named_type(name)? {
+            T::Scalar(t) => &t.directives,
+            T::Object(t) => &t.directives,
+            T::Interface(t) => &t.directives,
+            T::Union(t) => &t.directives,
+            T::Enum(t) => &t.directives,
+            T::InputObject(t) => &t.directives,
+        };
+        Some(
+            directives
+                .iter()
+                .filter(|dir| dir.name != "subgraphId")
+                .collect(),
+        )
+    }
+
+    /// Return the directives on the field that `path` refers to, where
+    /// `path` has the form `Type.field` and `Type` is an object type or an
+    /// interface. Like `directives_on_type`, this includes the directives
+    /// that graph-node does not interpret. Return `None` if there is no
+    /// such field
+    pub fn directives_on_field(&self, path: &str) -> Option<Vec<&s::Directive>> {
+        let (type_name, field_name) = path.split_once('.')?;
+        let fields = match self.inner.schema.document.get_named_type(type_name)? {
+            s::TypeDefinition::Object(t) => &t.fields,
+            s::TypeDefinition::Interface(t) => &t.fields,
+            _ => return None,
+        };
+        let field = fields.iter().find(|field| field.name == field_name)?;
+        Some(field.directives.iter().collect())
+    }
+
     /// Return true if `type_name` is the name of an object or interface type
     pub fn is_reference(&self, type_name: &str) -> bool {
         self.inner
@@ -1037,6 +1746,198 @@ impl InputSchema {
         })
     }
 
+    /// Return the interfaces that `entity_type` implements, sorted by
+    /// name. The result is empty if `entity_type` is not an object type
+    pub fn interfaces_implemented_by(&self, entity_type: &EntityType) -> Vec<EntityType> {
+        let mut intfs: Vec<_> = match self.type_info(entity_type.atom) {
+            Ok(ti) => ti
+                .interfaces()
+                .map(|intf| {
+                    let atom = self
+                        .inner
+                        .pool
+                        .lookup(intf)
+                        .expect("interface names have been interned");
+                    EntityType::new(self.cheap_clone(), atom)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        intfs.sort();
+        intfs
+    }
+
+    /// Return the object types that implement `interface`, sorted by name.
+    /// The result is empty if `interface` is not an interface
+    pub fn implementers_of(&self, interface: &EntityType) -> Vec<EntityType> {
+        if !matches!(self.type_info(interface.atom), Ok(TypeInfo::Interface(_))) {
+            return Vec::new();
+        }
+        let mut types: Vec<_> = self
+            .inner
+            .type_infos
+            .iter()
+            .filter(|ti| ti.interfaces().any(|intf| intf == interface.as_str()))
+            .map(|ti| EntityType::new(self.cheap_clone(), ti.name()))
+            .collect();
+        types.sort();
+        types
+    }
+
+    /// Return an edge `(from, to, kind)` for each field of `from` that
+    /// references `to`, an object type or an interface, where `kind` says
+    /// whether the field is derived or not. A type that references another
+    /// through several fields produces an edge for each of them. Edges are
+    /// in no particular order
+    pub fn reference_graph(&self) -> impl Iterator<Item = (EntityType, EntityType, RefKind)> + '_ {
+        self.inner.type_infos.iter().flat_map(move |ti| {
+            ti.fields().iter().filter_map(move |field| {
+                let target = field.field_type.get_base_type();
+                if !self.is_reference(target) {
+                    return None;
+                }
+                let target = self
+                    .inner
+                    .pool
+                    .lookup(target)
+                    .expect("referenced types have been interned");
+                let kind = if field.is_derived {
+                    RefKind::Derived
+                } else {
+                    RefKind::Direct
+                };
+                Some((
+                    EntityType::new(self.cheap_clone(), ti.name()),
+                    EntityType::new(self.cheap_clone(), target),
+                    kind,
+                ))
+            })
+        })
+    }
+
+    /// For each entity type that other entity types reference, list the
+    /// references for which it has no field `@derivedFrom` them. A field
+    /// `source.field` counts as having an inverse if the referenced type
+    /// has a field `@derivedFrom(field: "field")` whose type is `source` or
+    /// an interface that `source` implements. Missing inverses are not an
+    /// error, but often mean that a relationship can only be queried in one
+    /// direction by accident. Entity types without missing inverses are
+    /// left out, and references are in the order in which they are declared
+    pub fn entities_without_derived_inverse(&self) -> BTreeMap<EntityType, Vec<MissingInverse>> {
+        let document = &self.inner.schema.document;
+        let has_inverse = |target: &str, source: &s::ObjectType, field: &str| {
+            let fields = match document.get_named_type(target) {
+                Some(s::TypeDefinition::Object(t)) => &t.fields,
+                Some(s::TypeDefinition::Interface(t)) => &t.fields,
+                _ => return false,
+            };
+            fields.iter().any(|inverse| {
+                let base_type = inverse.field_type.get_base_type();
+                let derived_from = inverse
+                    .find_directive("derivedFrom")
+                    .and_then(|dir| dir.argument("field"))
+                    .and_then(|value| value.as_str());
+                derived_from == Some(field)
+                    && (base_type == source.name
+                        || source
+                            .implements_interfaces
+                            .iter()
+                            .any(|intf| intf == base_type))
+            })
+        };
+
+        let mut missing: BTreeMap<EntityType, Vec<MissingInverse>> = BTreeMap::new();
+        for source in document.get_object_type_definitions() {
+            if source.find_directive(kw::ENTITY).is_none() {
+                continue;
+            }
+            for field in &source.fields {
+                let target = field.field_type.get_base_type();
+                if field.is_derived()
+                    || !self.is_reference(target)
+                    || has_inverse(target, source, &field.name)
+                {
+                    continue;
+                }
+                let (Ok(target), Ok(source)) =
+                    (self.entity_type(target), self.entity_type(source))
+                else {
+                    continue;
+                };
+                missing
+                    .entry(target.clone())
+                    .or_default()
+                    .push(MissingInverse {
+                        target,
+                        source,
+                        field: field.name.clone(),
+                    });
+            }
+        }
+        missing
+    }
+
+    /// The length of the longest chain of non-derived references between
+    /// entity types, like `Transfer.token -> Token.owner -> Account`,
+    /// which has length 2. Deep chains make expensive nested queries
+    /// possible. A reference to an interface leads to all the types that
+    /// implement it. Types that reference each other in a cycle are each
+    /// counted once, so that the depth is finite; in particular, a type
+    /// that only references itself does not make a chain longer
+    pub fn max_reference_depth(&self) -> usize {
+        use petgraph::algo::tarjan_scc;
+        use petgraph::graph::DiGraph;
+
+        let mut graph: DiGraph<EntityType, ()> = DiGraph::new();
+        let mut nodes = BTreeMap::new();
+        for (from, to, kind) in self.reference_graph() {
+            // The fields of interfaces are also fields of the types that
+            // implement them. We can't use `from.kind()` since `from` might
+            // be an aggregation
+            if kind == RefKind::Derived || self.type_kind(from.atom) == Some(TypeKind::Interface) {
+                continue;
+            }
+            let targets = match to.kind() {
+                TypeKind::Interface => self.implementers_of(&to),
+                TypeKind::Object | TypeKind::Aggregation => vec![to],
+            };
+            for to in std::iter::once(from.clone()).chain(targets.iter().cloned()) {
+                nodes
+                    .entry(to.clone())
+                    .or_insert_with(|| graph.add_node(to));
+            }
+            for to in targets {
+                graph.add_edge(nodes[&from], nodes[&to], ());
+            }
+        }
+
+        // `tarjan_scc` returns the strongly connected components in
+        // reverse topological order, so that the depth of all components
+        // a component references is known by the time we get to it. The
+        // depth of a component is the number of types on the longest
+        // chain starting in it
+        let sccs = tarjan_scc(&graph);
+        let mut component = vec![0; graph.node_count()];
+        for (idx, scc) in sccs.iter().enumerate() {
+            for node in scc {
+                component[node.index()] = idx;
+            }
+        }
+        let mut depths = vec![0; sccs.len()];
+        for (idx, scc) in sccs.iter().enumerate() {
+            let below = scc
+                .iter()
+                .flat_map(|node| graph.neighbors(*node))
+                .map(|node| component[node.index()])
+                .filter(|other| *other != idx)
+                .map(|other| depths[other])
+                .max()
+                .unwrap_or(0);
+            depths[idx] = scc.len() + below;
+        }
+        depths.into_iter().max().unwrap_or(0).saturating_sub(1)
+    }
+
     /// Return a list of all entity types that implement one of the
     /// interfaces that `entity_type` implements
     pub(in crate::schema) fn share_interfaces(
@@ -1099,7 +2000,26 @@ impl InputSchema {
         self.inner.enum_map.values(name)
     }
 
-    /// Return a list of the entity types defined in the schema, i.e., the
+    /// Return all enum types with their values. Unlike `enum_types` and
+    /// `enum_values`, enums and their values are listed in the order in
+    /// which they are declared in the schema
+    pub fn enums(&self) -> impl Iterator<Item = (&str, Vec<&str>)> {
+        self.inner
+            .schema
+            .document
+            .get_enum_definitions()
+            .into_iter()
+            .map(|enum_type| {
+                let values = enum_type
+                    .values
+                    .iter()
+                    .map(|value| value.name.as_str())
+                    .collect();
+                (enum_type.name.as_str(), values)
+            })
+    }
+
+    /// Return a list of the entity types defined in the schema, i.e., the
     /// types that have a `@entity` annotation. This does not include the
     /// type for the PoI
     pub fn entity_types(&self) -> Vec<EntityType> {
@@ -1115,6 +2035,85 @@ impl InputSchema {
             .collect()
     }
 
+    /// Return the entity types that are immutable, either because they are
+    /// declared with `@entity(immutable: true)`, or because they are
+    /// timeseries. Entity types without an `immutable` argument are
+    /// mutable. Like `entity_types`, this does not include the type for
+    /// the PoI
+    pub fn immutable_types(&self) -> Vec<EntityType> {
+        self.declared_object_types()
+            .filter(|obj_type| obj_type.immutable)
+            .map(|obj_type| EntityType::new(self.cheap_clone(), obj_type.name))
+            .collect()
+    }
+
+    /// The object types declared in the schema, i.e., the types with an
+    /// `@entity` annotation, without the PoI type
+    fn declared_object_types(&self) -> impl Iterator<Item = &ObjectType> {
+        let poi = self.inner.pool.lookup(POI_OBJECT);
+        self.inner
+            .type_infos
+            .iter()
+            .filter_map(|ti| match ti {
+                TypeInfo::Object(obj_type) => Some(obj_type),
+                TypeInfo::Interface(_) | TypeInfo::Aggregation(_) => None,
+            })
+            .filter(move |obj_type| Some(obj_type.name) != poi)
+    }
+
+    /// The number of object types declared in the schema. Like
+    /// `entity_types`, this does not count the PoI type
+    pub fn object_type_count(&self) -> usize {
+        self.declared_object_types().count()
+    }
+
+    /// The number of interfaces declared in the schema
+    pub fn interface_count(&self) -> usize {
+        self.inner
+            .type_infos
+            .iter()
+            .filter(|ti| ti.is_interface())
+            .count()
+    }
+
+    /// The total number of fields declared on object types and
+    /// interfaces in the schema
+    pub fn field_count(&self) -> usize {
+        let obj_fields: usize = self
+            .declared_object_types()
+            .map(|obj_type| obj_type.fields.len())
+            .sum();
+        let intf_fields: usize = self
+            .inner
+            .type_infos
+            .iter()
+            .filter(|ti| ti.is_interface())
+            .map(|ti| ti.fields().len())
+            .sum();
+        obj_fields + intf_fields
+    }
+
+    /// The number of fields that the object types, interfaces, and input
+    /// types of the API schema for this schema have in total; most of them
+    /// are the fields of the `<Type>_filter` input types. This generates
+    /// the definitions of the API schema, but neither sets up an
+    /// `ApiSchema` nor adds the introspection schema to it, and is
+    /// therefore cheaper than `api_schema`
+    pub fn estimated_api_schema_size(&self) -> Result<usize, anyhow::Error> {
+        let document = api_schema(&self.inner.schema)?;
+        let size = document
+            .definitions
+            .iter()
+            .map(|defn| match defn {
+                s::Definition::TypeDefinition(s::TypeDefinition::Object(t)) => t.fields.len(),
+                s::Definition::TypeDefinition(s::TypeDefinition::Interface(t)) => t.fields.len(),
+                s::Definition::TypeDefinition(s::TypeDefinition::InputObject(t)) => t.fields.len(),
+                _ => 0,
+            })
+            .sum();
+        Ok(size)
+    }
+
     /// Return a list of all the entity types for aggregations; these are
     /// types derived from types with `@aggregation` annotations
     pub fn ts_entity_types(&self) -> Vec<EntityType> {
@@ -1140,19 +2139,97 @@ impl InputSchema {
             .any(|ti| matches!(ti, TypeInfo::Aggregation(_)))
     }
 
-    pub fn entity_fulltext_definitions(
+    /// Return all the aggregations declared with `@aggregation` in the
+    /// schema, in no particular order
+    pub fn aggregations(&self) -> impl Iterator<Item = &Aggregation> {
+        self.inner
+            .type_infos
+            .iter()
+            .filter_map(TypeInfo::aggregation)
+    }
+
+    /// Return the features that this schema uses
+    pub fn features(&self) -> BTreeSet<SchemaFeature> {
+        let mut features = BTreeSet::new();
+        if !self.get_fulltext_directives().is_empty() {
+            features.insert(SchemaFeature::FullTextSearch);
+        }
+        for ti in self.inner.type_infos.iter() {
+            match ti {
+                TypeInfo::Object(obj_type) => {
+                    if obj_type.immutable {
+                        features.insert(SchemaFeature::ImmutableEntities);
+                    }
+                    if obj_type.timeseries {
+                        features.insert(SchemaFeature::Timeseries);
+                    }
+                    if obj_type.id_type == IdType::Int8 {
+                        features.insert(SchemaFeature::Int8Id);
+                    }
+                }
+                TypeInfo::Aggregation(_) => {
+                    features.insert(SchemaFeature::Aggregations);
+                }
+                TypeInfo::Interface(_) => { /* nothing to check */ }
+            }
+        }
+        features
+    }
+
+    /// Return the lowest spec version that supports all the features
+    /// this schema uses
+    pub fn minimum_spec_version(&self) -> Version {
+        self.features()
+            .iter()
+            .map(SchemaFeature::min_spec_version)
+            .max()
+            .unwrap_or(MIN_SPEC_VERSION)
+    }
+
+    /// Check whether mappings with the API version `api_version` can write
+    /// the entities of this schema. If they can not, the error lists the
+    /// features of the schema that need a newer API version. graph-node
+    /// does not check this when it deploys a subgraph, and the error
+    /// therefore has `Severity::Strict`
+    pub fn is_compatible_with_api_version(
         &self,
-        entity: &str,
-    ) -> Result<Vec<FulltextDefinition>, anyhow::Error> {
-        Self::fulltext_definitions(&self.inner.schema.document, entity)
+        api_version: &Version,
+    ) -> Result<(), SchemaValidationError> {
+        let unsupported: Vec<_> = self
+            .features()
+            .into_iter()
+            .filter(|feature| &feature.min_api_version() > api_version)
+            .map(|feature| format!("{} (needs {})", feature.as_str(), feature.min_api_version()))
+            .collect();
+        if unsupported.is_empty() {
+            Ok(())
+        } else {
+            Err(SchemaValidationError::ApiVersionTooOld(
+                api_version.clone(),
+                Strings(unsupported),
+            ))
+        }
+    }
+
+    pub fn entity_fulltext_definitions(&self, entity: &str) -> Vec<FulltextDefinition> {
+        Self::document_fulltext_definitions(&self.inner.schema.document, entity)
     }
 
-    fn fulltext_definitions(
+    /// Return all fulltext search definitions declared with `@fulltext`
+    /// directives in this schema
+    pub fn fulltext_definitions(&self) -> Vec<FulltextDefinition> {
+        self.get_fulltext_directives()
+            .into_iter()
+            .map(FulltextDefinition::from)
+            .collect()
+    }
+
+    fn document_fulltext_definitions(
         document: &s::Document,
         entity: &str,
-    ) -> Result<Vec<FulltextDefinition>, anyhow::Error> {
-        Ok(document
-            .get_fulltext_directives()?
+    ) -> Vec<FulltextDefinition> {
+        document
+            .get_fulltext_directives()
             .into_iter()
             .filter(|directive| match directive.argument("include") {
                 Some(Value::List(includes)) if !includes.is_empty() => {
@@ -1169,7 +2246,7 @@ impl InputSchema {
                 _ => false,
             })
             .map(FulltextDefinition::from)
-            .collect())
+            .collect()
     }
 
     pub fn id(&self) -> &DeploymentHash {
@@ -1180,7 +2257,7 @@ impl InputSchema {
         self.inner.schema.document.to_string()
     }
 
-    pub fn get_fulltext_directives(&self) -> Result<Vec<&s::Directive>, Error> {
+    pub fn get_fulltext_directives(&self) -> Vec<&s::Directive> {
         self.inner.schema.document.get_fulltext_directives()
     }
 
@@ -1329,7 +2406,7 @@ fn atom_pool(document: &s::Document) -> AtomPool {
     }
 
     for object_type in document.get_object_type_definitions() {
-        for defn in InputSchema::fulltext_definitions(&document, &object_type.name).unwrap() {
+        for defn in InputSchema::document_fulltext_definitions(&document, &object_type.name) {
             pool.intern(defn.name.as_str());
         }
     }
@@ -1339,7 +2416,10 @@ fn atom_pool(document: &s::Document) -> AtomPool {
 
 /// Validations for an `InputSchema`.
 mod validations {
-    use std::{collections::HashSet, str::FromStr};
+    use std::{
+        collections::{BTreeMap, BTreeSet, HashSet},
+        str::FromStr,
+    };
 
     use inflector::Inflector;
     use itertools::Itertools;
@@ -1348,16 +2428,20 @@ mod validations {
     use crate::{
         data::{
             graphql::{
-                ext::DirectiveFinder, DirectiveExt, DocumentExt, ObjectTypeExt, TypeExt, ValueExt,
+                ext::{DirectiveFinder, TypeDefinitionExt},
+                DirectiveExt, DocumentExt, ObjectTypeExt, TypeExt, ValueExt,
             },
             store::{IdType, ValueType, ID},
-            subgraph::SPEC_VERSION_1_1_0,
+            subgraph::SchemaFeature,
         },
         prelude::s,
         schema::{
-            input_schema::{kw, AggregateFn, AggregationInterval},
+            input_schema::{kw, AggregateFn, AggregationInterval, ValidationOptions},
+            lint::{id_references, immutable_lists, nullable_scalar_lists},
+            reserved_argument_names, reserved_field_names, reserved_prefix, reserved_type_names,
             FulltextAlgorithm, FulltextLanguage, Schema as BaseSchema, SchemaValidationError,
-            SchemaValidationError as Err, Strings, SCHEMA_TYPE_NAME,
+            SchemaValidationError as Err, Strings, BLOCK_FIELD_TYPE, META_FIELD_TYPE,
+            SCHEMA_TYPE_NAME,
         },
     };
 
@@ -1365,6 +2449,8 @@ mod validations {
     struct Schema<'a> {
         #[allow(dead_code)]
         spec_version: &'a Version,
+        /// The features from `ValidationOptions::features`
+        features: Option<&'a BTreeSet<SchemaFeature>>,
         schema: &'a BaseSchema,
         subgraph_schema_type: Option<&'a s::ObjectType>,
         // All entity types, excluding the subgraph schema type
@@ -1375,8 +2461,15 @@ mod validations {
     pub(super) fn validate(
         spec_version: &Version,
         schema: &BaseSchema,
+        options: &ValidationOptions,
     ) -> Result<(), Vec<SchemaValidationError>> {
-        let schema = Schema::new(spec_version, schema);
+        let schema = Schema::new(spec_version, schema).with_features(options.features.as_ref());
+
+        // Everything else we would report about an API schema would only
+        // be confusing
+        if let Some(err) = schema.validate_not_api_schema() {
+            return Err(vec![err]);
+        }
 
         let mut errors: Vec<SchemaValidationError> = [
             schema.validate_no_extra_types(),
@@ -1394,9 +2487,32 @@ mod validations {
 
         errors.append(&mut schema.validate_entity_directives());
         errors.append(&mut schema.validate_entity_type_ids());
+        errors.append(&mut schema.validate_names());
+        errors.append(&mut schema.validate_no_duplicate_types());
+        errors.append(&mut schema.validate_no_duplicate_fields());
+        errors.append(&mut schema.validate_no_duplicate_enum_values());
         errors.append(&mut schema.validate_fields());
+        errors.append(&mut schema.validate_list_types(options.strict));
+        if options.strict {
+            errors.append(&mut schema.validate_reserved_field_names());
+        }
+        if !options.allow_fulltext && !schema.schema.document.get_fulltext_directives().is_empty() {
+            errors.push(SchemaValidationError::FulltextNotAllowed);
+        }
         errors.append(&mut schema.validate_fulltext_directives());
         errors.append(&mut schema.validate_aggregations());
+        if options.strict {
+            errors.append(&mut schema.validate_known_directives());
+            errors.append(&mut schema.validate_no_conflicting_directives());
+            errors.append(&mut schema.validate_no_mandatory_cycles());
+            errors.append(&mut schema.validate_no_orphan_interfaces());
+            errors.append(&mut schema.validate_immutable_lists());
+            errors.append(&mut schema.validate_id_references());
+            errors.append(&mut schema.validate_nullable_scalar_lists());
+        }
+        if options.substreams {
+            errors.append(&mut schema.validate_substreams_field_types());
+        }
         if errors.is_empty() {
             Ok(())
         } else {
@@ -1414,6 +2530,7 @@ mod validations {
 
             Schema {
                 spec_version,
+                features: None,
                 schema,
                 subgraph_schema_type,
                 entity_types,
@@ -1421,6 +2538,18 @@ mod validations {
             }
         }
 
+        fn with_features(self, features: Option<&'a BTreeSet<SchemaFeature>>) -> Self {
+            Schema { features, ..self }
+        }
+
+        /// Whether the spec version we validate against supports `feature`
+        fn supports(&self, feature: SchemaFeature) -> bool {
+            match self.features {
+                Some(features) => features.contains(&feature),
+                None => feature.is_supported_by(self.spec_version),
+            }
+        }
+
         fn validate_schema_type_has_no_fields(&self) -> Result<(), SchemaValidationError> {
             match self.subgraph_schema_type.and_then(|subgraph_schema_type| {
                 if !subgraph_schema_type.fields.is_empty() {
@@ -1494,25 +2623,50 @@ mod validations {
                 )];
             }
 
-            // Validate that each fulltext directive has a distinct name
-            if self
+            // Validate that each fulltext directive has a distinct name.
+            // Duplicates are reported once, for the first directive with
+            // the name, and list all the directives that use it
+            let same_name: Vec<_> = self
                 .subgraph_schema_type
                 .unwrap()
                 .directives
                 .iter()
                 .filter(|directive| directive.name.eq("fulltext"))
-                .filter_map(|fulltext| {
-                    // Collect all @fulltext directives with the same name
-                    match fulltext.argument("name") {
-                        Some(s::Value::String(n)) if name.eq(n) => Some(n.as_str()),
-                        _ => None,
-                    }
+                .filter(|fulltext| {
+                    matches!(fulltext.argument("name"), Some(s::Value::String(n)) if name.eq(n))
                 })
-                .count()
-                > 1
-            {
+                .collect();
+            if same_name.len() > 1 && std::ptr::eq(same_name[0], fulltext) {
+                let declarations = same_name
+                    .iter()
+                    .map(|fulltext| {
+                        let entities = fulltext
+                            .argument("include")
+                            .and_then(|include| include.as_list())
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|include| match include {
+                                s::Value::Object(include) => match include.get("entity") {
+                                    Some(s::Value::String(entity)) => Some(format!("`{}`", entity)),
+                                    _ => None,
+                                },
+                                _ => None,
+                            })
+                            .join(", ");
+                        format!(
+                            "at line {} for {}",
+                            fulltext.position.line,
+                            if entities.is_empty() {
+                                "no entities".to_string()
+                            } else {
+                                entities
+                            }
+                        )
+                    })
+                    .join(" and ");
                 vec![SchemaValidationError::FulltextNameConflict(
                     name.to_string(),
+                    declarations,
                 )]
             } else {
                 vec![]
@@ -1596,18 +2750,25 @@ mod validations {
                         };
 
                             // Validate the included field is a String field on the local entity types specified
-                            if !&entity_type
-                            .fields
-                            .iter()
-                            .any(|field| {
-                                let base_type: &str = field.field_type.get_base_type();
-                                matches!(ValueType::from_str(base_type), Ok(ValueType::String) if field.name.eq(field_name))
-                            })
-                        {
-                            return vec![SchemaValidationError::FulltextIncludedFieldInvalid(
-                                field_name.clone(),
-                            )];
-                        };
+                            let Some(field) = entity_type
+                                .fields
+                                .iter()
+                                .find(|field| field.name.eq(field_name))
+                            else {
+                                return vec![SchemaValidationError::FulltextIncludedFieldInvalid(
+                                    field_name.clone(),
+                                )];
+                            };
+                            let base_type: &str = field.field_type.get_base_type();
+                            if !matches!(ValueType::from_str(base_type), Ok(ValueType::String)) {
+                                return vec![
+                                    SchemaValidationError::FulltextIncludedFieldNotString(
+                                        entity_type.name.clone(),
+                                        field.name.clone(),
+                                        field.field_type.to_string(),
+                                    ),
+                                ];
+                            }
                         }
                     }
                 }
@@ -1616,6 +2777,502 @@ mod validations {
             vec![]
         }
 
+        /// Check that entity types do not reference each other in a cycle
+        /// through fields that are non-nullable and not derived, since
+        /// none of the entities in such a cycle can be saved with all its
+        /// references pointing to an existing entity. A type that
+        /// references itself is fine. Such schemas have always been
+        /// accepted, and this is therefore only checked in strict mode
+        fn validate_no_mandatory_cycles(&self) -> Vec<SchemaValidationError> {
+            // The entity types and fields that each entity type must
+            // reference, sorted by name to make the result deterministic
+            let mut refs: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+            for obj_type in &self.entity_types {
+                let targets = refs.entry(obj_type.name.as_str()).or_default();
+                for field in &obj_type.fields {
+                    let s::Type::NonNullType(inner) = &field.field_type else {
+                        continue;
+                    };
+                    let s::Type::NamedType(target) = inner.as_ref() else {
+                        continue;
+                    };
+                    if field.is_derived() || target == &obj_type.name {
+                        continue;
+                    }
+                    if self.entity_types.iter().any(|t| &t.name == target) {
+                        targets.push((field.name.as_str(), target.as_str()));
+                    } else if let Some(impls) = self.schema.types_for_interface.get(target) {
+                        for imp in impls.iter().filter(|imp| imp.name != obj_type.name) {
+                            targets.push((field.name.as_str(), imp.name.as_str()));
+                        }
+                    }
+                }
+            }
+
+            // Depth-first search that reports a cycle whenever we find an
+            // edge back to a type that is on the current path
+            fn visit<'b>(
+                name: &'b str,
+                refs: &BTreeMap<&'b str, Vec<(&'b str, &'b str)>>,
+                path: &mut Vec<(&'b str, &'b str)>,
+                done: &mut HashSet<&'b str>,
+                errors: &mut Vec<SchemaValidationError>,
+            ) {
+                for (field, target) in refs.get(name).into_iter().flatten() {
+                    if let Some(start) = path.iter().position(|(typ, _)| typ == target) {
+                        let cycle: Vec<_> = path[start..]
+                            .iter()
+                            .chain(std::iter::once(&(name, *field)))
+                            .map(|(typ, field)| format!("{}.{}", typ, field))
+                            .collect();
+                        errors.push(Err::MandatoryReferenceCycle(
+                            target.to_string(),
+                            format!("{} -> {}", cycle.join(" -> "), target),
+                        ));
+                    } else if target != &name && !done.contains(target) {
+                        path.push((name, *field));
+                        visit(target, refs, path, done, errors);
+                        path.pop();
+                    }
+                }
+                done.insert(name);
+            }
+
+            let mut errors = Vec::new();
+            let mut done = HashSet::new();
+            for name in refs.keys() {
+                if !done.contains(name) {
+                    visit(name, &refs, &mut vec![], &mut done, &mut errors);
+                }
+            }
+            errors
+        }
+
+        /// Check that object types, interfaces, and their fields only use
+        /// directives that graph-node interprets, and only with arguments
+        /// that graph-node looks at. Since graph-node ignores unknown
+        /// directives and arguments, this is only checked in strict mode
+        fn validate_known_directives(&self) -> Vec<SchemaValidationError> {
+            /// The directives graph-node interprets, and their arguments
+            const KNOWN_DIRECTIVES: [(&str, &[&str]); 7] = [
+                (kw::ENTITY, &[kw::IMMUTABLE, kw::TIMESERIES]),
+                (kw::AGGREGATION, &[kw::INTERVALS, kw::SOURCE]),
+                (kw::AGGREGATE, &[kw::FUNC, kw::ARG]),
+                ("derivedFrom", &["field"]),
+                ("fulltext", &["name", "language", "algorithm", "include"]),
+                ("subgraphId", &["id"]),
+                ("deprecated", &["reason"]),
+            ];
+            let known_args = |dir: &s::Directive| {
+                KNOWN_DIRECTIVES
+                    .iter()
+                    .find(|(name, _)| dir.name == *name)
+                    .map(|(_, args)| *args)
+            };
+            // The arguments of `dir` that graph-node ignores
+            let ignored_args = |dir: &s::Directive| -> Vec<String> {
+                let known = known_args(dir).unwrap_or(&[]);
+                dir.arguments
+                    .iter()
+                    .filter(|(arg, _)| !known.contains(&arg.as_str()))
+                    .map(|(arg, _)| arg.clone())
+                    .collect()
+            };
+            let unknown = |dir: &&s::Directive| known_args(dir).is_none();
+
+            let mut errors = Vec::new();
+            for defn in &self.schema.document.definitions {
+                let (type_name, directives, fields) = match defn {
+                    s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => {
+                        (&obj_type.name, &obj_type.directives, &obj_type.fields)
+                    }
+                    s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                        (&intf_type.name, &intf_type.directives, &intf_type.fields)
+                    }
+                    _ => continue,
+                };
+                for dir in directives {
+                    if unknown(&dir) {
+                        errors.push(Err::UnknownTypeDirective(
+                            type_name.clone(),
+                            dir.name.clone(),
+                        ));
+                        continue;
+                    }
+                    for arg in ignored_args(dir) {
+                        errors.push(Err::UnknownTypeDirectiveArgument(
+                            type_name.clone(),
+                            dir.name.clone(),
+                            arg,
+                        ));
+                    }
+                }
+                for field in fields {
+                    for dir in &field.directives {
+                        if unknown(&dir) {
+                            errors.push(Err::UnknownFieldDirective(
+                                type_name.clone(),
+                                field.name.clone(),
+                                dir.name.clone(),
+                            ));
+                            continue;
+                        }
+                        for arg in ignored_args(dir) {
+                            errors.push(Err::UnknownFieldDirectiveArgument(
+                                type_name.clone(),
+                                field.name.clone(),
+                                dir.name.clone(),
+                                arg,
+                            ));
+                        }
+                    }
+                }
+            }
+            errors
+        }
+
+        /// Check that the names of all types, fields, and enum values
+        /// follow the GraphQL grammar for names. The parser only enforces
+        /// that for schemas it parses, not for documents that were
+        /// constructed some other way
+        fn validate_names(&self) -> Vec<SchemaValidationError> {
+            fn is_name(name: &str) -> bool {
+                let mut chars = name.chars();
+                chars
+                    .next()
+                    .map_or(false, |c| c == '_' || c.is_ascii_alphabetic())
+                    && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+            }
+
+            let mut errors = Vec::new();
+            for defn in &self.schema.document.definitions {
+                let s::Definition::TypeDefinition(defn) = defn else {
+                    continue;
+                };
+                let type_name = defn.name();
+                if !is_name(type_name) {
+                    errors.push(Err::InvalidTypeName(type_name.to_string()));
+                }
+                let field_names: Vec<_> = match defn {
+                    s::TypeDefinition::Object(obj_type) => {
+                        obj_type.fields.iter().map(|field| &field.name).collect()
+                    }
+                    s::TypeDefinition::Interface(intf_type) => {
+                        intf_type.fields.iter().map(|field| &field.name).collect()
+                    }
+                    s::TypeDefinition::InputObject(input_type) => {
+                        input_type.fields.iter().map(|field| &field.name).collect()
+                    }
+                    s::TypeDefinition::Enum(enum_type) => {
+                        for value in &enum_type.values {
+                            if !is_name(&value.name) {
+                                errors.push(Err::InvalidEnumValue(
+                                    type_name.to_string(),
+                                    value.name.clone(),
+                                ));
+                            }
+                        }
+                        vec![]
+                    }
+                    s::TypeDefinition::Scalar(_) | s::TypeDefinition::Union(_) => vec![],
+                };
+                for field_name in field_names {
+                    if !is_name(field_name) {
+                        errors.push(Err::InvalidFieldName(
+                            type_name.to_string(),
+                            field_name.clone(),
+                        ));
+                    }
+                }
+            }
+            errors
+        }
+
+        /// Check that list fields have a type that graph-node can store.
+        /// Lists can not be nested, for scalars and references alike. In
+        /// strict mode, the elements of lists of references that are
+        /// stored, i.e., that are not derived, must not be nullable since a
+        /// `null` does not reference anything. Existing schemas use such
+        /// lists, which is why that is only checked in strict mode
+        fn validate_list_types(&self, strict: bool) -> Vec<SchemaValidationError> {
+            fn strip_non_null(typ: &s::Type) -> &s::Type {
+                match typ {
+                    s::Type::NonNullType(inner) => inner.as_ref(),
+                    _ => typ,
+                }
+            }
+
+            let document = &self.schema.document;
+            let is_reference = |name: &str| {
+                self.entity_types.iter().any(|t| t.name == name)
+                    || document
+                        .get_interface_type_definitions()
+                        .iter()
+                        .any(|t| t.name == name)
+            };
+
+            let mut errors = Vec::new();
+            for defn in &document.definitions {
+                let (type_name, fields) = match defn {
+                    s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => {
+                        (&obj_type.name, &obj_type.fields)
+                    }
+                    s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                        (&intf_type.name, &intf_type.fields)
+                    }
+                    _ => continue,
+                };
+                for field in fields {
+                    let s::Type::ListType(elem) = strip_non_null(&field.field_type) else {
+                        continue;
+                    };
+                    let base = field.field_type.get_base_type();
+                    if let s::Type::ListType(_) = strip_non_null(elem) {
+                        let reason = format!(
+                            "lists can not be nested; use one of `[{0}]`, `[{0}!]`, `[{0}]!`, or `[{0}!]!` instead of `{1}`",
+                            base, field.field_type
+                        );
+                        errors.push(Err::InvalidListType(
+                            type_name.clone(),
+                            field.name.clone(),
+                            reason,
+                        ));
+                    } else if strict
+                        && !field.is_derived()
+                        && is_reference(base)
+                        && !elem.is_non_null()
+                    {
+                        errors.push(Err::NullableReferenceListElements(
+                            type_name.clone(),
+                            field.name.clone(),
+                            base.to_string(),
+                            field.field_type.to_string(),
+                        ));
+                    }
+                }
+            }
+            errors
+        }
+
+        /// Check that no field of an object type or interface is named like
+        /// one of the fields that graph-node adds to the `<Type>_filter`
+        /// types or one of the arguments that it adds to query fields.
+        /// Such names lead to confusing errors or to API schemas that are
+        /// hard to use, but existing schemas use some of them, which is why
+        /// this is only checked in strict mode
+        fn validate_reserved_field_names(&self) -> Vec<SchemaValidationError> {
+            let mut errors = Vec::new();
+            for defn in &self.schema.document.definitions {
+                if let s::Definition::TypeDefinition(typedef) = defn {
+                    let type_name = typedef.name();
+                    // Exact matches are rejected by `validate_reserved_types_usage`
+                    if !reserved_type_names().contains(&type_name) {
+                        if let Some(reason) = reserved_prefix(type_name, true) {
+                            errors.push(Err::ReservedTypeName(
+                                type_name.to_string(),
+                                reason.to_string(),
+                            ));
+                        }
+                    }
+                }
+                let (type_name, fields) = match defn {
+                    s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => {
+                        (&obj_type.name, &obj_type.fields)
+                    }
+                    s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                        (&intf_type.name, &intf_type.fields)
+                    }
+                    _ => continue,
+                };
+                for field in fields {
+                    let name = field.name.as_str();
+                    let reason = if reserved_field_names().contains(&name) {
+                        format!(
+                            "`{}` is a field of the `{}_filter` type that graph-node generates",
+                            name, type_name
+                        )
+                    } else if reserved_argument_names().contains(&name) {
+                        format!(
+                            "`{}` is an argument of the query fields that graph-node generates",
+                            name
+                        )
+                    } else if let Some(reason) = reserved_prefix(name, false) {
+                        reason.to_string()
+                    } else {
+                        continue;
+                    };
+                    errors.push(Err::ReservedFieldName(
+                        type_name.clone(),
+                        field.name.clone(),
+                        reason,
+                    ));
+                }
+            }
+            errors
+        }
+
+        /// Check that no object type or interface declares the same field
+        /// more than once
+        fn validate_no_duplicate_fields(&self) -> Vec<SchemaValidationError> {
+            let mut errors = Vec::new();
+            for defn in &self.schema.document.definitions {
+                let (type_name, fields) = match defn {
+                    s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => {
+                        (&obj_type.name, &obj_type.fields)
+                    }
+                    s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                        (&intf_type.name, &intf_type.fields)
+                    }
+                    _ => continue,
+                };
+                let mut seen = HashSet::new();
+                let mut reported = HashSet::new();
+                for field in fields {
+                    if !seen.insert(&field.name) && reported.insert(&field.name) {
+                        errors.push(SchemaValidationError::DuplicateField(
+                            type_name.clone(),
+                            field.name.clone(),
+                        ));
+                    }
+                }
+            }
+            errors
+        }
+
+        /// Check that no two type definitions have the same name, whether
+        /// they are of the same kind or not. The API schema can only have
+        /// one type of any name, and which one it would use is arbitrary
+        fn validate_no_duplicate_types(&self) -> Vec<SchemaValidationError> {
+            let mut kinds: Vec<(&str, Vec<&str>)> = Vec::new();
+            for defn in &self.schema.document.definitions {
+                let (name, kind) = match defn {
+                    s::Definition::TypeDefinition(typedef) => match typedef {
+                        s::TypeDefinition::Scalar(t) => (&t.name, "a scalar"),
+                        s::TypeDefinition::Object(t) => (&t.name, "an object type"),
+                        s::TypeDefinition::Interface(t) => (&t.name, "an interface"),
+                        s::TypeDefinition::Union(t) => (&t.name, "a union"),
+                        s::TypeDefinition::Enum(t) => (&t.name, "an enum"),
+                        s::TypeDefinition::InputObject(t) => (&t.name, "an input object"),
+                    },
+                    _ => continue,
+                };
+                match kinds.iter_mut().find(|(other, _)| other == name) {
+                    Some((_, seen)) => seen.push(kind),
+                    None => kinds.push((name, vec![kind])),
+                }
+            }
+            kinds
+                .into_iter()
+                .filter(|(_, kinds)| kinds.len() > 1)
+                .map(|(name, kinds)| Err::DuplicateTypeName(name.to_string(), kinds.join(" and ")))
+                .collect()
+        }
+
+        /// Check that the directives that graph-node interprets are used in a
+        /// way that has only one meaning. Since graph-node only looks at the
+        /// first directive of a kind and the first argument of a name, a
+        /// type or field must not use any of them more than once, except for
+        /// `@fulltext`, and no such directive may repeat an argument.
+        ///
+        /// Arguments that contradict each other apart from that are checked
+        /// where the directive is interpreted, e.g., a timeseries that is
+        /// declared mutable is rejected in `validate_entity_directives`.
+        /// Since graph-node has always accepted repeated directives and
+        /// arguments, and deployed schemas may contain them, this is only
+        /// checked in strict mode
+        fn validate_no_conflicting_directives(&self) -> Vec<SchemaValidationError> {
+            const SINGLE_USE: &[&str] = &[
+                kw::ENTITY,
+                kw::AGGREGATION,
+                kw::AGGREGATE,
+                "derivedFrom",
+                "subgraphId",
+                "deprecated",
+            ];
+
+            // The conflicts in `directives` as `(directive, reason)`
+            let conflicts = |directives: &[s::Directive]| {
+                let mut conflicts = Vec::new();
+                let mut seen = HashSet::new();
+                let mut reported = HashSet::new();
+                for dir in directives {
+                    let name = dir.name.as_str();
+                    if SINGLE_USE.contains(&name) && !seen.insert(name) {
+                        if reported.insert(name) {
+                            conflicts.push((
+                                dir.name.clone(),
+                                "the directive is used more than once".to_string(),
+                            ));
+                        }
+                        continue;
+                    }
+                    if !SINGLE_USE.contains(&name) && name != "fulltext" {
+                        continue;
+                    }
+                    let mut seen_args = HashSet::new();
+                    let mut reported_args = HashSet::new();
+                    for (arg, _) in &dir.arguments {
+                        if !seen_args.insert(arg) && reported_args.insert(arg) {
+                            conflicts.push((
+                                dir.name.clone(),
+                                format!("the argument `{}` is given more than once", arg),
+                            ));
+                        }
+                    }
+                }
+                conflicts
+            };
+
+            let mut errors = Vec::new();
+            for defn in &self.schema.document.definitions {
+                let (type_name, directives, fields) = match defn {
+                    s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => {
+                        (&obj_type.name, &obj_type.directives, &obj_type.fields)
+                    }
+                    s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                        (&intf_type.name, &intf_type.directives, &intf_type.fields)
+                    }
+                    _ => continue,
+                };
+                for (dir, reason) in conflicts(directives) {
+                    errors.push(Err::ConflictingTypeDirective(
+                        type_name.clone(),
+                        dir,
+                        reason,
+                    ));
+                }
+                for field in fields {
+                    for (dir, reason) in conflicts(&field.directives) {
+                        errors.push(Err::ConflictingFieldDirective(
+                            type_name.clone(),
+                            field.name.clone(),
+                            dir,
+                            reason,
+                        ));
+                    }
+                }
+            }
+            errors
+        }
+
+        /// Check that no enum declares the same value more than once
+        fn validate_no_duplicate_enum_values(&self) -> Vec<SchemaValidationError> {
+            let mut errors = Vec::new();
+            for enum_type in self.schema.document.get_enum_definitions() {
+                let mut seen = HashSet::new();
+                let mut reported = HashSet::new();
+                for value in &enum_type.values {
+                    if !seen.insert(&value.name) && reported.insert(&value.name) {
+                        errors.push(Err::DuplicateEnumValue(
+                            enum_type.name.clone(),
+                            value.name.clone(),
+                        ));
+                    }
+                }
+            }
+            errors
+        }
+
         fn validate_fields(&self) -> Vec<SchemaValidationError> {
             let local_types = self.schema.document.get_object_and_interface_type_fields();
             let local_enums = self
@@ -1653,8 +3310,8 @@ mod validations {
         /// `timeseries`, and when `timeseries` is `true`, `immutable` can
         /// not be `false`.
         ///
-        /// For timeseries, also check that there is a `timestamp` field of
-        /// type `Int8`
+        /// For timeseries, also check that the spec version supports them
+        /// and that there is a `timestamp` field of type `Int8`
         fn validate_entity_directives(&self) -> Vec<SchemaValidationError> {
             fn bool_arg(
                 dir: &s::Directive,
@@ -1683,7 +3340,12 @@ mod validations {
                         Err(e) => return Some(e),
                     };
                     if timeseries {
-                        if !immutable {
+                        if !self.supports(SchemaFeature::Timeseries) {
+                            Some(SchemaValidationError::TimeseriesNotSupported(
+                                object_type.name.clone(),
+                                self.spec_version.clone(),
+                            ))
+                        } else if !immutable {
                             Some(SchemaValidationError::MutableTimeseries(
                                 object_type.name.clone(),
                             ))
@@ -1709,7 +3371,7 @@ mod validations {
                         )),
                         Some(_) => match IdType::try_from(*object_type) {
                             Ok(IdType::Int8) => {
-                                if self.spec_version < &SPEC_VERSION_1_1_0 {
+                                if !self.supports(SchemaFeature::Int8Id) {
                                     errors.push(SchemaValidationError::IdTypeInt8NotSupported(
                                         self.spec_version.clone(),
                                     ))
@@ -1755,19 +3417,10 @@ mod validations {
                 .map(|type_name| format!("{}_orderBy", type_name))
                 .collect();
 
-            let mut reserved_types: Vec<String> = vec![
-                // The built-in scalar types
-                "Boolean".into(),
-                "ID".into(),
-                "Int".into(),
-                "BigDecimal".into(),
-                "String".into(),
-                "Bytes".into(),
-                "BigInt".into(),
-                // Reserved Query and Subscription types
-                "Query".into(),
-                "Subscription".into(),
-            ];
+            let mut reserved_types: Vec<String> = reserved_type_names()
+                .iter()
+                .map(|name| name.to_string())
+                .collect();
 
             reserved_types.append(&mut filter_types);
             reserved_types.append(&mut order_by_types);
@@ -1782,9 +3435,38 @@ mod validations {
             if reserved_types.is_empty() {
                 Ok(())
             } else {
-                Err(SchemaValidationError::UsageOfReservedTypes(Strings(
-                    reserved_types,
-                )))
+                Err(SchemaValidationError::UsageOfReservedTypes(Strings(
+                    reserved_types,
+                )))
+            }
+        }
+
+        /// Detect schemas that were generated by `api_schema()` and are
+        /// fed back in as if they were subgraph schemas. Such a schema
+        /// declares `Query` together with some of the other types that we
+        /// only ever generate
+        fn validate_not_api_schema(&self) -> Option<SchemaValidationError> {
+            const GENERATED: &[&str] = &[
+                "Query",
+                "Subscription",
+                META_FIELD_TYPE,
+                BLOCK_FIELD_TYPE,
+                "Block_height",
+                "BlockChangedFilter",
+                "OrderDirection",
+                "_SubgraphErrorPolicy_",
+            ];
+
+            let document = &self.schema.document;
+            let found: Vec<_> = GENERATED
+                .iter()
+                .filter(|name| document.get_named_type(name).is_some())
+                .map(|name| name.to_string())
+                .collect();
+            if found.len() > 1 && found[0] == "Query" {
+                Some(SchemaValidationError::GeneratedApiSchema(Strings(found)))
+            } else {
+                None
             }
         }
 
@@ -1887,8 +3569,26 @@ mod validations {
                     }
                 };
 
-                // Check that the type we are deriving from exists
+                // Check that the type we are deriving from exists. Derived
+                // fields do not store anything and are resolved by looking
+                // up the entities that reference us, so they can not have
+                // a scalar or enum type
                 let target_type_name = field.field_type.get_base_type();
+                let is_enum = || {
+                    self.schema
+                        .document
+                        .get_enum_definitions()
+                        .iter()
+                        .any(|enum_type| enum_type.name == target_type_name)
+                };
+                if ValueType::from_str(target_type_name).is_ok() || is_enum() {
+                    let msg = format!(
+                        "@derivedFrom can only be used on fields that reference an entity \
+                         or interface, but `{}` is not one",
+                        target_type_name
+                    );
+                    return Err(invalid(object_type, &field.name, &msg));
+                }
                 let target_fields = object_and_interface_type_fields
                     .get(target_type_name)
                     .ok_or_else(|| {
@@ -1899,6 +3599,22 @@ mod validations {
                         )
                     })?;
 
+                // Hierarchies, where a type derives a list of its children
+                // from their reference to their parent, are easy to get
+                // wrong; spell out what such a field needs
+                let self_ref_hint = |target_field: &str| {
+                    if target_type_name == object_type.name {
+                        format!(
+                            "; a field of `{t}` derived from `{t}` needs a field `{f}: {t}` \
+                             that references the parent `{t}`",
+                            t = object_type.name,
+                            f = target_field
+                        )
+                    } else {
+                        String::new()
+                    }
+                };
+
                 // Check that the type we are deriving from has a field with the
                 // right name and type
                 let target_field = target_fields
@@ -1906,8 +3622,10 @@ mod validations {
                     .find(|field| field.name.eq(target_field))
                     .ok_or_else(|| {
                         let msg = format!(
-                            "field `{}` does not exist on type `{}`",
-                            target_field, target_type_name
+                            "field `{}` does not exist on type `{}`{}",
+                            target_field,
+                            target_type_name,
+                            self_ref_hint(target_field)
                         );
                         invalid(object_type, &field.name, &msg)
                     })?;
@@ -1941,10 +3659,11 @@ mod validations {
                     let valid_types = valid_types.join(", ");
 
                     let msg = format!(
-                    "field `{tf}` on type `{tt}` must have one of the following types: {valid_types}",
+                    "field `{tf}` on type `{tt}` must have one of the following types: {valid_types}{hint}",
                     tf = target_field.name,
                     tt = target_type_name,
                     valid_types = valid_types,
+                    hint = self_ref_hint(&target_field.name),
                 );
                     return Err(invalid(object_type, &field.name, &msg));
                 }
@@ -1952,6 +3671,84 @@ mod validations {
             Ok(())
         }
 
+        /// Check that every interface is implemented by at least one type.
+        /// Since graph-node accepts such interfaces, and `lint` warns about
+        /// them, this is only checked in strict mode
+        fn validate_no_orphan_interfaces(&self) -> Vec<SchemaValidationError> {
+            self.schema
+                .document
+                .get_interface_type_definitions()
+                .into_iter()
+                .filter(|intf| {
+                    self.schema
+                        .types_for_interface
+                        .get(&intf.name)
+                        .map_or(true, |impls| impls.is_empty())
+                })
+                .map(|intf| Err::OrphanInterface(intf.name.clone()))
+                .collect()
+        }
+
+        /// Check that immutable entity types do not store lists of the
+        /// entities that reference them, since graph-node can not add to
+        /// these lists; see `lint::immutable_lists`. Since graph-node
+        /// accepts such lists, and `lint` warns about them, this is only
+        /// checked in strict mode
+        fn validate_immutable_lists(&self) -> Vec<SchemaValidationError> {
+            immutable_lists(&self.schema.document)
+                .into_iter()
+                .map(Err::ImmutableList)
+                .collect()
+        }
+
+        /// Check that only the `id` field of entity types has type `ID`,
+        /// since other fields of that type are most likely meant to be
+        /// references; see `lint::id_references`. This is only checked in
+        /// strict mode
+        fn validate_id_references(&self) -> Vec<SchemaValidationError> {
+            id_references(&self.schema.document)
+                .into_iter()
+                .map(Err::IdReference)
+                .collect()
+        }
+
+        /// Check that entity types only have fields that the entity changes
+        /// of a substreams data source can set. Entity changes carry `Int`,
+        /// `BigInt`, `BigDecimal`, `String`, `Bytes`, and `Boolean` values
+        /// and lists of them, but no `Int8` values. Ids are taken from the
+        /// entity change itself and can have type `Int8`, but other fields of
+        /// that type, including the `timestamp` of timeseries, could never
+        /// be set. This is only checked for substreams
+        fn validate_substreams_field_types(&self) -> Vec<SchemaValidationError> {
+            let mut errors = Vec::new();
+            for entity_type in &self.entity_types {
+                for field in &entity_type.fields {
+                    if field.name == ID.as_str() || field.is_derived() {
+                        continue;
+                    }
+                    let base_type = field.field_type.get_base_type();
+                    if let Ok(ValueType::Int8) = ValueType::from_str(base_type) {
+                        errors.push(Err::SubstreamsUnsupportedFieldType(
+                            entity_type.name.clone(),
+                            field.name.clone(),
+                            base_type.to_string(),
+                        ));
+                    }
+                }
+            }
+            errors
+        }
+
+        /// Check that the elements of lists of scalars and enums can not be
+        /// null; see `lint::nullable_scalar_lists`. This is only checked in
+        /// strict mode
+        fn validate_nullable_scalar_lists(&self) -> Vec<SchemaValidationError> {
+            nullable_scalar_lists(&self.schema.document)
+                .into_iter()
+                .map(Err::NullableScalarList)
+                .collect()
+        }
+
         fn validate_interface_id_type(&self) -> Result<(), SchemaValidationError> {
             for (intf, obj_types) in &self.schema.types_for_interface {
                 let id_types: HashSet<&str> = HashSet::from_iter(
@@ -2263,7 +4060,7 @@ mod validations {
                 }
             }
 
-            if !self.aggregations.is_empty() && self.spec_version < &SPEC_VERSION_1_1_0 {
+            if !self.aggregations.is_empty() && !self.supports(SchemaFeature::Aggregations) {
                 return vec![SchemaValidationError::AggregationsNotSupported(
                     self.spec_version.clone(),
                 )];
@@ -2322,7 +4119,7 @@ mod validations {
         }
 
         fn validate(schema: &BaseSchema) -> Result<(), Vec<SchemaValidationError>> {
-            super::validate(LATEST_VERSION, schema)
+            super::validate(LATEST_VERSION, schema, &ValidationOptions::default())
         }
 
         #[test]
@@ -2340,16 +4137,743 @@ mod validations {
                 )])
             );
 
-            let res = validate(&parse(ID_BIGINT));
-            let errs = res.unwrap_err();
-            assert_eq!(1, errs.len());
-            assert!(matches!(errs[0], SchemaValidationError::IllegalIdType(_)));
+            let res = validate(&parse(ID_BIGINT));
+            let errs = res.unwrap_err();
+            assert_eq!(1, errs.len());
+            assert!(matches!(errs[0], SchemaValidationError::IllegalIdType(_)));
+
+            let res = validate(&parse(INTF_NO_ID));
+            assert_eq!(Ok(()), res);
+
+            let res = validate(&parse(ROOT_SCHEMA));
+            assert_eq!(Ok(()), res);
+        }
+
+        #[test]
+        fn duplicate_fields() {
+            const DUP_FIELD: &str = "type Transfer @entity {
+                id: ID!
+                amount: BigInt
+                amount: BigInt
+                amount: BigInt!
+            }";
+            const INTF_DUP_FIELD: &str = "interface Named { name: String, name: String }";
+
+            let res = validate(&parse(DUP_FIELD));
+            assert_eq!(
+                res,
+                Err(vec![SchemaValidationError::DuplicateField(
+                    "Transfer".to_string(),
+                    "amount".to_string()
+                )])
+            );
+
+            let res = validate(&parse(INTF_DUP_FIELD));
+            assert_eq!(
+                res,
+                Err(vec![SchemaValidationError::DuplicateField(
+                    "Named".to_string(),
+                    "name".to_string()
+                )])
+            );
+        }
+
+        #[test]
+        fn conflicting_directives() {
+            const SCHEMA: &str = r#"
+                type _Schema_
+                    @fulltext(name: "search", name: "other", language: en, algorithm: rank,
+                        include: [{ entity: "B", fields: [{ name: "name" }] }])
+                type A @entity(immutable: true) @entity(immutable: false) { id: ID! }
+                type B @entity(immutable: true, immutable: false) {
+                    id: ID!
+                    name: String!
+                    c: [C!]! @derivedFrom(field: "b") @derivedFrom(field: "other")
+                }
+                type C @entity { id: ID!, b: B!, other: B! }
+                type Stats @entity(timeseries: true, immutable: false) {
+                    id: Int8!
+                    timestamp: Int8!
+                }"#;
+            let conflict = |type_name: &str, dir: &str, reason: &str| {
+                Err::ConflictingTypeDirective(
+                    type_name.to_string(),
+                    dir.to_string(),
+                    reason.to_string(),
+                )
+            };
+
+            // graph-node uses the first directive and argument, and only
+            // rejects contradicting arguments where it interprets them
+            let options = ValidationOptions {
+                allow_fulltext: true,
+                ..ValidationOptions::default()
+            };
+            let res = super::validate(LATEST_VERSION, &parse(SCHEMA), &options);
+            assert_eq!(Err(vec![Err::MutableTimeseries("Stats".to_string())]), res);
+
+            // Repeated directives and arguments are only rejected in
+            // strict mode
+            let strict = ValidationOptions {
+                strict: true,
+                ..options
+            };
+            let errs: Vec<_> = super::validate(LATEST_VERSION, &parse(SCHEMA), &strict)
+                .unwrap_err()
+                .into_iter()
+                .filter(|err| {
+                    matches!(
+                        err,
+                        Err::ConflictingTypeDirective(..) | Err::ConflictingFieldDirective(..)
+                    )
+                })
+                .collect();
+            assert_eq!(
+                vec![
+                    conflict(
+                        "_Schema_",
+                        "fulltext",
+                        "the argument `name` is given more than once"
+                    ),
+                    conflict("A", "entity", "the directive is used more than once"),
+                    conflict(
+                        "B",
+                        "entity",
+                        "the argument `immutable` is given more than once"
+                    ),
+                    Err::ConflictingFieldDirective(
+                        "B".to_string(),
+                        "c".to_string(),
+                        "derivedFrom".to_string(),
+                        "the directive is used more than once".to_string()
+                    ),
+                ],
+                errs
+            );
+            assert!(errs
+                .iter()
+                .all(|err| err.severity() == crate::schema::Severity::Strict));
+
+            // Unknown directives may be repeated; strict mode only
+            // complains that they are unknown
+            let res = super::validate(
+                LATEST_VERSION,
+                &parse("type D @entity @docs(page: 1) @docs(page: 2) { id: ID! }"),
+                &strict,
+            );
+            assert!(res.unwrap_err().iter().all(|err| !matches!(
+                err,
+                Err::ConflictingTypeDirective(..) | Err::ConflictingFieldDirective(..)
+            )));
+        }
+
+        #[test]
+        fn duplicate_types() {
+            const SCHEMA: &str = "
+                enum Status { active, inactive }
+                type Status @entity { id: ID! }
+                type Account @entity { id: ID! }
+                interface Account { id: ID! }
+                type Token @entity { id: ID! }";
+
+            let res = validate(&parse(SCHEMA));
+            let errors = res.unwrap_err();
+            assert!(errors.contains(&SchemaValidationError::DuplicateTypeName(
+                "Status".to_string(),
+                "an enum and an object type".to_string()
+            )));
+            assert!(errors.contains(&SchemaValidationError::DuplicateTypeName(
+                "Account".to_string(),
+                "an object type and an interface".to_string()
+            )));
+            assert!(errors.iter().any(|e| e.to_string()
+                == "The name `Status` is used for more than one type: an enum and an object type"));
+        }
+
+        #[test]
+        fn invalid_names() {
+            const SCHEMA: &str = "
+                enum Color { red, green }
+                type Thing @entity { id: ID!, color: Color! }";
+
+            // The parser rejects invalid names, and we can only construct
+            // such a schema by changing the document after parsing
+            let mut document = graphql_parser::parse_schema(SCHEMA)
+                .expect("Failed to parse schema")
+                .into_static();
+            for defn in &mut document.definitions {
+                match defn {
+                    s::Definition::TypeDefinition(s::TypeDefinition::Enum(enum_type)) => {
+                        enum_type.values[1].name = "green-ish".to_string();
+                    }
+                    s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) => {
+                        obj_type.fields[1].name = "1color".to_string();
+                    }
+                    _ => {}
+                }
+            }
+            let schema = BaseSchema::new(DeploymentHash::new("id1").unwrap(), document).unwrap();
+            assert_eq!(
+                Schema::new(LATEST_VERSION, &schema).validate_names(),
+                vec![
+                    Err::InvalidEnumValue("Color".to_string(), "green-ish".to_string()),
+                    Err::InvalidFieldName("Thing".to_string(), "1color".to_string()),
+                ]
+            );
+        }
+
+        #[test]
+        fn mandatory_cycles() {
+            fn check(schema: &str) -> Vec<SchemaValidationError> {
+                let schema = parse(schema);
+                Schema::new(LATEST_VERSION, &schema).validate_no_mandatory_cycles()
+            }
+
+            const CYCLE: &str = "
+                type A @entity { id: ID!, b: B! }
+                type B @entity { id: ID!, a: A! }";
+            assert_eq!(
+                vec![Err::MandatoryReferenceCycle(
+                    "A".to_string(),
+                    "A.b -> B.a -> A".to_string()
+                )],
+                check(CYCLE)
+            );
+
+            const NULLABLE: &str = "
+                type A @entity { id: ID!, b: B! }
+                type B @entity { id: ID!, a: A }";
+            assert_eq!(Vec::<SchemaValidationError>::new(), check(NULLABLE));
+
+            const DERIVED: &str = r#"
+                type A @entity { id: ID!, b: B! }
+                type B @entity { id: ID!, a: A! @derivedFrom(field: "b") }"#;
+            assert_eq!(Vec::<SchemaValidationError>::new(), check(DERIVED));
+
+            const SELF: &str = "type A @entity { id: ID!, parent: A! }";
+            assert_eq!(Vec::<SchemaValidationError>::new(), check(SELF));
+
+            // Cycles are only rejected in strict mode
+            let schema = parse(CYCLE);
+            assert_eq!(Ok(()), validate(&schema));
+            let strict = ValidationOptions {
+                strict: true,
+                ..ValidationOptions::default()
+            };
+            assert!(super::validate(LATEST_VERSION, &schema, &strict).is_err());
+        }
+
+        #[test]
+        fn reserved_field_name_usage() {
+            let schema = parse(
+                "type A @entity { id: ID!, first: Int, name: String }
+                 interface I { id: ID!, or: String }",
+            );
+            let errs = Schema::new(LATEST_VERSION, &schema).validate_reserved_field_names();
+            assert_eq!(
+                vec![
+                    Err::ReservedFieldName(
+                        "A".to_string(),
+                        "first".to_string(),
+                        "`first` is an argument of the query fields that graph-node generates"
+                            .to_string()
+                    ),
+                    Err::ReservedFieldName(
+                        "I".to_string(),
+                        "or".to_string(),
+                        "`or` is a field of the `I_filter` type that graph-node generates"
+                            .to_string()
+                    ),
+                ],
+                errs
+            );
+
+            // Reserved field names are only rejected in strict mode
+            assert!(
+                super::validate(LATEST_VERSION, &schema, &ValidationOptions::default()).is_ok()
+            );
+            let strict = ValidationOptions {
+                strict: true,
+                ..ValidationOptions::default()
+            };
+            // `I` is also rejected in strict mode since nothing implements it
+            let errs = super::validate(LATEST_VERSION, &schema, &strict).unwrap_err();
+            assert_eq!(
+                2,
+                errs.iter()
+                    .filter(|err| matches!(err, Err::ReservedFieldName(..)))
+                    .count()
+            );
+        }
+
+        #[test]
+        fn reserved_prefixes() {
+            let schema = parse(
+                "type _Internal_ @entity { id: ID!, __secret: String, _private: String }
+                 type _private @entity { id: ID! }",
+            );
+            let errs = Schema::new(LATEST_VERSION, &schema).validate_reserved_field_names();
+            assert_eq!(
+                vec![
+                    Err::ReservedTypeName(
+                        "_Internal_".to_string(),
+                        "names that start and end with `_`, like `_Meta_`, are reserved for \
+                         types that graph-node generates"
+                            .to_string()
+                    ),
+                    Err::ReservedFieldName(
+                        "_Internal_".to_string(),
+                        "__secret".to_string(),
+                        "names starting with `__` are reserved for GraphQL introspection"
+                            .to_string()
+                    ),
+                ],
+                errs
+            );
+
+            // Reserved prefixes are only rejected in strict mode
+            assert!(
+                super::validate(LATEST_VERSION, &schema, &ValidationOptions::default()).is_ok()
+            );
+        }
+
+        #[test]
+        fn list_types() {
+            fn check(field: &str, strict: bool) -> Vec<SchemaValidationError> {
+                let raw = format!(
+                    "type A @entity {{ id: ID!, {} }}
+                     type B @entity {{ id: ID!, a: A! }}",
+                    field
+                );
+                let schema = parse(&raw);
+                Schema::new(LATEST_VERSION, &schema).validate_list_types(strict)
+            }
+            fn ok(field: &str, strict: bool) {
+                assert_eq!(
+                    Vec::<SchemaValidationError>::new(),
+                    check(field, strict),
+                    "`{}` should be accepted",
+                    field
+                );
+            }
+            fn fails(field: &str, strict: bool, msg: &str) {
+                let errs = check(field, strict);
+                assert_eq!(1, errs.len(), "`{}` should be rejected", field);
+                assert!(
+                    errs[0].to_string().contains(msg),
+                    "`{}` fails with `{}`",
+                    field,
+                    errs[0]
+                );
+            }
+
+            for strict in [false, true] {
+                ok("x: [Int]", strict);
+                ok("x: [Int!]", strict);
+                ok("x: [Int]!", strict);
+                ok("x: [Int!]!", strict);
+                ok("x: [B!]", strict);
+                ok("x: [B!]!", strict);
+                ok("x: [B!]! @derivedFrom(field: \"a\")", strict);
+                ok("x: [B] @derivedFrom(field: \"a\")", strict);
+                fails("x: [[Int]]", strict, "lists can not be nested");
+                fails("x: [[Int!]!]!", strict, "lists can not be nested");
+                fails("x: [[B!]]", strict, "lists can not be nested");
+            }
+
+            // Nullable references in lists are only rejected in strict mode
+            ok("x: [B]", false);
+            ok("x: [B]!", false);
+            fails("x: [B]", true, "can not be null; use `[B!]` or `[B!]!`");
+            fails("x: [B]!", true, "can not be null; use `[B!]` or `[B!]!`");
+        }
+
+        #[test]
+        fn scalar_list_types() {
+            fn check(field: &str, strict: bool) -> Result<(), Vec<SchemaValidationError>> {
+                let raw = format!(
+                    "enum Color {{ RED, GREEN }}
+                     type A @entity {{ id: ID!, {} }}",
+                    field
+                );
+                let schema = parse(&raw);
+                let options = ValidationOptions {
+                    strict,
+                    ..ValidationOptions::default()
+                };
+                super::validate(LATEST_VERSION, &schema, &options)
+            }
+
+            for base in ["String", "Int", "BigDecimal", "Bytes", "Color"] {
+                // The supported shapes
+                for field in [format!("x: [{base}!]!"), format!("x: [{base}!]")] {
+                    for strict in [false, true] {
+                        assert_eq!(Ok(()), check(&field, strict), "`{}` is accepted", field);
+                    }
+                }
+                // Nullable elements are only rejected in strict mode
+                for field in [format!("x: [{base}]!"), format!("x: [{base}]")] {
+                    assert_eq!(Ok(()), check(&field, false), "`{}` is accepted", field);
+                    let errs = check(&field, true).unwrap_err();
+                    assert_eq!(1, errs.len(), "`{}` is rejected", field);
+                    assert_eq!("NULLABLE_SCALAR_LIST", errs[0].code());
+                    assert!(errs[0]
+                        .to_string()
+                        .contains(&format!("Use `[{base}!]!`, or `[{base}!]`")));
+                }
+                // Nested lists are always rejected
+                for strict in [false, true] {
+                    let errs = check(&format!("x: [[{base}!]!]!"), strict).unwrap_err();
+                    assert_eq!(
+                        vec!["INVALID_LIST_TYPE"],
+                        errs.iter().map(|e| e.code()).collect::<Vec<_>>()
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn strict_directives() {
+            const SCHEMA: &str = r#"
+            type _Schema_ @fulltext(name: "search", language: en, algorithm: rank,
+                include: [{ entity: "User", fields: [{ name: "name" }] }])
+
+            type User @entty {
+                id: ID!
+                name: String! @deprecated(reason: "use `nick`")
+                nick: String! @search
+            }
+
+            type Account @entity {
+                id: ID!
+                users: [User!]! @derivedfrom(field: "account")
+            }"#;
+
+            let schema = parse(SCHEMA);
+            let strict = ValidationOptions {
+                strict: true,
+                ..ValidationOptions::default()
+            };
+            let res = super::validate(LATEST_VERSION, &schema, &strict);
+            let errs: Vec<_> = res
+                .unwrap_err()
+                .into_iter()
+                .filter(|err| {
+                    matches!(
+                        err,
+                        Err::UnknownTypeDirective(_, _) | Err::UnknownFieldDirective(_, _, _)
+                    )
+                })
+                .collect();
+            assert_eq!(
+                vec![
+                    Err::UnknownTypeDirective("User".to_string(), "entty".to_string()),
+                    Err::UnknownFieldDirective(
+                        "User".to_string(),
+                        "nick".to_string(),
+                        "search".to_string()
+                    ),
+                    Err::UnknownFieldDirective(
+                        "Account".to_string(),
+                        "users".to_string(),
+                        "derivedfrom".to_string()
+                    ),
+                ],
+                errs
+            );
+
+            // Unknown directives are ignored when not in strict mode
+            let res = validate(&schema).unwrap_err();
+            assert!(!res.iter().any(|err| matches!(
+                err,
+                Err::UnknownTypeDirective(_, _) | Err::UnknownFieldDirective(_, _, _)
+            )));
+        }
+
+        #[test]
+        fn orphan_interfaces() {
+            const SCHEMA: &str = r#"
+            interface Named { id: ID!, name: String! }
+            interface Orphan { id: ID! }
+            type User implements Named @entity { id: ID!, name: String!, other: Orphan }"#;
+
+            let schema = parse(SCHEMA);
+            let strict = ValidationOptions {
+                strict: true,
+                ..ValidationOptions::default()
+            };
+            assert_eq!(
+                Err(vec![Err::OrphanInterface("Orphan".to_string())]),
+                super::validate(LATEST_VERSION, &schema, &strict)
+            );
+            // Without strict, orphan interfaces are only a lint
+            assert_eq!(Ok(()), validate(&schema));
+        }
+
+        #[test]
+        fn immutable_lists() {
+            const SCHEMA: &str = r#"
+            type Token @entity(immutable: true) {
+                id: ID!
+                holders: [Holder!]! @derivedFrom(field: "token")
+                minters: [Account!]!
+            }
+            type Holder @entity { id: ID!, token: Token! }
+            type Account @entity { id: ID! }
+            type Transfer @entity(immutable: true) {
+                id: ID!
+                token: Token!
+                accounts: [Account!]!
+            }"#;
+
+            let strict = ValidationOptions {
+                strict: true,
+                ..ValidationOptions::default()
+            };
+
+            // Derived lists, and lists of entities that do not reference
+            // the immutable type, are fine
+            let schema = parse(SCHEMA);
+            assert_eq!(Ok(()), super::validate(LATEST_VERSION, &schema, &strict));
+
+            // A stored list of entities that reference the immutable type
+            let schema = parse(&SCHEMA.replace(
+                r#"holders: [Holder!]! @derivedFrom(field: "token")"#,
+                "holders: [Holder!]!",
+            ));
+            let errs = super::validate(LATEST_VERSION, &schema, &strict).unwrap_err();
+            assert_eq!(1, errs.len());
+            assert_eq!("IMMUTABLE_LIST", errs[0].code());
+            assert_eq!(
+                "field `holders` of the immutable type `Token` stores a list of \
+                 `Holder` entities, but `Holder` references `Token` through `token`; since \
+                 `Token` can not be updated, the list can not change when `Holder` entities \
+                 are added. Use `@derivedFrom(field: \"token\")` instead",
+                errs[0].to_string()
+            );
+            // Without strict, this is only a lint
+            assert_eq!(Ok(()), validate(&schema));
+
+            // Mutable types can maintain such lists
+            let schema = parse(
+                &SCHEMA
+                    .replace(r#"@derivedFrom(field: "token")"#, "")
+                    .replace("type Token @entity(immutable: true)", "type Token @entity"),
+            );
+            assert_eq!(Ok(()), super::validate(LATEST_VERSION, &schema, &strict));
+        }
+
+        #[test]
+        fn id_references() {
+            let strict = ValidationOptions {
+                strict: true,
+                ..ValidationOptions::default()
+            };
+
+            // The `id` field must have type `ID`
+            let schema = parse("type User @entity { id: ID!, name: String! }");
+            assert_eq!(Ok(()), super::validate(LATEST_VERSION, &schema, &strict));
+
+            let schema = parse(
+                "type User @entity { id: ID!, name: String! }
+                 type Thing @entity { id: ID!, owner: ID! }",
+            );
+            let errs = super::validate(LATEST_VERSION, &schema, &strict).unwrap_err();
+            assert_eq!(1, errs.len());
+            assert_eq!("ID_REFERENCE", errs[0].code());
+            assert_eq!(
+                "field `owner` of `Thing` has type `ID`, which does not reference any \
+                 entity; use the referenced entity type instead, or `String` if the field \
+                 is not a reference",
+                errs[0].to_string()
+            );
+            // Without strict, this is only a lint
+            assert_eq!(Ok(()), validate(&schema));
+        }
+
+        #[test]
+        fn strict_directive_arguments() {
+            const SCHEMA: &str = r#"
+            type User @entity(immutible: true) {
+                id: ID!
+                name: String! @deprecated(reason: "use `nick`", since: "v2")
+                nick: String!
+            }
+
+            type Account @entity(immutable: true) {
+                id: ID!
+                users: [User!]! @derivedFrom(field: "account", fields: "owner")
+            }"#;
+
+            let is_arg_error = |err: &Err| {
+                matches!(
+                    err,
+                    Err::UnknownTypeDirectiveArgument(_, _, _)
+                        | Err::UnknownFieldDirectiveArgument(_, _, _, _)
+                )
+            };
+
+            let schema = parse(SCHEMA);
+            let strict = ValidationOptions {
+                strict: true,
+                ..ValidationOptions::default()
+            };
+            let res = super::validate(LATEST_VERSION, &schema, &strict);
+            let errs: Vec<_> = res.unwrap_err().into_iter().filter(is_arg_error).collect();
+            assert_eq!(
+                vec![
+                    Err::UnknownTypeDirectiveArgument(
+                        "User".to_string(),
+                        "entity".to_string(),
+                        "immutible".to_string()
+                    ),
+                    Err::UnknownFieldDirectiveArgument(
+                        "User".to_string(),
+                        "name".to_string(),
+                        "deprecated".to_string(),
+                        "since".to_string()
+                    ),
+                    Err::UnknownFieldDirectiveArgument(
+                        "Account".to_string(),
+                        "users".to_string(),
+                        "derivedFrom".to_string(),
+                        "fields".to_string()
+                    ),
+                ],
+                errs
+            );
+
+            // Unknown arguments are ignored when not in strict mode
+            let res = validate(&schema);
+            assert!(!res.iter().flatten().any(is_arg_error));
+        }
+
+        #[test]
+        fn duplicate_fulltext_names() {
+            const SCHEMA: &str = r#"
+type _Schema_
+  @fulltext(name: "search", language: en, algorithm: rank,
+    include: [{ entity: "User", fields: [{ name: "name" }] }])
+  @fulltext(name: "search", language: en, algorithm: rank,
+    include: [{ entity: "Group", fields: [{ name: "name" }] }])
+  @fulltext(name: "groups", language: en, algorithm: rank,
+    include: [{ entity: "Group", fields: [{ name: "name" }] }])
+
+type User @entity { id: ID!, name: String! }
+type Group @entity { id: ID!, name: String! }"#;
+
+            let schema = parse(SCHEMA);
+            let schema = Schema::new(LATEST_VERSION, &schema);
+            let errs = schema.validate_fulltext_directives();
+            assert_eq!(
+                vec![Err::FulltextNameConflict(
+                    "search".to_string(),
+                    "at line 3 for `User` and at line 5 for `Group`".to_string()
+                )],
+                errs
+            );
+            assert_eq!(
+                "Fulltext directive name `search` is declared more than once: \
+                 at line 3 for `User` and at line 5 for `Group`",
+                errs[0].to_string()
+            );
+        }
+
+        #[test]
+        fn fulltext_not_allowed() {
+            const SCHEMA: &str = r#"
+            type _Schema_ @fulltext(name: "search", language: en, algorithm: rank,
+                include: [{ entity: "User", fields: [{ name: "name" }] }])
+
+            type User @entity {
+                id: ID!
+                name: String!
+            }"#;
+
+            let schema = parse(SCHEMA);
+            let allow = |allow_fulltext| ValidationOptions {
+                strict: false,
+                allow_fulltext,
+                ..ValidationOptions::default()
+            };
+            let res = super::validate(LATEST_VERSION, &schema, &allow(false));
+            assert_eq!(Some(vec![Err::FulltextNotAllowed]), res.err());
+            let res = super::validate(LATEST_VERSION, &schema, &allow(true));
+            assert_eq!(Ok(()), res);
+        }
+
+        #[test]
+        fn substreams_field_types() {
+            const SCHEMA: &str = r#"
+            type Transfer @entity(immutable: true) {
+                id: Int8!
+                amount: BigInt!
+                block: Int8!
+                logs: [Int8!]!
+            }
+
+            type Volume @entity(timeseries: true) {
+                id: Int8!
+                timestamp: Int8!
+                amount: BigDecimal!
+            }"#;
+
+            let schema = parse(SCHEMA);
+            let substreams = ValidationOptions {
+                substreams: true,
+                ..ValidationOptions::default()
+            };
+            let res = super::validate(LATEST_VERSION, &schema, &ValidationOptions::default());
+            assert_eq!(Ok(()), res);
+            let res = super::validate(LATEST_VERSION, &schema, &substreams);
+            let unsupported = |type_name: &str, field: &str| {
+                Err::SubstreamsUnsupportedFieldType(
+                    type_name.to_string(),
+                    field.to_string(),
+                    "Int8".to_string(),
+                )
+            };
+            assert_eq!(
+                Some(vec![
+                    unsupported("Transfer", "block"),
+                    unsupported("Transfer", "logs"),
+                    unsupported("Volume", "timestamp"),
+                ]),
+                res.err()
+            );
+        }
+
+        #[test]
+        fn features_from_options() {
+            use crate::data::subgraph::MIN_SPEC_VERSION;
+
+            let schema = parse("type Thing @entity { id: Int8! }");
+            let features = |features: &[SchemaFeature]| ValidationOptions {
+                features: Some(features.iter().copied().collect()),
+                ..ValidationOptions::default()
+            };
 
-            let res = validate(&parse(INTF_NO_ID));
+            // Without explicit features, the spec version decides
+            let res = super::validate(&MIN_SPEC_VERSION, &schema, &ValidationOptions::default());
+            assert_eq!(
+                Some(vec![Err::IdTypeInt8NotSupported(MIN_SPEC_VERSION)]),
+                res.err()
+            );
+            let res = super::validate(LATEST_VERSION, &schema, &ValidationOptions::default());
             assert_eq!(Ok(()), res);
 
-            let res = validate(&parse(ROOT_SCHEMA));
+            // Explicit features override the spec version
+            let res = super::validate(
+                &MIN_SPEC_VERSION,
+                &schema,
+                &features(&[SchemaFeature::Int8Id]),
+            );
             assert_eq!(Ok(()), res);
+            let res = super::validate(LATEST_VERSION, &schema, &features(&[]));
+            assert_eq!(
+                Some(vec![Err::IdTypeInt8NotSupported(LATEST_VERSION.clone())]),
+                res.err()
+            );
         }
 
         #[test]
@@ -2455,6 +4979,17 @@ type Account implements Address @entity { id: ID!, txn: Transaction! @derivedFro
                 "type must be an existing entity or interface",
             );
             validate("j: B @derivedFrom(field: \"id\")", "ok");
+            validate(
+                "k: BigInt @derivedFrom(field: \"a\")",
+                "@derivedFrom can only be used on fields that reference an entity \
+                 or interface, but `BigInt` is not one",
+            );
+            validate(
+                "l: [String!]! @derivedFrom(field: \"a\")",
+                "@derivedFrom can only be used on fields that reference an entity \
+                 or interface, but `String` is not one",
+            );
+            validate("m: [H!]! @derivedFrom(field: \"a\")", "ok");
         }
 
         #[test]
@@ -2617,6 +5152,22 @@ type Gravatar @entity {
             let schema = BaseSchema::new(DeploymentHash::new("id1").unwrap(), document).unwrap();
             let schema = Schema::new(LATEST_VERSION, &schema);
             assert_eq!(schema.validate_fulltext_directives(), vec![]);
+
+            // Only String fields can be included
+            let numeric = SCHEMA
+                .replace("{ name: \"imageUrl\"}", "{ name: \"rank\"}")
+                .replace("imageUrl: String!", "rank: BigInt!");
+            let document = graphql_parser::parse_schema(&numeric).expect("Failed to parse schema");
+            let schema = BaseSchema::new(DeploymentHash::new("id1").unwrap(), document).unwrap();
+            let schema = Schema::new(LATEST_VERSION, &schema);
+            assert_eq!(
+                schema.validate_fulltext_directives(),
+                vec![SchemaValidationError::FulltextIncludedFieldNotString(
+                    "Gravatar".to_string(),
+                    "rank".to_string(),
+                    "BigInt!".to_string()
+                )]
+            );
         }
 
         #[test]
@@ -2692,7 +5243,7 @@ type Gravatar @entity {
                         Err(e) => panic!("test case {file_name} failed to parse: {e}"),
                     }
                 };
-                let res = super::validate(&version, &schema);
+                let res = super::validate(&version, &schema, &ValidationOptions::default());
                 match (valid, res) {
                     (true, Err(errs)) => {
                         panic!("{file_name} should validate: {errs:?}",);
@@ -2723,16 +5274,25 @@ type Gravatar @entity {
 
 #[cfg(test)]
 mod tests {
+    use itertools::Itertools;
+
     use crate::{
-        data::store::ID,
-        prelude::DeploymentHash,
+        data::{
+            graphql::{ext::TypeDefinitionExt, DirectiveExt},
+            store::{IdType, ValueType, ID},
+            subgraph::LATEST_VERSION,
+        },
+        prelude::{s, DeploymentHash, ENV_VARS},
         schema::{
             input_schema::{POI_DIGEST, POI_OBJECT},
-            EntityType,
+            EntityType, FulltextAlgorithm, FulltextLanguage, SchemaValidationError,
+            ValidationErrors,
         },
     };
 
-    use super::InputSchema;
+    use super::{
+        AggregateFn, AggregationInterval, InputSchema, RefKind, TypeKind, ValidationOptions,
+    };
 
     const SCHEMA: &str = r#"
       type Thing @entity {
@@ -2823,6 +5383,20 @@ mod tests {
         assert!(person.share_interfaces().unwrap().is_empty());
     }
 
+    #[test]
+    fn repeated_directives() {
+        // Schemas that repeat a directive could be deployed before
+        // conflicting directives were checked in strict mode, and the store
+        // must still be able to load them
+        const SCHEMA: &str = "type A @entity @entity(immutable: true) { id: ID! }";
+
+        let id = DeploymentHash::new("test").unwrap();
+        let schema = InputSchema::parse_latest(SCHEMA, id).unwrap();
+
+        // Only the first `@entity` counts
+        assert!(!schema.entity_type("A").unwrap().is_immutable());
+    }
+
     #[test]
     fn intern() {
         static NAMES: &[&str] = &[
@@ -2881,4 +5455,1008 @@ mod tests {
         assert!(hippo_stats_hour_ent.share_interfaces().unwrap().is_empty());
         assert!(hippo_stats_hour_ent.is_immutable());
     }
+
+    #[test]
+    fn validate() {
+        const VALID: &str = "type Thing @entity { id: ID!, name: String! }";
+        const SYNTAX_ERROR: &str = "type Thing @entity { id: ID!";
+        const NO_ID: &str = "type Thing @entity { name: String! }";
+
+        let id = DeploymentHash::new("test").unwrap();
+
+        assert_eq!(
+            Ok(()),
+            InputSchema::validate(
+                LATEST_VERSION,
+                VALID,
+                id.clone(),
+                true,
+                &ValidationOptions::default()
+            )
+        );
+
+        let res = InputSchema::validate(
+            LATEST_VERSION,
+            SYNTAX_ERROR,
+            id.clone(),
+            false,
+            &ValidationOptions::default(),
+        );
+        assert!(matches!(
+            res,
+            Err(SchemaValidationError::SchemaParseError(_))
+        ));
+
+        let res = InputSchema::validate(
+            LATEST_VERSION,
+            NO_ID,
+            id.clone(),
+            false,
+            &ValidationOptions::default(),
+        );
+        assert_eq!(
+            Err(SchemaValidationError::SchemaInvalid(
+                "test".to_string(),
+                ValidationErrors(vec![SchemaValidationError::IdFieldMissing(
+                    "Thing".to_string()
+                )])
+            )),
+            res
+        );
+
+        // `parse` reports the same errors
+        let err = InputSchema::parse(LATEST_VERSION, NO_ID, id).unwrap_err();
+        assert_eq!(res.unwrap_err().to_string(), err.to_string());
+    }
+
+    #[test]
+    fn derived_from_missing_inverse() {
+        const SCHEMA: &str = r#"
+        type Account @entity {
+            id: ID!
+            wallets: [Wallet!]! @derivedFrom(field: "owner")
+        }
+
+        type Wallet @entity {
+            id: ID!
+            account: Account!
+        }
+        "#;
+
+        let id = DeploymentHash::new("test").unwrap();
+        let res = InputSchema::validate(
+            LATEST_VERSION,
+            SCHEMA,
+            id,
+            false,
+            &ValidationOptions::default(),
+        );
+        let exp = SchemaValidationError::InvalidDerivedFrom(
+            "Account".to_string(),
+            "wallets".to_string(),
+            "field `owner` does not exist on type `Wallet`".to_string(),
+        );
+        assert_eq!(
+            Err(SchemaValidationError::SchemaInvalid(
+                "test".to_string(),
+                ValidationErrors(vec![exp])
+            )),
+            res
+        );
+    }
+
+    #[test]
+    fn self_referential_derived_from() {
+        fn validate(node: &str) -> Result<(), SchemaValidationError> {
+            let schema = format!(
+                "type Node @entity {{ id: ID!, {} children: [Node!]! @derivedFrom(field: \"parent\") }}
+                 type Category @entity {{ id: ID! }}",
+                node
+            );
+            let id = DeploymentHash::new("test").unwrap();
+            InputSchema::validate(
+                LATEST_VERSION,
+                &schema,
+                id,
+                false,
+                &ValidationOptions::default(),
+            )
+        }
+
+        fn invalid(msg: &str) -> Result<(), SchemaValidationError> {
+            Err(SchemaValidationError::SchemaInvalid(
+                "test".to_string(),
+                ValidationErrors(vec![SchemaValidationError::InvalidDerivedFrom(
+                    "Node".to_string(),
+                    "children".to_string(),
+                    msg.to_string(),
+                )]),
+            ))
+        }
+
+        const HINT: &str =
+            "a field of `Node` derived from `Node` needs a field `parent: Node` that references the parent `Node`";
+
+        // A tree
+        assert_eq!(Ok(()), validate("parent: Node"));
+        assert_eq!(Ok(()), validate("parent: Node!"));
+
+        // The inverse is missing
+        assert_eq!(
+            invalid(&format!(
+                "field `parent` does not exist on type `Node`; {}",
+                HINT
+            )),
+            validate("")
+        );
+
+        // The inverse has the wrong type
+        for parent in ["parent: Category", "parent: String"] {
+            assert_eq!(
+                invalid(&format!(
+                    "field `parent` on type `Node` must have one of the following types: \
+                     Node, Node!, [Node!], [Node!]!; {}",
+                    HINT
+                )),
+                validate(parent)
+            );
+        }
+    }
+
+    #[test]
+    fn counts() {
+        let schema = make_schema();
+
+        // Thing, Hippo, Rhino, HippoData; HippoStats is an aggregation
+        assert_eq!(4, schema.object_type_count());
+        assert_eq!(1, schema.interface_count());
+        // 2 + 2 + 2 + 4 fields on object types and 1 on Animal
+        assert_eq!(11, schema.field_count());
+    }
+
+    #[test]
+    fn estimated_api_schema_size() {
+        fn size(raw: &str) -> usize {
+            InputSchema::parse(LATEST_VERSION, raw, DeploymentHash::new("test").unwrap())
+                .unwrap()
+                .estimated_api_schema_size()
+                .unwrap()
+        }
+
+        const THING: &str = "type Thing @entity { id: ID!, name: String! }";
+
+        // The size counts the same fields as the `ApiSchema` without the
+        // introspection schema
+        let schema =
+            InputSchema::parse(LATEST_VERSION, THING, DeploymentHash::new("test").unwrap())
+                .unwrap();
+        let api_schema = schema.api_schema().unwrap();
+        let api_fields: usize = api_schema
+            .document()
+            .definitions
+            .iter()
+            .filter_map(|defn| match defn {
+                s::Definition::TypeDefinition(typedef) if !typedef.name().starts_with("__") => {
+                    match typedef {
+                        s::TypeDefinition::Object(t) => Some(&t.fields),
+                        s::TypeDefinition::Interface(t) => Some(&t.fields),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .flatten()
+            .filter(|field| !field.name.starts_with("__"))
+            .count();
+        let input_fields: usize = api_schema
+            .document()
+            .definitions
+            .iter()
+            .map(|defn| match defn {
+                s::Definition::TypeDefinition(s::TypeDefinition::InputObject(t)) => t.fields.len(),
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(api_fields + input_fields, size(THING));
+
+        // Every field adds a field to the entity type and several filters
+        let wider = size("type Thing @entity { id: ID!, name: String!, symbol: String! }");
+        assert!(wider > size(THING) + 1);
+    }
+
+    #[test]
+    fn rename_type() {
+        use crate::data::subgraph::MIN_SPEC_VERSION;
+
+        const SCHEMA: &str = r#"
+        type _Schema_ @fulltext(
+            name: "search"
+            language: en
+            algorithm: rank
+            include: [{ entity: "User", fields: [{ name: "name" }] }]
+        )
+
+        interface Named { id: ID!, name: String! }
+        type User implements Named @entity { id: ID!, name: String!, pets: [Pet!]! @derivedFrom(field: "owner") }
+        type Pet implements Named @entity { id: ID!, name: String!, owner: User! }
+        type Tag @entity { id: ID!, named: Named! }
+        "#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let options = ValidationOptions {
+            allow_fulltext: true,
+            ..ValidationOptions::default()
+        };
+        let rename = |schema: &InputSchema, from: &str, to: &str| {
+            schema.rename_type(LATEST_VERSION, from, to, &options)
+        };
+
+        let renamed = rename(&schema, "Named", "Nameable").unwrap();
+        assert!(renamed.entity_type("Named").is_err());
+        let nameable = renamed.entity_type("Nameable").unwrap();
+        assert_eq!(TypeKind::Interface, nameable.kind());
+        let implementers = renamed.implementers_of(&nameable);
+        assert_eq!(
+            vec!["Pet", "User"],
+            implementers.iter().map(|et| et.as_str()).collect_vec()
+        );
+        let user = renamed.entity_type("User").unwrap();
+        assert_eq!(
+            vec!["Nameable"],
+            user.interfaces().map(|i| i.name.as_str()).collect_vec()
+        );
+        assert!(renamed.to_canonical_sdl().contains("named: Nameable!"));
+
+        // Renaming a type that is referenced from `@derivedFrom` fields and
+        // from fulltext search
+        let renamed = rename(&schema, "User", "Person").unwrap();
+        assert_eq!("Person", renamed.fulltext_definitions()[0].entity);
+        let sdl = renamed.to_canonical_sdl();
+        assert!(sdl.contains("owner: Person!"));
+        assert!(!sdl.contains("User"));
+
+        assert_eq!(
+            Err(SchemaValidationError::RenameTypeConflict(
+                "User".to_string(),
+                "Pet".to_string()
+            )),
+            rename(&schema, "User", "Pet").map(|_| ())
+        );
+        assert_eq!(
+            Err(SchemaValidationError::RenameTypeConflict(
+                "User".to_string(),
+                "BigInt".to_string()
+            )),
+            rename(&schema, "User", "BigInt").map(|_| ())
+        );
+        assert_eq!(
+            Err(SchemaValidationError::RenameTypeUndefined(
+                "Owner".to_string()
+            )),
+            rename(&schema, "Owner", "Person").map(|_| ())
+        );
+
+        // The renamed schema is validated for the given spec version
+        let schema = InputSchema::raw("type Block @entity { id: Int8! }", "hash");
+        assert!(rename(&schema, "Block", "Header").is_ok());
+        let err = schema
+            .rename_type(&MIN_SPEC_VERSION, "Block", "Header", &options)
+            .unwrap_err();
+        assert_eq!(
+            vec![&SchemaValidationError::IdTypeInt8NotSupported(
+                MIN_SPEC_VERSION
+            )],
+            err.errors()
+        );
+    }
+
+    #[test]
+    fn fulltext_definitions() {
+        const SCHEMA: &str = r#"
+        type _Schema_ @fulltext(
+            name: "search"
+            language: en
+            algorithm: proximityRank
+            include: [{ entity: "Gravatar", fields: [{ name: "displayName" }] }]
+        )
+
+        type Gravatar @entity {
+            id: ID!
+            displayName: String!
+            imageUrl: String!
+        }
+        "#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let defns = schema.fulltext_definitions();
+        assert_eq!(1, defns.len());
+        let defn = &defns[0];
+        assert_eq!("search", defn.name);
+        assert_eq!("Gravatar", defn.entity);
+        assert_eq!(
+            vec!["displayName".to_string()],
+            defn.included_fields.iter().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(FulltextLanguage::English, defn.config.language);
+        assert_eq!(FulltextAlgorithm::ProximityRank, defn.config.algorithm);
+
+        assert!(make_schema().fulltext_definitions().is_empty());
+    }
+
+    #[test]
+    fn fields_for() {
+        let schema = make_schema();
+
+        let thing = schema.entity_type("Thing").unwrap();
+        let fields: Vec<_> = schema
+            .fields_for(&thing)
+            .map(|field| {
+                (
+                    field.name.as_str(),
+                    field.value_type,
+                    field.is_list(),
+                    field.is_nullable(),
+                    field.is_derived,
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("id", ValueType::String, false, false, false),
+                ("name", ValueType::String, false, false, false),
+            ],
+            fields
+        );
+
+        let stats_hour = schema.entity_type("HippoStats_hour").unwrap();
+        let names: Vec<_> = schema
+            .fields_for(&stats_hour)
+            .map(|field| field.name.as_str())
+            .collect();
+        assert!(names.contains(&"timestamp"));
+    }
+
+    #[test]
+    fn aggregations() {
+        let schema = make_schema();
+
+        let aggs: Vec<_> = schema.aggregations().collect();
+        assert_eq!(1, aggs.len());
+        let agg = aggs[0];
+        assert_eq!("HippoStats", agg.type_name(&schema));
+        assert_eq!("HippoData", agg.source_type(&schema).as_str());
+        assert_eq!(&[AggregationInterval::Hour][..], agg.intervals.as_ref());
+        assert_eq!(1, agg.aggregates.len());
+        assert_eq!("maxWeight", agg.aggregates[0].name.as_str());
+        assert_eq!(AggregateFn::Max, agg.aggregates[0].func);
+
+        let schema = InputSchema::raw("type Thing @entity { id: ID! }", "hash");
+        assert_eq!(0, schema.aggregations().count());
+    }
+
+    #[test]
+    fn immutable_types() {
+        const SCHEMA: &str = r#"
+        type Mutable @entity {
+            id: ID!
+        }
+
+        type ExplicitlyMutable @entity(immutable: false) {
+            id: ID!
+        }
+
+        type Immutable @entity(immutable: true) {
+            id: ID!
+        }
+
+        type Event @entity(timeseries: true) {
+            id: Int8!
+            timestamp: Int8!
+        }
+
+        interface Named {
+            id: ID!
+        }
+        "#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let immutable: Vec<_> = schema
+            .immutable_types()
+            .into_iter()
+            .map(|entity_type| entity_type.as_str().to_string())
+            .sorted()
+            .collect();
+        assert_eq!(vec!["Event", "Immutable"], immutable);
+
+        for name in ["Mutable", "ExplicitlyMutable"] {
+            assert!(!schema.entity_type(name).unwrap().is_immutable());
+        }
+        for name in ["Event", "Immutable"] {
+            assert!(schema.entity_type(name).unwrap().is_immutable());
+        }
+    }
+
+    #[test]
+    fn canonical_sdl() {
+        const ONE: &str = r#"
+        type Thing @entity { id: ID!, name: String!, color: Color }
+        enum Color { red, green }
+        type Alpha @entity(immutable: true) {
+            name: String
+            id: ID!
+        }"#;
+        const TWO: &str = r#"
+        type Alpha @entity(immutable: true) { id: ID! name: String }
+
+        enum Color {
+          red
+          green
+        }
+
+        type Thing @entity {
+          name: String!
+          color: Color
+          id: ID!
+        }"#;
+
+        let one = InputSchema::raw(ONE, "one");
+        let two = InputSchema::raw(TWO, "two");
+        let sdl = one.to_canonical_sdl();
+        assert_eq!(sdl, two.to_canonical_sdl());
+        assert!(!sdl.contains("subgraphId"));
+
+        let alpha = sdl.find("type Alpha").unwrap();
+        let color = sdl.find("enum Color").unwrap();
+        let thing = sdl.find("type Thing").unwrap();
+        assert!(alpha < color && color < thing);
+
+        // Parsing the canonical form again is idempotent
+        let again = InputSchema::parse_latest(&sdl, DeploymentHash::new("again").unwrap()).unwrap();
+        assert_eq!(sdl, again.to_canonical_sdl());
+        assert!(one.diff(&again).is_empty());
+    }
+
+    #[test]
+    fn interface_implementations() {
+        const SCHEMA: &str = r#"
+        interface Animal { id: ID!, name: String! }
+        interface Pet { id: ID!, owner: String }
+        type Dog implements Animal & Pet @entity { id: ID!, name: String!, owner: String }
+        type Cow implements Animal @entity { id: ID!, name: String! }
+        type Rock @entity { id: ID! }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let names = |types: Vec<EntityType>| -> Vec<String> {
+            types.iter().map(|t| t.as_str().to_string()).collect()
+        };
+        let typ = |name: &str| schema.entity_type(name).unwrap();
+
+        assert_eq!(
+            vec!["Animal", "Pet"],
+            names(schema.interfaces_implemented_by(&typ("Dog")))
+        );
+        assert_eq!(
+            vec!["Animal"],
+            names(schema.interfaces_implemented_by(&typ("Cow")))
+        );
+        assert!(schema.interfaces_implemented_by(&typ("Rock")).is_empty());
+        assert!(schema.interfaces_implemented_by(&typ("Animal")).is_empty());
+
+        assert_eq!(
+            vec!["Cow", "Dog"],
+            names(schema.implementers_of(&typ("Animal")))
+        );
+        assert_eq!(vec!["Dog"], names(schema.implementers_of(&typ("Pet"))));
+        assert!(schema.implementers_of(&typ("Dog")).is_empty());
+
+        // Implementers must have all the fields of the interface
+        const MISSING: &str = r#"
+        interface Animal { id: ID!, name: String! }
+        type Cow implements Animal @entity { id: ID! }"#;
+        let res = InputSchema::parse_latest(MISSING, DeploymentHash::new("hash").unwrap());
+        assert!(res
+            .unwrap_err()
+            .to_string()
+            .contains("missing the following fields"));
+
+        // Implementers must declare interface fields with the same type.
+        // Making a field non-nullable in the implementer is not enough
+        const MISMATCH: &str = r#"
+        interface Animal { id: ID!, name: String, legs: Int! }
+        type Cow implements Animal @entity { id: ID!, name: String!, legs: Int! }"#;
+        let err = InputSchema::parse_latest(MISMATCH, DeploymentHash::new("hash").unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("`Animal`"));
+        assert!(err.contains("`Cow`"));
+        assert!(err.contains("field `name` has type `String!`"));
+        assert!(err.contains("declares it as `String`"));
+    }
+
+    #[test]
+    fn indexed_fields() {
+        const SCHEMA: &str = r#"
+        enum Size { Small, Large }
+        type Token @entity {
+            id: ID!
+            name: String!
+            owner: Account
+            tags: [String!]!
+            supplies: [BigInt!]!
+            sizes: [Size!]!
+            transfers: [Transfer!]! @derivedFrom(field: "token")
+        }
+        type Account @entity { id: Bytes! }
+        type Transfer @entity(immutable: true) { id: ID!, token: Token! }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let indexes = |name: &str| {
+            let entity_type = schema.entity_type(name).unwrap();
+            schema
+                .indexed_fields(&entity_type)
+                .into_iter()
+                .map(|index| format!("{}:{}", index.name, index.method.as_str()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut expected = vec!["id:btree", "name:btree", "owner:gist"];
+        if ENV_VARS.store.create_gin_indexes {
+            expected.push("tags:gin");
+        }
+        assert_eq!(expected, indexes("Token"));
+        assert_eq!(vec!["id:btree", "token:btree"], indexes("Transfer"));
+    }
+
+    #[test]
+    fn directives() {
+        const SCHEMA: &str = r#"
+        type Token @entity(immutable: true) @docs(page: "tokens") {
+            id: ID!
+            name: String! @docs(page: "names", hidden: false)
+            symbol: String!
+        }
+        enum Size @docs(page: "sizes") { Small, Large }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let names =
+            |dirs: Vec<&s::Directive>| dirs.iter().map(|dir| dir.name.as_str()).collect::<Vec<_>>();
+
+        let dirs = schema.directives_on_type("Token").unwrap();
+        assert_eq!(vec!["entity", "docs"], names(dirs.clone()));
+        assert_eq!(
+            Some(&s::Value::String("tokens".to_string())),
+            dirs[1].argument("page")
+        );
+        assert_eq!(
+            vec!["docs"],
+            names(schema.directives_on_type("Size").unwrap())
+        );
+
+        let dirs = schema.directives_on_field("Token.name").unwrap();
+        assert_eq!(vec!["docs"], names(dirs.clone()));
+        assert_eq!(
+            vec![
+                ("page".to_string(), s::Value::String("names".to_string())),
+                ("hidden".to_string(), s::Value::Boolean(false)),
+            ],
+            dirs[0].arguments
+        );
+        assert!(schema
+            .directives_on_field("Token.symbol")
+            .unwrap()
+            .is_empty());
+
+        assert!(schema.directives_on_type("NoSuchType").is_none());
+        assert!(schema.directives_on_field("Token.noSuchField").is_none());
+        assert!(schema.directives_on_field("Size.Small").is_none());
+    }
+
+    #[test]
+    fn resolve_field_path() {
+        const SCHEMA: &str = r#"
+        interface Named { id: ID!, name: String! }
+        type Token implements Named @entity {
+            id: ID!
+            name: String!
+            owner: Account
+            transfers: [Transfer!]! @derivedFrom(field: "token")
+        }
+        type Account @entity { id: Bytes! }
+        type Transfer @entity { id: ID!, token: Token! }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+
+        let owner = schema.resolve_field_path("Token.owner").unwrap();
+        assert_eq!("owner", owner.name.as_str());
+        assert_eq!(ValueType::Bytes, owner.value_type);
+        assert!(owner.is_nullable());
+        assert!(!owner.is_derived);
+
+        let transfers = schema.resolve_field_path("Token.transfers").unwrap();
+        assert!(transfers.is_list());
+        assert!(transfers.is_derived);
+
+        assert!(schema.resolve_field_path("Named.name").is_some());
+
+        assert!(schema.resolve_field_path("NoSuchType.name").is_none());
+        assert!(schema.resolve_field_path("Token.noSuchField").is_none());
+        assert!(schema.resolve_field_path("Token").is_none());
+    }
+
+    #[test]
+    fn enums() {
+        const SCHEMA: &str = r#"
+        enum Size { Small, Medium, Large }
+        type Shirt @entity { id: ID!, size: Size!, color: Color }
+        enum Color { Red, Green, Blue, Black }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        assert_eq!(
+            vec![
+                ("Size", vec!["Small", "Medium", "Large"]),
+                ("Color", vec!["Red", "Green", "Blue", "Black"]),
+            ],
+            schema.enums().collect::<Vec<_>>()
+        );
+
+        const DUPLICATE: &str = r#"
+        enum Size { Small, Medium, Small }
+        type Shirt @entity { id: ID!, size: Size! }"#;
+        let err =
+            InputSchema::parse_latest(DUPLICATE, DeploymentHash::new("hash").unwrap()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Enum `Size` declares the value `Small` more than once"));
+    }
+
+    #[test]
+    fn generated_api_schema() {
+        let id = DeploymentHash::new("hash").unwrap();
+        let schema =
+            InputSchema::parse_latest("type Token @entity { id: ID!, name: String! }", id.clone())
+                .unwrap();
+        let api_sdl = schema.api_schema().unwrap().document().to_string();
+
+        let err = InputSchema::parse_latest(&api_sdl, id).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("This looks like a generated API schema, not a subgraph schema"));
+    }
+
+    #[test]
+    fn parse_bytes() {
+        let id = DeploymentHash::new("hash").unwrap();
+        let options = ValidationOptions::default();
+
+        let schema =
+            InputSchema::parse_bytes(LATEST_VERSION, SCHEMA.as_bytes(), id.clone(), &options)
+                .unwrap();
+        assert!(schema.entity_type("Thing").is_ok());
+
+        // A stray byte from bad escaping in a dump
+        let mut raw = b"type Thing @entity { id: ID!, name: String! } # ".to_vec();
+        raw.push(0xff);
+        let err = InputSchema::parse_bytes(LATEST_VERSION, &raw, id, &options).unwrap_err();
+        match err.downcast_ref::<SchemaValidationError>() {
+            Some(SchemaValidationError::Encoding(msg)) => assert!(msg.contains("index 48")),
+            _ => panic!("expected an encoding error but got {}", err),
+        }
+    }
+
+    #[test]
+    fn id_types() {
+        const SCHEMA: &str = r#"
+        type Account @entity { id: Bytes!, name: String! }
+        type Transfer @entity { id: String!, from: Account! }
+        type Token @entity { id: ID! }
+        type Stats @entity(timeseries: true) { id: Int8!, timestamp: Int8!, amount: BigInt! }"#;
+
+        let schema =
+            InputSchema::parse_latest(SCHEMA, DeploymentHash::new("hash").unwrap()).unwrap();
+        let id_type = |name: &str| schema.id_type(&schema.entity_type(name).unwrap());
+
+        assert_eq!(IdType::Bytes, id_type("Account"));
+        assert_eq!(IdType::String, id_type("Transfer"));
+        assert_eq!(IdType::String, id_type("Token"));
+        assert_eq!(IdType::Int8, id_type("Stats"));
+        // References use the id type of the entity they point to
+        assert_eq!(
+            Some(&ValueType::Bytes),
+            schema
+                .fields_for(&schema.entity_type("Transfer").unwrap())
+                .find(|field| field.name.as_str() == "from")
+                .map(|field| &field.value_type)
+        );
+    }
+
+    #[test]
+    fn reference_graph() {
+        const SCHEMA: &str = r#"
+        interface Named { id: ID!, name: String! }
+        type Owner @entity {
+            id: ID!
+            pets: [Pet!]! @derivedFrom(field: "owner")
+            favorite: Named
+        }
+        type Pet implements Named @entity {
+            id: ID!
+            name: String!
+            owner: Owner!
+            previousOwner: Owner
+        }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let edges: Vec<_> = schema
+            .reference_graph()
+            .map(|(from, to, kind)| (from.as_str().to_string(), to.as_str().to_string(), kind))
+            .sorted()
+            .collect();
+        let edge = |from: &str, to: &str, kind| (from.to_string(), to.to_string(), kind);
+        assert_eq!(
+            vec![
+                edge("Owner", "Named", RefKind::Direct),
+                edge("Owner", "Pet", RefKind::Derived),
+                edge("Pet", "Owner", RefKind::Direct),
+                edge("Pet", "Owner", RefKind::Direct),
+            ],
+            edges
+        );
+    }
+
+    #[test]
+    fn entities_without_derived_inverse() {
+        const SCHEMA: &str = r#"
+        interface Named { id: ID!, name: String! }
+        type Owner @entity {
+            id: ID!
+            pets: [Pet!]! @derivedFrom(field: "owner")
+            named: [Named!]! @derivedFrom(field: "keeper")
+        }
+        type Pet implements Named @entity {
+            id: ID!
+            name: String!
+            owner: Owner!
+            keeper: Owner
+            previousOwner: Owner
+            mother: Pet
+        }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let missing: Vec<_> = schema
+            .entities_without_derived_inverse()
+            .into_iter()
+            .map(|(target, refs)| {
+                let refs: Vec<_> = refs
+                    .iter()
+                    .map(|r| format!("{}.{}", r.source, r.field))
+                    .collect();
+                (target.to_string(), refs)
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("Owner".to_string(), vec!["Pet.previousOwner".to_string()]),
+                ("Pet".to_string(), vec!["Pet.mother".to_string()]),
+            ],
+            missing
+        );
+
+        let missing =
+            &schema.entities_without_derived_inverse()[&schema.entity_type("Pet").unwrap()];
+        assert_eq!(
+            "`Pet.mother` references `Pet`, but `Pet` has no field derived from it; \
+             consider adding `pets: [Pet!]! @derivedFrom(field: \"mother\")` to `Pet`",
+            missing[0].to_string()
+        );
+    }
+
+    #[test]
+    fn max_reference_depth() {
+        let depth = |schema: &str| InputSchema::raw(schema, "hash").max_reference_depth();
+
+        assert_eq!(0, depth("type Thing @entity { id: ID! }"));
+        assert_eq!(
+            2,
+            depth(
+                "type Account @entity { id: ID!, tokens: [Token!]! @derivedFrom(field: \"owner\") }
+                 type Token @entity { id: ID!, owner: Account! }
+                 type Transfer @entity { id: ID!, token: Token!, previous: Transfer }"
+            )
+        );
+        // Self-references don't count, and each type in a cycle counts
+        // once
+        assert_eq!(0, depth("type Node @entity { id: ID!, parent: Node }"));
+        assert_eq!(
+            2,
+            depth(
+                "type A @entity { id: ID!, b: B }
+                 type B @entity { id: ID!, a: A, c: C }
+                 type C @entity { id: ID! }"
+            )
+        );
+        // References to interfaces lead to their implementers
+        assert_eq!(
+            2,
+            depth(
+                "interface Named { id: ID!, name: String! }
+                 type Pet implements Named @entity { id: ID!, name: String!, owner: Owner! }
+                 type Owner @entity { id: ID! }
+                 type Tag @entity { id: ID!, named: Named! }"
+            )
+        );
+    }
+
+    #[test]
+    fn minimum_spec_version() {
+        use crate::data::subgraph::{SchemaFeature, MIN_SPEC_VERSION, SPEC_VERSION_1_1_0};
+
+        let schema = InputSchema::raw("type Thing @entity { id: ID! }", "hash");
+        assert!(schema.features().is_empty());
+        assert_eq!(MIN_SPEC_VERSION, schema.minimum_spec_version());
+
+        let schema = InputSchema::raw(
+            "type Thing @entity(immutable: true) { id: ID! }
+             type Other @entity { id: Int8! }",
+            "hash",
+        );
+        assert_eq!(
+            vec![SchemaFeature::ImmutableEntities, SchemaFeature::Int8Id],
+            schema.features().into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(SPEC_VERSION_1_1_0, schema.minimum_spec_version());
+
+        let schema = make_schema();
+        assert!(schema.features().contains(&SchemaFeature::Aggregations));
+        assert!(schema.features().contains(&SchemaFeature::Timeseries));
+        assert_eq!(SPEC_VERSION_1_1_0, schema.minimum_spec_version());
+    }
+
+    #[test]
+    fn api_version_compatibility() {
+        use crate::data::subgraph::{
+            API_VERSION_0_0_2, API_VERSION_0_0_6, API_VERSION_0_0_7, API_VERSION_0_0_8,
+        };
+        use crate::schema::Severity;
+
+        let schema = InputSchema::raw(
+            "type Block @entity(immutable: true) { id: Bytes!, number: Int! }",
+            "hash",
+        );
+        assert!(!schema.features().is_empty());
+        assert_eq!(
+            Ok(()),
+            schema.is_compatible_with_api_version(&API_VERSION_0_0_2)
+        );
+
+        let schema = InputSchema::raw(
+            "type Block @entity(immutable: true) { id: Int8!, number: Int! }",
+            "hash",
+        );
+        for version in [API_VERSION_0_0_7, API_VERSION_0_0_8] {
+            assert_eq!(Ok(()), schema.is_compatible_with_api_version(&version));
+        }
+        let err = schema
+            .is_compatible_with_api_version(&API_VERSION_0_0_6)
+            .unwrap_err();
+        assert_eq!(
+            "Mappings with API version 0.0.6 can not write entities that use int8Id (needs 0.0.7)",
+            err.to_string()
+        );
+        // The validator only fails the schema for this in strict mode, and
+        // warns about it otherwise
+        assert_eq!(Severity::Strict, err.severity());
+        assert!(err.is_recoverable());
+
+        let schema = make_schema();
+        let err = schema
+            .is_compatible_with_api_version(&API_VERSION_0_0_2)
+            .unwrap_err();
+        let SchemaValidationError::ApiVersionTooOld(_, features) = &err else {
+            panic!("expected ApiVersionTooOld but got {}", err);
+        };
+        assert!(features.0.contains(&"timeseries (needs 0.0.7)".to_string()));
+        assert!(features
+            .0
+            .contains(&"aggregations (needs 0.0.7)".to_string()));
+    }
+
+    #[test]
+    fn schema_hash() {
+        const ONE: &str = r#"
+        # A comment
+        type Thing @entity { id: ID!, name: String! }
+        type Alpha @entity { id: ID! }"#;
+        const TWO: &str = r#"
+        type Alpha @entity {
+          id: ID!
+        }
+
+        "A thing"
+        type Thing @entity {
+          "The name of the thing"
+          name: String!
+          id: ID!
+        }"#;
+        const THREE: &str = r#"
+        type Thing @entity { id: ID!, name: String }
+        type Alpha @entity { id: ID! }"#;
+
+        let one = InputSchema::raw(ONE, "one");
+        let two = InputSchema::raw(TWO, "two");
+        let three = InputSchema::raw(THREE, "three");
+        assert_eq!(one.schema_hash(), two.schema_hash());
+        assert_ne!(one.schema_hash(), three.schema_hash());
+    }
+
+    #[test]
+    fn to_json() {
+        const SCHEMA: &str = r#"
+        enum Color { RED, GREEN }
+        interface Named { id: ID!, name: String! }
+        type Thing implements Named @entity(immutable: true) {
+            id: ID!
+            name: String!
+            color: Color
+            parts: [Part!]! @derivedFrom(field: "thing")
+        }
+        type Part @entity { id: Bytes!, thing: Thing! }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let field = |name: &str, typ: &str, value_type: &str, derived: bool| {
+            serde_json::json!({
+                "name": name,
+                "type": typ,
+                "value_type": value_type,
+                "derived": derived
+            })
+        };
+        assert_eq!(
+            serde_json::json!({
+                "types": [
+                    {
+                        "name": "Named",
+                        "kind": "interface",
+                        "id_type": "String",
+                        "immutable": false,
+                        "timeseries": false,
+                        "interfaces": [],
+                        "fields": [
+                            field("id", "ID!", "String", false),
+                            field("name", "String!", "String", false),
+                        ]
+                    },
+                    {
+                        "name": "Part",
+                        "kind": "object",
+                        "id_type": "Bytes",
+                        "immutable": false,
+                        "timeseries": false,
+                        "interfaces": [],
+                        "fields": [
+                            field("id", "Bytes!", "Bytes", false),
+                            field("thing", "Thing!", "String", false),
+                        ]
+                    },
+                    {
+                        "name": "Thing",
+                        "kind": "object",
+                        "id_type": "String",
+                        "immutable": true,
+                        "timeseries": false,
+                        "interfaces": ["Named"],
+                        "fields": [
+                            field("id", "ID!", "String", false),
+                            field("name", "String!", "String", false),
+                            field("color", "Color", "String", false),
+                            field("parts", "[Part!]!", "Bytes", true),
+                        ]
+                    }
+                ],
+                "enums": { "Color": ["RED", "GREEN"] },
+                "fulltext": [],
+                "aggregations": [],
+            }),
+            schema.to_json()
+        );
+    }
 }
@@ -0,0 +1,843 @@
+//! Checks for things in a subgraph schema that graph-node accepts, but
+//! that are almost certainly mistakes.
+//!
+//! Since graph-node has always accepted such schemas, and deployed
+//! subgraphs may depend on that, `InputSchema::lint` only warns about
+//! them. Most of these warnings are errors with
+//! `ValidationOptions::strict`, so that new schemas can be kept free of
+//! them without rejecting existing deployments.
+
+use std::fmt;
+
+use graphql_parser::Pos;
+
+use crate::data::graphql::ext::{DirectiveExt, DirectiveFinder, TypeDefinitionExt};
+use crate::data::graphql::{DocumentExt, TypeExt};
+use crate::data::store::ValueType;
+use crate::prelude::s;
+
+use super::input_schema::kw;
+use super::{declaration_position, reserved_prefix, reserved_type_names, Schema, SCHEMA_TYPE_NAME};
+
+/// Something in a subgraph schema that graph-node can work with, but that
+/// is strongly discouraged since it is almost certainly a mistake
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaWarning {
+    /// An entity type that has no fields besides `id`
+    IdOnlyEntity(String),
+    /// An interface that no type implements and no field uses
+    UnusedInterface(String),
+    /// An interface that no type implements, but that some field uses
+    OrphanInterface(String),
+    /// A type, or a field of a type, whose name has a prefix that
+    /// graph-node reserves, and why it is reserved
+    ReservedPrefix(String, Option<String>, &'static str),
+    /// A field of an immutable entity type that stores a list of entities
+    /// that reference the immutable type; see `immutable_lists`
+    ImmutableList(ImmutableList),
+    /// A field other than `id` whose type is `ID`; see `id_references`
+    IdReference(IdReference),
+    /// A list of scalars or enums whose elements can be null; see
+    /// `nullable_scalar_lists`
+    NullableScalarList(NullableScalarList),
+}
+
+impl SchemaWarning {
+    /// A short code that identifies the lint that produced this warning,
+    /// in the same style as `SchemaValidationError::code`
+    pub fn code(&self) -> &'static str {
+        use SchemaWarning::*;
+
+        match self {
+            IdOnlyEntity(_) => "ID_ONLY_ENTITY",
+            UnusedInterface(_) => "UNUSED_INTERFACE",
+            OrphanInterface(_) => "ORPHAN_INTERFACE",
+            ReservedPrefix(..) => "RESERVED_PREFIX",
+            ImmutableList(_) => "IMMUTABLE_LIST",
+            IdReference(_) => "ID_REFERENCE",
+            NullableScalarList(_) => "NULLABLE_SCALAR_LIST",
+        }
+    }
+
+    /// The type, and possibly the field of that type, that this warning is
+    /// about
+    fn declaration(&self) -> (&str, Option<&str>) {
+        use SchemaWarning::*;
+
+        match self {
+            IdOnlyEntity(name) | UnusedInterface(name) | OrphanInterface(name) => {
+                (name.as_str(), None)
+            }
+            ReservedPrefix(name, field, _) => (name.as_str(), field.as_deref()),
+            ImmutableList(list) => (list.type_name.as_str(), Some(list.field.as_str())),
+            IdReference(id_ref) => (id_ref.type_name.as_str(), Some(id_ref.field.as_str())),
+            NullableScalarList(list) => (list.type_name.as_str(), Some(list.field.as_str())),
+        }
+    }
+
+    /// Find the position in `document` of the declaration that this
+    /// warning is about
+    pub fn position(&self, document: &s::Document) -> Option<Pos> {
+        let (type_name, field_name) = self.declaration();
+        declaration_position(document, type_name, field_name)
+    }
+}
+
+impl fmt::Display for SchemaWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SchemaWarning::*;
+
+        match self {
+            IdOnlyEntity(name) => write!(f, "entity type `{}` has no fields besides `id`", name),
+            UnusedInterface(name) => write!(
+                f,
+                "interface `{}` is not implemented by any type and not used by any field",
+                name
+            ),
+            OrphanInterface(name) => write!(
+                f,
+                "interface `{}` is not implemented by any type, so fields of that type can never reference anything",
+                name
+            ),
+            ReservedPrefix(name, None, reason) => {
+                write!(f, "type `{}` uses a reserved name: {}", name, reason)
+            }
+            ReservedPrefix(name, Some(field), reason) => write!(
+                f,
+                "field `{}` of `{}` uses a reserved name: {}",
+                field, name, reason
+            ),
+            ImmutableList(list) => write!(f, "{}", list),
+            IdReference(id_ref) => write!(f, "{}", id_ref),
+            NullableScalarList(list) => write!(f, "{}", list),
+        }
+    }
+}
+
+/// A field `type_name.field` of an immutable entity type that stores a list
+/// of `target` entities, where `target` references `type_name` through its
+/// field `back_ref`, like `holders: [Holder!]!` on an immutable `Token`
+/// when `Holder` has a field `token: Token!`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImmutableList {
+    pub type_name: String,
+    pub field: String,
+    pub target: String,
+    pub back_ref: String,
+}
+
+impl fmt::Display for ImmutableList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "field `{field}` of the immutable type `{ty}` stores a list of `{target}` entities, \
+             but `{target}` references `{ty}` through `{back_ref}`; since `{ty}` can not be \
+             updated, the list can not change when `{target}` entities are added. \
+             Use `@derivedFrom(field: \"{back_ref}\")` instead",
+            field = self.field,
+            ty = self.type_name,
+            target = self.target,
+            back_ref = self.back_ref
+        )
+    }
+}
+
+/// Find the fields of immutable entity types that store a list of
+/// references to entities that in turn reference the immutable type
+/// through a single, non-derived field. That usually means that the list
+/// is meant to collect those entities as they are added, which graph-node
+/// can not do since it never updates immutable entities, and that the
+/// field should be derived from the reference instead
+pub(in crate::schema) fn immutable_lists(document: &s::Document) -> Vec<ImmutableList> {
+    let flag = |obj_type: &s::ObjectType, name: &str| {
+        obj_type
+            .find_directive(kw::ENTITY)
+            .and_then(|dir| dir.argument(name))
+            .map_or(false, |value| value == &s::Value::Boolean(true))
+    };
+    let is_immutable =
+        |obj_type: &s::ObjectType| flag(obj_type, kw::IMMUTABLE) || flag(obj_type, kw::TIMESERIES);
+    let object_type = |name: &str| {
+        document.definitions.iter().find_map(|defn| match defn {
+            s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type))
+                if obj_type.name == name =>
+            {
+                Some(obj_type)
+            }
+            _ => None,
+        })
+    };
+
+    let mut lists = Vec::new();
+    for defn in &document.definitions {
+        let s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) = defn else {
+            continue;
+        };
+        if !is_immutable(obj_type) {
+            continue;
+        }
+        for field in &obj_type.fields {
+            if !field.field_type.is_list() || field.is_derived() {
+                continue;
+            }
+            let Some(target) = object_type(field.field_type.get_base_type()) else {
+                continue;
+            };
+            let back_ref = target.fields.iter().find(|target_field| {
+                !target_field.field_type.is_list()
+                    && !target_field.is_derived()
+                    && target_field.field_type.get_base_type() == obj_type.name
+            });
+            if let Some(back_ref) = back_ref {
+                lists.push(ImmutableList {
+                    type_name: obj_type.name.clone(),
+                    field: field.name.clone(),
+                    target: target.name.clone(),
+                    back_ref: back_ref.name.clone(),
+                });
+            }
+        }
+    }
+    lists
+}
+
+/// A field `type_name.field` of an entity type, other than `id`, whose
+/// type is `ID` or a list of `ID`, like `owner: ID!`. If the name of the
+/// field suggests which entity type it references, that is `target`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdReference {
+    pub type_name: String,
+    pub field: String,
+    pub target: Option<String>,
+}
+
+impl fmt::Display for IdReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "field `{}` of `{}` has type `ID`, which does not reference any entity; ",
+            self.field, self.type_name
+        )?;
+        match &self.target {
+            Some(target) => write!(f, "did you mean to reference `{}`?", target),
+            None => write!(
+                f,
+                "use the referenced entity type instead, or `String` if the field \
+                 is not a reference"
+            ),
+        }
+    }
+}
+
+/// Find the fields of entity types, other than `id`, that have type `ID`.
+/// Such a field is usually meant to reference another entity, but since
+/// `ID` is just a string, graph-node does not treat it as a reference and
+/// the relationship can not be queried
+pub(in crate::schema) fn id_references(document: &s::Document) -> Vec<IdReference> {
+    let entity_types: Vec<&str> = document
+        .definitions
+        .iter()
+        .filter_map(|defn| match defn {
+            s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type))
+                if obj_type.find_directive(kw::ENTITY).is_some() =>
+            {
+                Some(obj_type.name.as_str())
+            }
+            s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                Some(intf_type.name.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    // The entity type that a field named like `owner`, `ownerId`, or
+    // `owners` most likely references
+    let target = |field: &str| {
+        let name = field
+            .strip_suffix("Ids")
+            .or_else(|| field.strip_suffix("Id"))
+            .or_else(|| field.strip_suffix("ID"))
+            .unwrap_or(field);
+        let singular = name.strip_suffix('s').unwrap_or(name);
+        entity_types
+            .iter()
+            .find(|ty| ty.eq_ignore_ascii_case(name) || ty.eq_ignore_ascii_case(singular))
+            .map(|ty| ty.to_string())
+    };
+
+    let mut id_refs = Vec::new();
+    for defn in &document.definitions {
+        let s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type)) = defn else {
+            continue;
+        };
+        if obj_type.find_directive(kw::ENTITY).is_none() {
+            continue;
+        }
+        for field in &obj_type.fields {
+            if field.name != "id" && field.field_type.get_base_type() == "ID" {
+                id_refs.push(IdReference {
+                    type_name: obj_type.name.clone(),
+                    field: field.name.clone(),
+                    target: target(&field.name),
+                });
+            }
+        }
+    }
+    id_refs
+}
+
+/// A field `type_name.field` of an entity type or interface whose type
+/// `field_type` is a list of the scalar or enum `base_type` with elements
+/// that can be null, like `tags: [String]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NullableScalarList {
+    pub type_name: String,
+    pub field: String,
+    pub base_type: String,
+    pub field_type: String,
+}
+
+impl fmt::Display for NullableScalarList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "field `{field}` of `{ty}` has type `{field_type}`, whose elements can be null; \
+             the elements of lists of scalars and enums should not be null. Use \
+             `[{base}!]!`, or `[{base}!]` if the list itself can be null",
+            field = self.field,
+            ty = self.type_name,
+            field_type = self.field_type,
+            base = self.base_type
+        )
+    }
+}
+
+/// Find the fields of entity types and interfaces that are lists of
+/// scalars or enums whose elements can be null. graph-node supports the
+/// shapes `[T!]!` and `[T!]` for such lists; it stores lists with null
+/// elements, too, but such elements are almost never intended, and neither
+/// mappings nor queries can tell them apart from a missing value.
+/// Lists of references have their own rules, and nested lists are always
+/// rejected, which is why neither is reported here
+pub(in crate::schema) fn nullable_scalar_lists(document: &s::Document) -> Vec<NullableScalarList> {
+    let is_scalar_or_enum = |name: &str| {
+        ValueType::is_scalar(name)
+            || matches!(
+                document.get_named_type(name),
+                Some(s::TypeDefinition::Enum(_))
+            )
+    };
+
+    let mut lists = Vec::new();
+    for defn in &document.definitions {
+        let (type_name, fields) = match defn {
+            s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type))
+                if obj_type.find_directive(kw::ENTITY).is_some() =>
+            {
+                (&obj_type.name, &obj_type.fields)
+            }
+            s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type)) => {
+                (&intf_type.name, &intf_type.fields)
+            }
+            _ => continue,
+        };
+        for field in fields {
+            let list = match &field.field_type {
+                s::Type::NonNullType(inner) => inner.as_ref(),
+                typ => typ,
+            };
+            let s::Type::ListType(elem) = list else {
+                continue;
+            };
+            let s::Type::NamedType(base_type) = elem.as_ref() else {
+                continue;
+            };
+            if !field.is_derived() && is_scalar_or_enum(base_type) {
+                lists.push(NullableScalarList {
+                    type_name: type_name.clone(),
+                    field: field.name.clone(),
+                    base_type: base_type.clone(),
+                    field_type: field.field_type.to_string(),
+                });
+            }
+        }
+    }
+    lists
+}
+
+/// A directive, or an argument of a directive, that graph-node still
+/// accepts but that will stop working in a future spec version
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deprecation {
+    /// The type that uses the deprecated directive or argument
+    pub type_name: String,
+    /// The field that uses it, if it is used on a field
+    pub field_name: Option<String>,
+    pub directive: String,
+    /// The deprecated argument, or `None` if the whole directive is
+    /// deprecated
+    pub argument: Option<String>,
+    /// What to do instead
+    pub note: &'static str,
+}
+
+impl Deprecation {
+    /// A short code in the same style as `SchemaWarning::code`
+    pub fn code(&self) -> &'static str {
+        match self.argument {
+            Some(_) => "DEPRECATED_ARGUMENT",
+            None => "DEPRECATED_DIRECTIVE",
+        }
+    }
+
+    /// Find the position in `document` of the declaration that uses the
+    /// deprecated directive or argument
+    pub fn position(&self, document: &s::Document) -> Option<Pos> {
+        declaration_position(document, &self.type_name, self.field_name.as_deref())
+    }
+}
+
+impl fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let target = match &self.field_name {
+            Some(field_name) => format!("field `{}` of `{}`", field_name, self.type_name),
+            None => format!("type `{}`", self.type_name),
+        };
+        match &self.argument {
+            Some(argument) => write!(
+                f,
+                "{} uses the argument `{}` of `@{}`, which is deprecated: {}",
+                target, argument, self.directive, self.note
+            ),
+            None => write!(
+                f,
+                "{} uses the directive `@{}`, which is deprecated: {}",
+                target, self.directive, self.note
+            ),
+        }
+    }
+}
+
+/// An entry in the list of deprecated directives and arguments
+// Only tests construct this while `DEPRECATED` is empty
+#[allow(dead_code)]
+pub(in crate::schema) struct Deprecated {
+    directive: &'static str,
+    /// If this is `None`, the whole directive is deprecated
+    argument: Option<&'static str>,
+    note: &'static str,
+}
+
+/// The directives and arguments that will be removed in a future spec
+/// version. Nothing is deprecated right now; when something is slated for
+/// removal, it should be added here with a note that explains what to use
+/// instead
+pub(in crate::schema) const DEPRECATED: &[Deprecated] = &[];
+
+/// Find all uses of the directives and arguments in `deprecated` on the
+/// object types and interfaces of `schema` and their fields
+pub(in crate::schema) fn deprecations(
+    schema: &Schema,
+    deprecated: &[Deprecated],
+) -> Vec<Deprecation> {
+    let find = |type_name: &str, field_name: Option<&str>, directives: &[s::Directive]| {
+        let mut found = Vec::new();
+        for dir in directives {
+            for depr in deprecated.iter().filter(|depr| dir.name == depr.directive) {
+                let used = match depr.argument {
+                    Some(arg) => dir.arguments.iter().any(|(name, _)| name == arg),
+                    None => true,
+                };
+                if used {
+                    found.push(Deprecation {
+                        type_name: type_name.to_string(),
+                        field_name: field_name.map(str::to_string),
+                        directive: dir.name.clone(),
+                        argument: depr.argument.map(str::to_string),
+                        note: depr.note,
+                    });
+                }
+            }
+        }
+        found
+    };
+
+    let mut found = Vec::new();
+    for defn in &schema.document.definitions {
+        let (type_name, directives, fields) = match defn {
+            s::Definition::TypeDefinition(s::TypeDefinition::Object(t)) => {
+                (&t.name, &t.directives, &t.fields)
+            }
+            s::Definition::TypeDefinition(s::TypeDefinition::Interface(t)) => {
+                (&t.name, &t.directives, &t.fields)
+            }
+            _ => continue,
+        };
+        found.extend(find(type_name, None, directives));
+        for field in fields {
+            found.extend(find(type_name, Some(&field.name), &field.directives));
+        }
+    }
+    found
+}
+
+/// A lint checks a schema that passed validation and reports what it finds
+/// questionable
+type Lint = fn(&Schema) -> Vec<SchemaWarning>;
+
+/// All the lints that `lint` runs. New lints only need to be added here
+const LINTS: &[Lint] = &[
+    id_only_entities,
+    unused_interfaces,
+    reserved_prefixes,
+    immutable_list_fields,
+    id_reference_fields,
+    nullable_scalar_list_fields,
+];
+
+/// Run all lints against `schema`. The warnings are sorted by lint, and
+/// for each lint in the order in which types are declared
+pub(in crate::schema) fn lint(schema: &Schema) -> Vec<SchemaWarning> {
+    LINTS.iter().flat_map(|lint| lint(schema)).collect()
+}
+
+fn object_types(schema: &Schema) -> impl Iterator<Item = &s::ObjectType> {
+    schema
+        .document
+        .definitions
+        .iter()
+        .filter_map(|defn| match defn {
+            s::Definition::TypeDefinition(s::TypeDefinition::Object(obj_type))
+                if obj_type.name != SCHEMA_TYPE_NAME =>
+            {
+                Some(obj_type)
+            }
+            _ => None,
+        })
+}
+
+fn id_only_entities(schema: &Schema) -> Vec<SchemaWarning> {
+    object_types(schema)
+        .filter(|obj_type| obj_type.find_directive(kw::ENTITY).is_some())
+        .filter(|obj_type| obj_type.fields.iter().all(|field| field.name == "id"))
+        .map(|obj_type| SchemaWarning::IdOnlyEntity(obj_type.name.clone()))
+        .collect()
+}
+
+/// Warn about interfaces that no type implements. If no field uses them
+/// either, they are `UnusedInterface`, otherwise `OrphanInterface`
+fn unused_interfaces(schema: &Schema) -> Vec<SchemaWarning> {
+    let implemented = |name: &str| {
+        schema
+            .types_for_interface
+            .get(name)
+            .map_or(false, |impls| !impls.is_empty())
+    };
+    let referenced = |name: &str| {
+        schema
+            .document
+            .definitions
+            .iter()
+            .filter_map(|defn| match defn {
+                s::Definition::TypeDefinition(s::TypeDefinition::Object(t)) => Some(&t.fields),
+                s::Definition::TypeDefinition(s::TypeDefinition::Interface(t)) => Some(&t.fields),
+                _ => None,
+            })
+            .flatten()
+            .any(|field| field.field_type.get_base_type() == name)
+    };
+
+    schema
+        .document
+        .definitions
+        .iter()
+        .filter_map(|defn| match defn {
+            s::Definition::TypeDefinition(s::TypeDefinition::Interface(intf_type))
+                if !implemented(&intf_type.name) =>
+            {
+                let name = intf_type.name.clone();
+                if referenced(&intf_type.name) {
+                    Some(SchemaWarning::OrphanInterface(name))
+                } else {
+                    Some(SchemaWarning::UnusedInterface(name))
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Warn about type and field names that use a prefix that graph-node
+/// reserves
+fn reserved_prefixes(schema: &Schema) -> Vec<SchemaWarning> {
+    let mut warnings = Vec::new();
+    for defn in &schema.document.definitions {
+        let s::Definition::TypeDefinition(typedef) = defn else {
+            continue;
+        };
+        let type_name = typedef.name();
+        if !reserved_type_names().contains(&type_name) {
+            if let Some(reason) = reserved_prefix(type_name, true) {
+                warnings.push(SchemaWarning::ReservedPrefix(
+                    type_name.to_string(),
+                    None,
+                    reason,
+                ));
+            }
+        }
+        let fields = match typedef {
+            s::TypeDefinition::Object(t) => &t.fields,
+            s::TypeDefinition::Interface(t) => &t.fields,
+            _ => continue,
+        };
+        for field in fields {
+            if let Some(reason) = reserved_prefix(&field.name, false) {
+                warnings.push(SchemaWarning::ReservedPrefix(
+                    type_name.to_string(),
+                    Some(field.name.clone()),
+                    reason,
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+/// Warn about the lists on immutable entity types that `immutable_lists`
+/// finds
+fn immutable_list_fields(schema: &Schema) -> Vec<SchemaWarning> {
+    immutable_lists(&schema.document)
+        .into_iter()
+        .map(SchemaWarning::ImmutableList)
+        .collect()
+}
+
+/// Warn about the fields of type `ID` that `id_references` finds
+fn id_reference_fields(schema: &Schema) -> Vec<SchemaWarning> {
+    id_references(&schema.document)
+        .into_iter()
+        .map(SchemaWarning::IdReference)
+        .collect()
+}
+
+/// Warn about the lists of scalars and enums that
+/// `nullable_scalar_lists` finds
+fn nullable_scalar_list_fields(schema: &Schema) -> Vec<SchemaWarning> {
+    nullable_scalar_lists(&schema.document)
+        .into_iter()
+        .map(SchemaWarning::NullableScalarList)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::InputSchema;
+
+    use super::{
+        deprecations, Deprecated, Deprecation, IdReference, ImmutableList, NullableScalarList,
+        SchemaWarning,
+    };
+
+    #[test]
+    fn lints() {
+        const SCHEMA: &str = r#"
+        interface Unused { id: ID! }
+        interface Named { name: String! }
+        interface Referenced { id: ID! }
+
+        type Empty @entity { id: ID! }
+        type Thing implements Named @entity {
+            id: ID!
+            name: String!
+            other: Referenced
+        }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        assert_eq!(
+            vec![
+                SchemaWarning::IdOnlyEntity("Empty".to_string()),
+                SchemaWarning::UnusedInterface("Unused".to_string()),
+                SchemaWarning::OrphanInterface("Referenced".to_string()),
+            ],
+            schema.lint()
+        );
+
+        let schema = InputSchema::raw("type Thing @entity { id: ID!, name: String }", "hash");
+        assert!(schema.lint().is_empty());
+    }
+
+    #[test]
+    fn immutable_lists() {
+        const SCHEMA: &str = r#"
+        type Token @entity(immutable: true) {
+            id: ID!
+            holders: [Holder!]!
+            transfers: [Transfer!]! @derivedFrom(field: "token")
+        }
+        type Holder @entity { id: ID!, token: Token! }
+        type Transfer @entity(immutable: true) { id: ID!, token: Token! }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        assert_eq!(
+            vec![SchemaWarning::ImmutableList(ImmutableList {
+                type_name: "Token".to_string(),
+                field: "holders".to_string(),
+                target: "Holder".to_string(),
+                back_ref: "token".to_string(),
+            })],
+            schema.lint()
+        );
+    }
+
+    #[test]
+    fn id_references() {
+        const SCHEMA: &str = r#"
+        type User @entity { id: ID!, name: String! }
+        type Thing @entity {
+            id: ID!
+            owner: ID!
+            userIds: [ID!]!
+            user: User
+        }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        assert_eq!(
+            vec![
+                SchemaWarning::IdReference(IdReference {
+                    type_name: "Thing".to_string(),
+                    field: "owner".to_string(),
+                    target: None,
+                }),
+                SchemaWarning::IdReference(IdReference {
+                    type_name: "Thing".to_string(),
+                    field: "userIds".to_string(),
+                    target: Some("User".to_string()),
+                }),
+            ],
+            schema.lint()
+        );
+        assert_eq!(
+            "field `userIds` of `Thing` has type `ID`, which does not reference any entity; \
+             did you mean to reference `User`?",
+            schema.lint()[1].to_string()
+        );
+
+        // The `id` field is supposed to have type `ID`
+        let schema = InputSchema::raw("type Thing @entity { id: ID!, name: String! }", "hash");
+        assert!(schema.lint().is_empty());
+    }
+
+    #[test]
+    fn nullable_scalar_lists() {
+        const SCHEMA: &str = r#"
+        enum Color { RED, GREEN }
+        type Other @entity { id: ID!, thing: Thing! }
+        type Thing @entity {
+            id: ID!
+            tags: [String]
+            colors: [Color]!
+            sizes: [Int!]
+            scores: [BigInt!]!
+            others: [Other!]! @derivedFrom(field: "thing")
+        }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let list = |field: &str, base_type: &str, field_type: &str| {
+            SchemaWarning::NullableScalarList(NullableScalarList {
+                type_name: "Thing".to_string(),
+                field: field.to_string(),
+                base_type: base_type.to_string(),
+                field_type: field_type.to_string(),
+            })
+        };
+        assert_eq!(
+            vec![
+                list("tags", "String", "[String]"),
+                list("colors", "Color", "[Color]!"),
+            ],
+            schema.lint()
+        );
+        assert_eq!(
+            "field `tags` of `Thing` has type `[String]`, whose elements can be null; \
+             the elements of lists of scalars and enums should not be null. Use \
+             `[String!]!`, or `[String!]` if the list itself can be null",
+            schema.lint()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn reserved_prefixes() {
+        const SCHEMA: &str = r#"
+        type _Internal_ @entity { id: ID!, __secret: String, _private: String }
+        type _private @entity { id: ID! }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let warnings: Vec<_> = schema
+            .lint()
+            .into_iter()
+            .filter(|warning| warning.code() == "RESERVED_PREFIX")
+            .map(|warning| warning.to_string())
+            .collect();
+        assert_eq!(2, warnings.len());
+        assert!(warnings[0].starts_with("type `_Internal_` uses a reserved name"));
+        assert!(warnings[1].starts_with("field `__secret` of `_Internal_` uses a reserved name"));
+
+        // The prefixes cover the names that graph-node reserves today
+        for name in crate::schema::reserved_type_names() {
+            if name.starts_with('_') {
+                assert!(crate::schema::reserved_prefix(name, true).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn deprecated_directives() {
+        const DEPRECATED: &[Deprecated] = &[
+            Deprecated {
+                directive: "entity",
+                argument: Some("immutable"),
+                note: "use `@entity(timeseries: true)`",
+            },
+            Deprecated {
+                directive: "deprecated",
+                argument: None,
+                note: "remove it",
+            },
+        ];
+        const SCHEMA: &str = r#"
+        type Thing @entity(immutable: true) {
+            id: ID!
+            name: String @deprecated(reason: "unused")
+        }
+        type Other @entity { id: ID! }"#;
+
+        let schema = InputSchema::raw(SCHEMA, "hash");
+        let found = deprecations(schema.schema(), DEPRECATED);
+        assert_eq!(
+            vec![
+                Deprecation {
+                    type_name: "Thing".to_string(),
+                    field_name: None,
+                    directive: "entity".to_string(),
+                    argument: Some("immutable".to_string()),
+                    note: "use `@entity(timeseries: true)`",
+                },
+                Deprecation {
+                    type_name: "Thing".to_string(),
+                    field_name: Some("name".to_string()),
+                    directive: "deprecated".to_string(),
+                    argument: None,
+                    note: "remove it",
+                },
+            ],
+            found
+        );
+        assert_eq!(
+            "type `Thing` uses the argument `immutable` of `@entity`, which is deprecated: \
+             use `@entity(timeseries: true)`",
+            found[0].to_string()
+        );
+
+        // Nothing is deprecated right now
+        assert!(schema.deprecations().is_empty());
+    }
+}
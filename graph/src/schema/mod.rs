@@ -18,21 +18,28 @@ mod api;
 /// Utilities for working with GraphQL schema ASTs.
 pub mod ast;
 
+mod builder;
+mod diff;
 mod entity_key;
 mod entity_type;
 mod fulltext;
 mod input_schema;
+mod lint;
 
 pub use api::{is_introspection_field, APISchemaError, INTROSPECTION_QUERY_TYPE};
 
-pub use api::{ApiSchema, ErrorPolicy};
+pub use api::{ApiSchema, ErrorPolicy, QueryFieldCounts, QueryFilters, QueryValidationError};
+pub use builder::{FieldBuilder, FieldType, SchemaBuilder, TypeBuilder};
+pub use diff::{GraftIncompatibility, SchemaChange, SchemaDiff};
 pub use entity_key::EntityKey;
 pub use entity_type::{AsEntityTypeName, EntityType};
 pub use fulltext::{FulltextAlgorithm, FulltextConfig, FulltextDefinition, FulltextLanguage};
 pub use input_schema::{
     Aggregate, AggregateFn, Aggregation, AggregationInterval, AggregationMapping, Field,
-    InputSchema, InterfaceType, ObjectType, TypeKind,
+    FieldIndex, IndexMethod, InputSchema, InterfaceType, MissingInverse, ObjectType, RefKind,
+    TypeKind, ValidationOptions,
 };
+pub use lint::{Deprecation, IdReference, ImmutableList, NullableScalarList, SchemaWarning};
 
 pub const SCHEMA_TYPE_NAME: &str = "_Schema_";
 pub const INTROSPECTION_SCHEMA_FIELD_NAME: &str = "__schema";
@@ -44,6 +51,81 @@ pub const INTROSPECTION_TYPE_FIELD_NAME: &str = "__type";
 
 pub const BLOCK_FIELD_TYPE: &str = "_Block_";
 
+/// The names of the types that graph-node defines itself, either as
+/// built-in scalars or when it generates the API schema. A subgraph schema
+/// must not declare types with any of these names. The names of the
+/// `<Type>_filter` and `<Type>_orderBy` types that are generated for each
+/// entity type are also reserved, but depend on the schema
+pub fn reserved_type_names() -> &'static [&'static str] {
+    &[
+        // Built-in scalars
+        "Boolean",
+        "ID",
+        "Int",
+        "Float",
+        "String",
+        "BigDecimal",
+        "Bytes",
+        "BigInt",
+        "Int8",
+        // Root types
+        "Query",
+        "Subscription",
+        // Types from meta.graphql
+        META_FIELD_TYPE,
+        BLOCK_FIELD_TYPE,
+        "Block_height",
+        "BlockChangedFilter",
+        "OrderDirection",
+        "_SubgraphErrorPolicy_",
+    ]
+}
+
+/// Explain why `name` is reserved if it follows one of the patterns that
+/// graph-node reserves for the names of types and fields that it defines
+/// itself. GraphQL reserves names starting with `__` for introspection.
+/// The names in `reserved_type_names` that start with `_` also end with
+/// `_`, and types of that form, except for `_Schema_`, could clash with
+/// types that graph-node adds in the future; that pattern is only
+/// reserved for type names
+pub fn reserved_prefix(name: &str, is_type: bool) -> Option<&'static str> {
+    if name.starts_with("__") {
+        Some("names starting with `__` are reserved for GraphQL introspection")
+    } else if is_type
+        && name != SCHEMA_TYPE_NAME
+        && name.len() > 2
+        && name.starts_with('_')
+        && name.ends_with('_')
+    {
+        Some("names that start and end with `_`, like `_Meta_`, are reserved for types that graph-node generates")
+    } else {
+        None
+    }
+}
+
+/// The names of the fields that graph-node adds to the `<Type>_filter`
+/// types it generates for the API schema. Entity fields with these names
+/// lead to clashes in those types
+pub fn reserved_field_names() -> &'static [&'static str] {
+    &["_change_block", "and", "or"]
+}
+
+/// The names of the arguments that graph-node adds to the query fields it
+/// generates for the API schema, both on the `Query` type and on fields of
+/// entity types that reference a list of entities
+pub fn reserved_argument_names() -> &'static [&'static str] {
+    &[
+        "skip",
+        "first",
+        "orderBy",
+        "orderDirection",
+        "where",
+        "block",
+        "subgraphError",
+        "text",
+    ]
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Strings(Vec<String>);
 
@@ -54,8 +136,39 @@ impl fmt::Display for Strings {
     }
 }
 
-#[derive(Debug, Error, PartialEq, Eq)]
+impl From<std::str::Utf8Error> for SchemaValidationError {
+    fn from(e: std::str::Utf8Error) -> Self {
+        SchemaValidationError::Encoding(e.to_string())
+    }
+}
+
+/// A list of validation errors, displayed as a numbered list
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationErrors(pub Vec<SchemaValidationError>);
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for (n, e) in self.0.iter().enumerate() {
+            if n > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "  ({}) - {}", n + 1, e)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
 pub enum SchemaValidationError {
+    #[error("Failed to parse schema: {0}")]
+    SchemaParseError(String),
+    #[error("Schema is not valid UTF-8: {0}")]
+    Encoding(String),
+    #[error("Validation errors in subgraph `{0}`:\n{1}")]
+    SchemaInvalid(String, ValidationErrors), // (subgraph, errors)
+    #[error("Failed to generate the API schema: {0}")]
+    ApiSchemaInvalid(String),
+
     #[error("Interface `{0}` not defined")]
     InterfaceUndefined(String),
 
@@ -69,24 +182,81 @@ pub enum SchemaValidationError {
          the following fields: {2}"
     )]
     InterfaceFieldsMissing(String, String, Strings), // (type, interface, missing_fields)
+    #[error(
+        "Entity type `{0}` does not satisfy interface `{1}` because its field `{2}` has \
+         type `{4}` but the interface declares it as `{3}`"
+    )]
+    InterfaceFieldTypeMismatch(String, String, String, String, String), // (type, interface, field, expected, actual)
     #[error("Implementors of interface `{0}` use different id types `{1}`. They must all use the same type")]
     InterfaceImplementorsMixId(String, String),
     #[error("Field `{1}` in type `{0}` has invalid @derivedFrom: {2}")]
     InvalidDerivedFrom(String, String, String), // (type, field, reason)
     #[error("The following type names are reserved: `{0}`")]
     UsageOfReservedTypes(Strings),
+    #[error("This looks like a generated API schema, not a subgraph schema, since it declares the types `{0}`; validate the schema from the subgraph's `schema.graphql` instead")]
+    GeneratedApiSchema(Strings),
     #[error("_Schema_ type is only for @fulltext and must not have any fields")]
     SchemaTypeWithFields,
     #[error("The _Schema_ type only allows @fulltext directives")]
     InvalidSchemaTypeDirectives,
     #[error("Type `{0}`, field `{1}`: type `{2}` is not defined")]
     FieldTypeUnknown(String, String, String), // (type_name, field_name, field_type)
+    #[error("Type `{0}`, field `{1}`: {2}")]
+    InvalidListType(String, String, String), // (type_name, field_name, reason)
+    #[error("Type `{0}`, field `{1}`: the elements of a list of references can not be null; use `[{2}!]` or `[{2}!]!` instead of `{3}`")]
+    NullableReferenceListElements(String, String, String, String), // (type_name, field_name, base_type, field_type)
+    #[error("Field `{1}` in type `{0}` uses a reserved name: {2}")]
+    ReservedFieldName(String, String, String), // (type_name, field_name, reason)
+    #[error("Type `{0}` uses a reserved name: {1}")]
+    ReservedTypeName(String, String), // (type_name, reason)
+    #[error("Type `{0}` declares the field `{1}` more than once")]
+    DuplicateField(String, String), // (type_name, field_name)
+    #[error("The name `{0}` is used for more than one type: {1}")]
+    DuplicateTypeName(String, String), // (type_name, kinds)
+    #[error("`{0}` is not a valid GraphQL name for a type")]
+    InvalidTypeName(String),
+    #[error("Type `{0}` has a field `{1}` whose name is not a valid GraphQL name")]
+    InvalidFieldName(String, String), // (type_name, field_name)
+    #[error("Enum `{0}` has the value `{1}` which is not a valid GraphQL name")]
+    InvalidEnumValue(String, String), // (enum_name, value)
+    #[error("Enum `{0}` declares the value `{1}` more than once")]
+    DuplicateEnumValue(String, String), // (enum_name, value)
+    #[error("Entity type `{0}` is part of a cycle of non-nullable references: {1}")]
+    MandatoryReferenceCycle(String, String), // (type_name, cycle)
+    #[error("Interface `{0}` is not implemented by any type")]
+    OrphanInterface(String),
+    #[error("{0}")]
+    ImmutableList(ImmutableList),
+    #[error("{0}")]
+    IdReference(IdReference),
+    #[error("{0}")]
+    NullableScalarList(NullableScalarList),
+    #[error("Type `{0}` uses `@{1}` ambiguously: {2}")]
+    ConflictingTypeDirective(String, String, String), // (type_name, directive, reason)
+    #[error("Field `{1}` in type `{0}` uses `@{2}` ambiguously: {3}")]
+    ConflictingFieldDirective(String, String, String, String), // (type_name, field_name, directive, reason)
+    #[error("Type `{0}` uses the unknown directive `@{1}`")]
+    UnknownTypeDirective(String, String), // (type_name, directive)
+    #[error("Field `{1}` in type `{0}` uses the unknown directive `@{2}`")]
+    UnknownFieldDirective(String, String, String), // (type_name, field_name, directive)
+    #[error("Type `{0}` passes the argument `{2}` to `@{1}`, but `@{1}` has no such argument")]
+    UnknownTypeDirectiveArgument(String, String, String), // (type_name, directive, argument)
+    #[error(
+        "Field `{1}` in type `{0}` passes the argument `{3}` to `@{2}`, but `@{2}` has no such argument"
+    )]
+    UnknownFieldDirectiveArgument(String, String, String, String), // (type_name, field_name, directive, argument)
+    #[error("Type `{0}` can not be renamed since it is not defined")]
+    RenameTypeUndefined(String),
+    #[error("Type `{0}` can not be renamed to `{1}` since a type with that name already exists")]
+    RenameTypeConflict(String, String), // (from, to)
     #[error("Imported type `{0}` does not exist in the `{1}` schema")]
     ImportedTypeUndefined(String, String), // (type_name, schema)
+    #[error("Fulltext search is not yet deterministic and must be explicitly allowed")]
+    FulltextNotAllowed,
     #[error("Fulltext directive name undefined")]
     FulltextNameUndefined,
-    #[error("Fulltext directive name overlaps with type: {0}")]
-    FulltextNameConflict(String),
+    #[error("Fulltext directive name `{0}` is declared more than once: {1}")]
+    FulltextNameConflict(String, String), // (name, declarations)
     #[error("Fulltext directive name overlaps with an existing entity field or a top-level query field: {0}")]
     FulltextNameCollision(String),
     #[error("Fulltext language is undefined")]
@@ -113,6 +283,11 @@ pub enum SchemaValidationError {
     FulltextIncludedFieldMissingRequiredProperty,
     #[error("Fulltext entity field, {0}, not found or not a string")]
     FulltextIncludedFieldInvalid(String),
+    #[error(
+        "Fulltext search includes the field `{1}` of `{0}`, which has type `{2}`, \
+         but only fields of type String can be included"
+    )]
+    FulltextIncludedFieldNotString(String, String, String), // (entity, field, type)
     #[error("Type {0} is missing an `id` field")]
     IdFieldMissing(String),
     #[error("{0}")]
@@ -141,6 +316,8 @@ pub enum SchemaValidationError {
         "Aggregation {0} has a derived field {1} but fields in aggregations can not be derived"
     )]
     AggregationDerivedField(String, String),
+    #[error("Timeseries {0} can not be used with spec version {1}; please migrate the subgraph to the latest version")]
+    TimeseriesNotSupported(String, Version),
     #[error("Timeseries {0} is marked as mutable, it must be immutable")]
     MutableTimeseries(String),
     #[error("Timeseries {0} is missing a `timestamp` field")]
@@ -177,6 +354,291 @@ pub enum SchemaValidationError {
     AggregationsNotSupported(Version),
     #[error("Using Int8 as the type for the `id` field is not supported with spec version {0}; please migrate the subgraph to the latest version")]
     IdTypeInt8NotSupported(Version),
+    #[error(
+        "Field `{1}` in type `{0}` has type `{2}`, which substreams entity changes can not set"
+    )]
+    SubstreamsUnsupportedFieldType(String, String, String), // (type_name, field_name, field_type)
+    #[error("Mappings with API version {0} can not write entities that use {1}")]
+    ApiVersionTooOld(Version, Strings), // (api_version, features and the API version they need)
+}
+
+impl SchemaValidationError {
+    /// A short code that identifies the rule that this error violates.
+    /// Unlike the error message, the code does not change between
+    /// releases and is meant for aggregating errors by their kind
+    pub fn code(&self) -> &'static str {
+        use SchemaValidationError::*;
+
+        match self {
+            SchemaParseError(..) => "SCHEMA_PARSE_ERROR",
+            Encoding(..) => "ENCODING",
+            SchemaInvalid(..) => "SCHEMA_INVALID",
+            ApiSchemaInvalid(..) => "API_SCHEMA_INVALID",
+            InterfaceUndefined(..) => "INTERFACE_UNDEFINED",
+            EntityDirectivesMissing(..) => "ENTITY_DIRECTIVES_MISSING",
+            EntityDirectiveNonBooleanArgValue(..) => "ENTITY_DIRECTIVE_NON_BOOLEAN_ARG_VALUE",
+            InterfaceFieldsMissing(..) => "INTERFACE_FIELDS_MISSING",
+            InterfaceFieldTypeMismatch(..) => "INTERFACE_FIELD_TYPE_MISMATCH",
+            InterfaceImplementorsMixId(..) => "INTERFACE_IMPLEMENTORS_MIX_ID",
+            InvalidDerivedFrom(..) => "BAD_DERIVED_FROM",
+            UsageOfReservedTypes(..) => "RESERVED_NAME",
+            GeneratedApiSchema(..) => "GENERATED_API_SCHEMA",
+            SchemaTypeWithFields => "SCHEMA_TYPE_WITH_FIELDS",
+            InvalidSchemaTypeDirectives => "INVALID_SCHEMA_TYPE_DIRECTIVES",
+            FieldTypeUnknown(..) => "FIELD_TYPE_UNKNOWN",
+            InvalidListType(..) | NullableReferenceListElements(..) => "INVALID_LIST_TYPE",
+            ReservedFieldName(..) => "RESERVED_FIELD_NAME",
+            ReservedTypeName(..) => "RESERVED_TYPE_NAME",
+            DuplicateField(..) => "DUPLICATE_FIELD",
+            DuplicateTypeName(..) => "DUPLICATE_TYPE_NAME",
+            InvalidTypeName(..) => "INVALID_TYPE_NAME",
+            InvalidFieldName(..) => "INVALID_FIELD_NAME",
+            InvalidEnumValue(..) => "INVALID_ENUM_VALUE",
+            DuplicateEnumValue(..) => "DUPLICATE_ENUM_VALUE",
+            MandatoryReferenceCycle(..) => "MANDATORY_REFERENCE_CYCLE",
+            OrphanInterface(..) => "ORPHAN_INTERFACE",
+            ImmutableList(..) => "IMMUTABLE_LIST",
+            IdReference(..) => "ID_REFERENCE",
+            NullableScalarList(..) => "NULLABLE_SCALAR_LIST",
+            RenameTypeUndefined(..) => "RENAME_TYPE_UNDEFINED",
+            RenameTypeConflict(..) => "RENAME_TYPE_CONFLICT",
+            UnknownTypeDirective(..) => "UNKNOWN_TYPE_DIRECTIVE",
+            UnknownFieldDirective(..) => "UNKNOWN_FIELD_DIRECTIVE",
+            ConflictingTypeDirective(..) | ConflictingFieldDirective(..) => "CONFLICTING_DIRECTIVE",
+            UnknownTypeDirectiveArgument(..) | UnknownFieldDirectiveArgument(..) => {
+                "UNKNOWN_DIRECTIVE_ARGUMENT"
+            }
+            ImportedTypeUndefined(..) => "IMPORTED_TYPE_UNDEFINED",
+            FulltextNotAllowed => "FULLTEXT_NOT_ALLOWED",
+            FulltextNameUndefined => "FULLTEXT_NAME_UNDEFINED",
+            FulltextNameConflict(..) => "FULLTEXT_NAME_CONFLICT",
+            FulltextNameCollision(..) => "FULLTEXT_NAME_COLLISION",
+            FulltextLanguageUndefined => "FULLTEXT_LANGUAGE_UNDEFINED",
+            FulltextLanguageInvalid(..) => "FULLTEXT_LANGUAGE_INVALID",
+            FulltextAlgorithmUndefined => "FULLTEXT_ALGORITHM_UNDEFINED",
+            FulltextAlgorithmInvalid(..) => "FULLTEXT_ALGORITHM_INVALID",
+            FulltextIncludeInvalid => "FULLTEXT_INCLUDE_INVALID",
+            FulltextIncludeUndefined => "FULLTEXT_INCLUDE_UNDEFINED",
+            FulltextIncludeObjectMissing => "FULLTEXT_INCLUDE_OBJECT_MISSING",
+            FulltextIncludeEntityMissingOrIncorrectAttributes => {
+                "FULLTEXT_INCLUDE_ENTITY_MISSING_OR_INCORRECT_ATTRIBUTES"
+            }
+            FulltextIncludedEntityNotFound => "FULLTEXT_INCLUDED_ENTITY_NOT_FOUND",
+            FulltextIncludedFieldMissingRequiredProperty => {
+                "FULLTEXT_INCLUDED_FIELD_MISSING_REQUIRED_PROPERTY"
+            }
+            FulltextIncludedFieldInvalid(..) => "FULLTEXT_INCLUDED_FIELD_INVALID",
+            FulltextIncludedFieldNotString(..) => "FULLTEXT_INCLUDED_FIELD_NOT_STRING",
+            IdFieldMissing(..) => "ID_FIELD_MISSING",
+            IllegalIdType(..) => "ILLEGAL_ID_TYPE",
+            TimestampFieldMissing(..) => "TIMESTAMP_FIELD_MISSING",
+            NonNumericAggregate(..) => "NON_NUMERIC_AGGREGATE",
+            AggregationMissingSource(..) => "AGGREGATION_MISSING_SOURCE",
+            AggregationInvalidSource(..) => "AGGREGATION_INVALID_SOURCE",
+            AggregationMissingIntervals(..) => "AGGREGATION_MISSING_INTERVALS",
+            AggregationWrongIntervals(..) => "AGGREGATION_WRONG_INTERVALS",
+            AggregationInvalidInterval(..) => "AGGREGATION_INVALID_INTERVAL",
+            PointlessAggregation(..) => "POINTLESS_AGGREGATION",
+            AggregationDerivedField(..) => "AGGREGATION_DERIVED_FIELD",
+            TimeseriesNotSupported(..) => "TIMESERIES_NOT_SUPPORTED",
+            MutableTimeseries(..) => "MUTABLE_TIMESERIES",
+            TimeseriesMissingTimestamp(..) => "TIMESERIES_MISSING_TIMESTAMP",
+            InvalidTimestampType(..) => "INVALID_TIMESTAMP_TYPE",
+            AggregationUnknownSource(..) => "AGGREGATION_UNKNOWN_SOURCE",
+            AggregationNonTimeseriesSource(..) => "AGGREGATION_NON_TIMESERIES_SOURCE",
+            AggregationUnknownField(..) => "AGGREGATION_UNKNOWN_FIELD",
+            AggregationNonMatchingType(..) => "AGGREGATION_NON_MATCHING_TYPE",
+            AggregationInvalidArg(..) => "AGGREGATION_INVALID_ARG",
+            AggregationInvalidFn(..) => "AGGREGATION_INVALID_FN",
+            AggregationMissingFn(..) => "AGGREGATION_MISSING_FN",
+            AggregationMissingArg(..) => "AGGREGATION_MISSING_ARG",
+            AggregationUnknownArg(..) => "AGGREGATION_UNKNOWN_ARG",
+            AggregationNonMatchingArg(..) => "AGGREGATION_NON_MATCHING_ARG",
+            AggregationNonNumericArg(..) => "AGGREGATION_NON_NUMERIC_ARG",
+            AggregationsNotSupported(..) => "AGGREGATIONS_NOT_SUPPORTED",
+            IdTypeInt8NotSupported(..) => "ID_TYPE_INT8_NOT_SUPPORTED",
+            SubstreamsUnsupportedFieldType(..) => "SUBSTREAMS_UNSUPPORTED_FIELD_TYPE",
+            ApiVersionTooOld(..) => "API_VERSION_TOO_OLD",
+        }
+    }
+
+    /// The type, and possibly the field of that type, that this error is
+    /// about. Errors about the whole document, like problems with
+    /// `@fulltext` directives, are attributed to the `_Schema_` type
+    fn declaration(&self) -> Option<(&str, Option<&str>)> {
+        use SchemaValidationError::*;
+
+        match self {
+            SchemaParseError(_)
+            | Encoding(_)
+            | SchemaInvalid(_, _)
+            | ApiSchemaInvalid(_)
+            | InterfaceUndefined(_)
+            | EntityDirectiveNonBooleanArgValue(_)
+            | ImportedTypeUndefined(_, _)
+            | IllegalIdType(_)
+            | AggregationsNotSupported(_)
+            | IdTypeInt8NotSupported(_)
+            | ApiVersionTooOld(..) => None,
+            EntityDirectivesMissing(types)
+            | UsageOfReservedTypes(types)
+            | GeneratedApiSchema(types) => types.0.first().map(|name| (name.as_str(), None)),
+            SchemaTypeWithFields
+            | InvalidSchemaTypeDirectives
+            | FulltextNotAllowed
+            | FulltextNameUndefined
+            | FulltextNameConflict(..)
+            | FulltextNameCollision(_)
+            | FulltextLanguageUndefined
+            | FulltextLanguageInvalid(_)
+            | FulltextAlgorithmUndefined
+            | FulltextAlgorithmInvalid(_)
+            | FulltextIncludeInvalid
+            | FulltextIncludeUndefined
+            | FulltextIncludeObjectMissing
+            | FulltextIncludeEntityMissingOrIncorrectAttributes
+            | FulltextIncludedEntityNotFound
+            | FulltextIncludedFieldMissingRequiredProperty
+            | FulltextIncludedFieldInvalid(_) => Some((SCHEMA_TYPE_NAME, None)),
+            InterfaceFieldsMissing(name, _, _)
+            | InterfaceImplementorsMixId(name, _)
+            | IdFieldMissing(name)
+            | TimestampFieldMissing(name)
+            | AggregationMissingSource(name)
+            | AggregationInvalidSource(name)
+            | AggregationMissingIntervals(name)
+            | AggregationWrongIntervals(name)
+            | AggregationInvalidInterval(name, _)
+            | PointlessAggregation(name)
+            | TimeseriesNotSupported(name, _)
+            | MutableTimeseries(name)
+            | TimeseriesMissingTimestamp(name)
+            | InvalidTimestampType(name, _)
+            | OrphanInterface(name)
+            | RenameTypeUndefined(name)
+            | RenameTypeConflict(_, name)
+            | UnknownTypeDirective(name, _)
+            | ConflictingTypeDirective(name, _, _)
+            | UnknownTypeDirectiveArgument(name, _, _)
+            | InvalidTypeName(name)
+            | ReservedTypeName(name, _)
+            | DuplicateTypeName(name, _)
+            | MandatoryReferenceCycle(name, _)
+            | InvalidEnumValue(name, _)
+            | DuplicateEnumValue(name, _)
+            | AggregationUnknownSource(name, _)
+            | AggregationNonTimeseriesSource(name, _) => Some((name.as_str(), None)),
+            InvalidDerivedFrom(name, field, _)
+            | FieldTypeUnknown(name, field, _)
+            | DuplicateField(name, field)
+            | InvalidFieldName(name, field)
+            | InvalidListType(name, field, _)
+            | NullableReferenceListElements(name, field, _, _)
+            | ReservedFieldName(name, field, _)
+            | InterfaceFieldTypeMismatch(name, _, field, _, _)
+            | UnknownFieldDirective(name, field, _)
+            | ConflictingFieldDirective(name, field, _, _)
+            | UnknownFieldDirectiveArgument(name, field, _, _)
+            | FulltextIncludedFieldNotString(name, field, _)
+            | NonNumericAggregate(name, field)
+            | AggregationDerivedField(name, field)
+            | AggregationUnknownField(name, _, field)
+            | AggregationNonMatchingType(name, field, _, _)
+            | AggregationInvalidArg(name, field)
+            | AggregationInvalidFn(name, field, _)
+            | AggregationMissingFn(name, field)
+            | AggregationMissingArg(name, field, _)
+            | AggregationUnknownArg(name, field, _)
+            | AggregationNonMatchingArg(name, field, _, _, _)
+            | AggregationNonNumericArg(name, field, _, _)
+            | SubstreamsUnsupportedFieldType(name, field, _) => {
+                Some((name.as_str(), Some(field.as_str())))
+            }
+            ImmutableList(list) => Some((list.type_name.as_str(), Some(list.field.as_str()))),
+            IdReference(id_ref) => Some((id_ref.type_name.as_str(), Some(id_ref.field.as_str()))),
+            NullableScalarList(list) => Some((list.type_name.as_str(), Some(list.field.as_str()))),
+        }
+    }
+
+    /// Find the position in `document` of the declaration that this error
+    /// is about. That is the position of a field if the error is about a
+    /// specific field, and the position of the type otherwise. Return
+    /// `None` if the error can't be attributed to a declaration
+    pub fn position(&self, document: &s::Document) -> Option<Pos> {
+        let (type_name, field_name) = self.declaration()?;
+        declaration_position(document, type_name, field_name)
+    }
+
+    /// How serious this error is. For `SchemaInvalid`, that is the
+    /// severity of the most serious of the errors it contains
+    pub fn severity(&self) -> Severity {
+        use SchemaValidationError::*;
+
+        match self {
+            SchemaInvalid(_, errors) => errors
+                .0
+                .iter()
+                .map(|error| error.severity())
+                .max()
+                .unwrap_or(Severity::Error),
+            UnknownTypeDirective(..)
+            | UnknownFieldDirective(..)
+            | UnknownTypeDirectiveArgument(..)
+            | UnknownFieldDirectiveArgument(..)
+            | MandatoryReferenceCycle(..)
+            | NullableReferenceListElements(..)
+            | ReservedFieldName(..)
+            | ConflictingTypeDirective(..)
+            | ConflictingFieldDirective(..)
+            | ApiVersionTooOld(..) => Severity::Strict,
+            OrphanInterface(..)
+            | ImmutableList(..)
+            | IdReference(..)
+            | NullableScalarList(..)
+            | ReservedTypeName(..) => Severity::Lint,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Whether graph-node would accept the schema despite this error,
+    /// i.e., whether the error is only reported because of
+    /// `ValidationOptions::strict`
+    pub fn is_recoverable(&self) -> bool {
+        self.severity() != Severity::Error
+    }
+
+    /// The errors contained in this error. For `SchemaInvalid`, these are
+    /// the individual validation errors, for all other errors, it is just
+    /// this error
+    pub fn errors(&self) -> Vec<&SchemaValidationError> {
+        match self {
+            SchemaValidationError::SchemaInvalid(_, errors) => errors.0.iter().collect(),
+            _ => vec![self],
+        }
+    }
+}
+
+/// How serious a `SchemaValidationError` is, from least to most serious
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Only reported with `ValidationOptions::strict`, for something that
+    /// `InputSchema::lint` reports as a warning otherwise
+    Lint,
+    /// Only reported with `ValidationOptions::strict`, for something that
+    /// graph-node accepts but that is likely a mistake
+    Strict,
+    /// graph-node rejects the schema
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Lint => "lint",
+            Severity::Strict => "strict",
+            Severity::Error => "error",
+        }
+    }
 }
 
 /// A validated and preprocessed GraphQL schema for a subgraph.
@@ -200,7 +662,7 @@ impl Schema {
     // fully validated. The code should be changed to make sure that a
     // `Schema` is always fully valid
     pub fn new(id: DeploymentHash, document: s::Document) -> Result<Self, SchemaValidationError> {
-        let (interfaces_for_type, types_for_interface) = Self::collect_interfaces(&document)?;
+        let (interfaces_for_type, types_for_interface) = Self::collect_interfaces(&id, &document)?;
 
         let mut schema = Schema {
             id: id.clone(),
@@ -214,7 +676,11 @@ impl Schema {
         Ok(schema)
     }
 
+    /// Map object types to the interfaces they implement and vice versa.
+    /// If several implementations are invalid, all of their errors are
+    /// reported together in a `SchemaInvalid` error
     fn collect_interfaces(
+        id: &DeploymentHash,
         document: &s::Document,
     ) -> Result<
         (
@@ -233,6 +699,7 @@ impl Schema {
                 _ => None,
             }));
         let mut interfaces_for_type = BTreeMap::<_, Vec<_>>::new();
+        let mut errors = Vec::new();
 
         for object_type in document.get_object_type_definitions() {
             for implemented_interface in &object_type.implements_interfaces {
@@ -251,7 +718,10 @@ impl Schema {
                         SchemaValidationError::InterfaceUndefined(implemented_interface.clone())
                     })?;
 
-                Self::validate_interface_implementation(object_type, &interface_type)?;
+                errors.extend(Self::validate_interface_implementation(
+                    object_type,
+                    &interface_type,
+                ));
 
                 interfaces_for_type
                     .entry(object_type.name.to_owned())
@@ -264,7 +734,14 @@ impl Schema {
             }
         }
 
-        Ok((interfaces_for_type, types_for_interface))
+        match errors.len() {
+            0 => Ok((interfaces_for_type, types_for_interface)),
+            1 => Err(errors.pop().unwrap()),
+            _ => Err(SchemaValidationError::SchemaInvalid(
+                id.to_string(),
+                ValidationErrors(errors),
+            )),
+        }
     }
 
     pub fn parse(raw: &str, id: DeploymentHash) -> Result<Self, Error> {
@@ -331,27 +808,39 @@ impl Schema {
     fn validate_interface_implementation(
         object: &s::ObjectType,
         interface: &s::InterfaceType,
-    ) -> Result<(), SchemaValidationError> {
-        // Check that all fields in the interface exist in the object with same name and type.
+    ) -> Vec<SchemaValidationError> {
+        // Check that all fields in the interface exist in the object with
+        // the same name and exactly the same type. We do not allow
+        // covariant types (e.g., `String!` for `String`) since queries
+        // against the interface read the columns of all implementors as
+        // if they had the interface's type
         let mut missing_fields = vec![];
+        let mut mismatched = vec![];
         for i in &interface.fields {
-            if !object
-                .fields
-                .iter()
-                .any(|o| o.name.eq(&i.name) && o.field_type.eq(&i.field_type))
-            {
-                missing_fields.push(i.to_string().trim().to_owned());
+            match object.fields.iter().find(|o| o.name.eq(&i.name)) {
+                None => missing_fields.push(i.to_string().trim().to_owned()),
+                Some(o) if !o.field_type.eq(&i.field_type) => {
+                    mismatched.push(SchemaValidationError::InterfaceFieldTypeMismatch(
+                        object.name.clone(),
+                        interface.name.clone(),
+                        o.name.clone(),
+                        i.field_type.to_string(),
+                        o.field_type.to_string(),
+                    ))
+                }
+                Some(_) => { /* field matches */ }
             }
         }
+        let mut errors = vec![];
         if !missing_fields.is_empty() {
-            Err(SchemaValidationError::InterfaceFieldsMissing(
+            errors.push(SchemaValidationError::InterfaceFieldsMissing(
                 object.name.clone(),
                 interface.name.clone(),
                 Strings(missing_fields),
-            ))
-        } else {
-            Ok(())
+            ));
         }
+        errors.append(&mut mismatched);
+        errors
     }
 
     fn subgraph_schema_object_type(&self) -> Option<&s::ObjectType> {
@@ -362,6 +851,42 @@ impl Schema {
     }
 }
 
+/// Find the position in `document` of the declaration of the field
+/// `field_name` of `type_name`, or of `type_name` itself if `field_name` is
+/// `None` or the type has no such field
+fn declaration_position(
+    document: &s::Document,
+    type_name: &str,
+    field_name: Option<&str>,
+) -> Option<Pos> {
+    let (type_pos, fields) = document.definitions.iter().find_map(|defn| match defn {
+        s::Definition::TypeDefinition(s::TypeDefinition::Object(t)) if t.name == type_name => {
+            Some((t.position, t.fields.as_slice()))
+        }
+        s::Definition::TypeDefinition(s::TypeDefinition::Interface(t)) if t.name == type_name => {
+            Some((t.position, t.fields.as_slice()))
+        }
+        s::Definition::TypeDefinition(s::TypeDefinition::Enum(t)) if t.name == type_name => {
+            Some((t.position, &[][..]))
+        }
+        s::Definition::TypeDefinition(s::TypeDefinition::Scalar(t)) if t.name == type_name => {
+            Some((t.position, &[][..]))
+        }
+        _ => None,
+    })?;
+
+    // Use the last field with a matching name so that duplicate fields
+    // are reported where the duplicate is declared
+    let field_pos = field_name.and_then(|field_name| {
+        fields
+            .iter()
+            .rev()
+            .find(|field| field.name == field_name)
+            .map(|field| field.position)
+    });
+    Some(field_pos.unwrap_or(type_pos))
+}
+
 #[test]
 fn non_existing_interface() {
     let schema = "type Foo implements Bar @entity { foo: Int }";
@@ -391,7 +916,115 @@ fn invalid_interface_implementation() {
     let res = Schema::parse(schema, DeploymentHash::new("dummy").unwrap());
     assert_eq!(
         res.unwrap_err().to_string(),
-        "Entity type `Bar` does not satisfy interface `Foo` because it is missing \
-         the following fields: x: Int, y: Int",
+        "Validation errors in subgraph `dummy`:\n  \
+         (1) - Entity type `Bar` does not satisfy interface `Foo` because it is missing \
+         the following fields: y: Int\n  \
+         (2) - Entity type `Bar` does not satisfy interface `Foo` because its field `x` has \
+         type `Boolean` but the interface declares it as `Int`",
+    );
+
+    // All mismatched fields are reported
+    let schema = "
+        interface Foo { x: Int, y: Int, z: Int }
+        type Bar implements Foo @entity { x: Int!, y: String, z: Int }
+    ";
+    let res = Schema::parse(schema, DeploymentHash::new("dummy").unwrap());
+    let error = res
+        .unwrap_err()
+        .downcast::<SchemaValidationError>()
+        .unwrap();
+    let mismatch = |field: &str, expected: &str, actual: &str| {
+        SchemaValidationError::InterfaceFieldTypeMismatch(
+            "Bar".to_owned(),
+            "Foo".to_owned(),
+            field.to_owned(),
+            expected.to_owned(),
+            actual.to_owned(),
+        )
+    };
+    assert_eq!(
+        SchemaValidationError::SchemaInvalid(
+            "dummy".to_owned(),
+            ValidationErrors(vec![
+                mismatch("x", "Int", "Int!"),
+                mismatch("y", "Int", "String")
+            ])
+        ),
+        error
     );
 }
+
+#[test]
+fn error_positions() {
+    use crate::data::subgraph::LATEST_VERSION;
+
+    let schema = "type Thing @entity {
+  id: ID!
+  name: String
+  name: String
+  other: Other
+}
+
+type Empty @entity {
+  name: String
+}";
+    let document = graphql_parser::parse_schema(schema).unwrap().into_static();
+    let err = InputSchema::validate(
+        LATEST_VERSION,
+        schema,
+        DeploymentHash::new("dummy").unwrap(),
+        false,
+        &ValidationOptions::default(),
+    )
+    .unwrap_err();
+
+    let positions: Vec<_> = err
+        .errors()
+        .iter()
+        .map(|e| e.position(&document).map(|pos| (pos.line, pos.column)))
+        .collect();
+    // The missing `id` in `Empty`, the duplicate `name`, and the unknown
+    // type of `other`
+    assert_eq!(vec![Some((8, 1)), Some((4, 3)), Some((5, 3))], positions);
+
+    let codes: Vec<_> = err.errors().iter().map(|e| e.code()).collect();
+    assert_eq!(
+        vec!["ID_FIELD_MISSING", "DUPLICATE_FIELD", "FIELD_TYPE_UNKNOWN"],
+        codes
+    );
+}
+
+#[test]
+fn error_severity() {
+    use crate::data::subgraph::LATEST_VERSION;
+
+    let validate = |schema: &str| {
+        let options = ValidationOptions {
+            strict: true,
+            ..ValidationOptions::default()
+        };
+        InputSchema::validate(
+            LATEST_VERSION,
+            schema,
+            DeploymentHash::new("dummy").unwrap(),
+            false,
+            &options,
+        )
+        .unwrap_err()
+    };
+
+    // Only strict mode rejects unused interfaces and unknown directives
+    let err = validate("interface Named { id: ID! } type Thing @entity { id: ID! }");
+    assert_eq!(Severity::Lint, err.severity());
+    assert!(err.is_recoverable());
+    let err = validate("type Thing @entity @cached { id: ID! }");
+    assert_eq!(Severity::Strict, err.severity());
+    assert!(err.is_recoverable());
+
+    // A hard error makes the whole schema unrecoverable
+    let err = validate("type Thing @entity @cached { name: String }");
+    let severities: Vec<_> = err.errors().iter().map(|e| e.severity()).collect();
+    assert!(severities.contains(&Severity::Strict));
+    assert_eq!(Severity::Error, err.severity());
+    assert!(!err.is_recoverable());
+}
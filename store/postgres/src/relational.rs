@@ -248,9 +248,7 @@ impl Layout {
                     schema,
                     entity_type,
                     &catalog,
-                    schema
-                        .entity_fulltext_definitions(entity_type.as_str())
-                        .map_err(|_| StoreError::FulltextSearchNonDeterministic)?,
+                    schema.entity_fulltext_definitions(entity_type.as_str()),
                     i as u32,
                     catalog.entities_with_causality_region.contains(entity_type),
                 )